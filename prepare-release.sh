@@ -17,7 +17,7 @@ VERSION="$1"
 MINOR="$( echo ${VERSION} | cut -d\. -f1-2 )"
 
 VERSION_MATCHER="([a-z0-9\\.-]+)"
-PROST_CRATE_MATCHER="(prost|prost-[a-z]+)"
+PROST_CRATE_MATCHER="(prost|prost-[a-z-]+)"
 
 # Update the README.md.
 sed -i -E "s/version = \"${VERSION_MATCHER}\"/version = \"${VERSION}\"/" "$DIR/README.md"
@@ -29,6 +29,7 @@ sed -i -E "s/${PROST_CRATE_MATCHER} = \"${VERSION_MATCHER}\"/\1 = \"${MINOR}\"/"
 sed -i -E "s~html_root_url = \"https://docs\.rs/${PROST_CRATE_MATCHER}/$VERSION_MATCHER\"~html_root_url = \"https://docs.rs/\1/${VERSION}\"~" \
   "$DIR/prost/src/lib.rs" \
   "$DIR/prost-derive/src/lib.rs" \
+  "$DIR/prost-derive-impl/src/lib.rs" \
   "$DIR/prost-build/src/lib.rs" \
   "$DIR/prost-types/src/lib.rs"
 
@@ -40,6 +41,7 @@ sed -i -E "s/^version = \"${VERSION_MATCHER}\"$/version = \"${VERSION}\"/" \
 sed -i -E "s/^${PROST_CRATE_MATCHER} = \{ version = \"${VERSION_MATCHER}\"/\1 = { version = \"${VERSION}\"/" \
   "$DIR/prost/Cargo.toml" \
   "$DIR/prost-derive/Cargo.toml" \
+  "$DIR/prost-derive-impl/Cargo.toml" \
   "$DIR/prost-build/Cargo.toml" \
   "$DIR/prost-types/Cargo.toml"
 
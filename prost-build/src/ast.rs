@@ -1,9 +1,16 @@
+use std::collections::HashMap;
+
 use once_cell::sync::Lazy;
 use prost_types::source_code_info::Location;
+use prost_types::{DescriptorProto, FileDescriptorProto};
 #[cfg(feature = "cleanup-markdown")]
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag};
 use regex::Regex;
 
+use crate::code_generator::resolve_ident;
+use crate::extern_paths::ExternPaths;
+use crate::message_graph::MessageGraph;
+
 /// Comments on a Protobuf item.
 #[derive(Debug, Default, Clone)]
 pub struct Comments {
@@ -103,7 +110,7 @@ impl Comments {
         let mut chars = sanitized_line.chars();
         chars
             .next()
-            .map_or(false, |c| c != ' ' || chars.next() == Some(' '))
+            .is_some_and(|c| c != ' ' || chars.next() == Some(' '))
     }
 
     /// Sanitizes the line for rustdoc by performing the following operations:
@@ -165,6 +172,65 @@ pub struct Method {
     pub server_streaming: bool,
 }
 
+/// The context of a service passed to a [`ServiceGenerator`](crate::ServiceGenerator).
+///
+/// Gives access to the file the service is defined in, the other services of the file and the
+/// descriptors of all messages being compiled.
+pub struct ServiceContext<'a> {
+    pub(crate) file: &'a FileDescriptorProto,
+    pub(crate) services: &'a [Service],
+    pub(crate) message_graph: &'a MessageGraph,
+    pub(crate) extern_paths: &'a ExternPaths,
+    pub(crate) renames: &'a HashMap<String, String>,
+    pub(crate) prost_path: &'a str,
+}
+
+impl ServiceContext<'_> {
+    /// Returns the descriptor of the `.proto` file defining the service.
+    pub fn file(&self) -> &FileDescriptorProto {
+        self.file
+    }
+
+    /// Returns all services defined in the `.proto` file, in the order they are generated.
+    pub fn services(&self) -> &[Service] {
+        self.services
+    }
+
+    /// Returns the descriptor of a message, such as the input or output type of a method.
+    ///
+    /// `proto_type` is the fully qualified Protobuf name of the message, as in
+    /// [`Method::input_proto_type`]. Only messages of the `.proto` files being compiled and their
+    /// imports are available.
+    pub fn message(&self, proto_type: &str) -> Option<&DescriptorProto> {
+        self.message_graph.get_message(proto_type)
+    }
+
+    /// Returns the Rust path of a Protobuf type configured with
+    /// [`Config::extern_path`](crate::Config::extern_path), or `None` if the type is generated.
+    pub fn resolve_extern(&self, proto_type: &str) -> Option<String> {
+        self.extern_paths.resolve_ident(proto_type)
+    }
+
+    /// Returns the Rust path of a Protobuf type, relative to the module of the service.
+    ///
+    /// Extern paths and renamed types are taken into account, as in [`Method::input_type`].
+    pub fn resolve_ident(&self, proto_type: &str) -> String {
+        resolve_ident(
+            self.extern_paths,
+            self.renames,
+            self.file.package(),
+            &[],
+            proto_type,
+        )
+    }
+
+    /// Returns the path of the `prost` crate, as configured with
+    /// [`Config::prost_path`](crate::Config::prost_path).
+    pub fn prost_path(&self) -> &str {
+        self.prost_path
+    }
+}
+
 #[cfg(not(feature = "cleanup-markdown"))]
 fn get_lines<S>(comments: S) -> Vec<String>
 where
@@ -0,0 +1,193 @@
+use crate::ast::{Method, Service, ServiceContext};
+use crate::ident::to_upper_snake;
+use crate::ServiceGenerator;
+
+/// A transport-agnostic service generator.
+///
+/// For every service, `AsyncServiceGenerator` generates:
+///
+///  * A trait with an async method for every method of the service, which returns a boxed
+///    future. The trait has an associated `Error` type for the errors of the methods.
+///  * A provided `dispatch` method on the trait, which decodes an encoded request for a method
+///    given by name, calls the method and encodes its response. Methods are named as in the
+///    `.proto` file, and an unknown method, an invalid request or an error of the method is
+///    returned as a [`prost::DispatchError`][1].
+///  * `<SERVICE>_SERVICE_NAME` and `<SERVICE>_METHODS` constants with the fully qualified name of
+///    the service and the names of its methods.
+///
+/// This gives transports like message buses or HTTP servers, which deliver a method name and a
+/// request body, a way to call a service implementation without an RPC framework. Streaming
+/// methods are not supported and are left out of the generated trait.
+///
+/// The generated code only depends on `core` and `prost`, and does not depend on an async
+/// runtime.
+///
+/// # Example
+///
+/// With a `Greeter` service with a `SayHello` method, the generated trait can be implemented and
+/// called like this:
+///
+/// ```rust,ignore
+/// struct MyGreeter;
+///
+/// impl Greeter for MyGreeter {
+///     type Error = std::convert::Infallible;
+///
+///     fn say_hello(
+///         &self,
+///         request: HelloRequest,
+///     ) -> Pin<Box<dyn Future<Output = Result<HelloReply, Self::Error>> + Send + '_>> {
+///         Box::pin(async move {
+///             Ok(HelloReply {
+///                 message: format!("Hello {}!", request.name),
+///             })
+///         })
+///     }
+/// }
+///
+/// let response: Vec<u8> = MyGreeter.dispatch("SayHello", &request).await?;
+/// ```
+///
+/// [1]: https://docs.rs/prost/latest/prost/enum.DispatchError.html
+#[derive(Debug, Default)]
+pub struct AsyncServiceGenerator {
+    _private: (),
+}
+
+impl AsyncServiceGenerator {
+    /// Creates a new `AsyncServiceGenerator`.
+    pub fn new() -> AsyncServiceGenerator {
+        AsyncServiceGenerator::default()
+    }
+
+    fn append_service(&self, service: &Service, prost_path: &str, buf: &mut String) {
+        let methods: Vec<&Method> = service
+            .methods
+            .iter()
+            .filter(|method| !method.client_streaming && !method.server_streaming)
+            .collect();
+
+        let constant = to_upper_snake(&service.proto_name);
+        buf.push_str(&format!(
+            "/// The fully qualified name of the `{}` service.\n",
+            service.proto_name
+        ));
+        buf.push_str(&format!(
+            "pub const {}_SERVICE_NAME: &str = \"{}{}{}\";\n",
+            constant,
+            service.package,
+            if service.package.is_empty() { "" } else { "." },
+            service.proto_name
+        ));
+        buf.push_str(&format!(
+            "/// The names of the methods of the `{}` service, which can be dispatched.\n",
+            service.proto_name
+        ));
+        buf.push_str(&format!(
+            "pub const {}_METHODS: &[&str] = &[{}];\n",
+            constant,
+            methods
+                .iter()
+                .map(|method| format!("\"{}\"", method.proto_name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+
+        service.comments.append_with_indent(0, buf);
+        buf.push_str(&format!(
+            "pub trait {}: ::core::marker::Send + ::core::marker::Sync {{\n",
+            service.name
+        ));
+        buf.push_str("    /// The error returned by the methods of the service.\n");
+        buf.push_str("    type Error;\n");
+
+        for method in &methods {
+            method.comments.append_with_indent(1, buf);
+            buf.push_str(&format!(
+                "    fn {}(&self, request: {}) -> {};\n",
+                method.name,
+                method.input_type,
+                boxed_future(
+                    &format!(
+                        "::core::result::Result<{}, Self::Error>",
+                        method.output_type
+                    ),
+                    "'_",
+                    prost_path
+                )
+            ));
+        }
+
+        buf.push_str(
+            "    /// Decodes a request for the method named `method`, calls the method and encodes its response.\n",
+        );
+        buf.push_str("    ///\n");
+        buf.push_str("    /// Methods are named as in the `.proto` file.\n");
+        buf.push_str(&format!(
+            "    fn dispatch<'a>(&'a self, method: &'a str, request: &'a [u8]) -> {} {{\n",
+            boxed_future(
+                &format!(
+                    "::core::result::Result<{0}::alloc::vec::Vec<u8>, {0}::DispatchError<Self::Error>>",
+                    prost_path
+                ),
+                "'a",
+                prost_path
+            )
+        ));
+        buf.push_str(&format!(
+            "        {}::alloc::boxed::Box::pin(async move {{\n",
+            prost_path
+        ));
+        buf.push_str("            match method {\n");
+        for method in &methods {
+            buf.push_str(&format!(
+                "                \"{}\" => {{\n",
+                method.proto_name
+            ));
+            buf.push_str(&format!(
+                "                    let request = <{} as {}::Message>::decode(request).map_err({}::DispatchError::Decode)?;\n",
+                method.input_type, prost_path, prost_path
+            ));
+            buf.push_str(&format!(
+                "                    let response = self.{}(request).await.map_err({}::DispatchError::Service)?;\n",
+                method.name, prost_path
+            ));
+            buf.push_str(&format!(
+                "                    ::core::result::Result::Ok({}::Message::encode_to_vec(&response))\n",
+                prost_path
+            ));
+            buf.push_str("                }\n");
+        }
+        buf.push_str(&format!(
+            "                _ => ::core::result::Result::Err({}::DispatchError::UnknownMethod(method.into())),\n",
+            prost_path
+        ));
+        buf.push_str("            }\n");
+        buf.push_str("        })\n");
+        buf.push_str("    }\n");
+        buf.push_str("}\n");
+    }
+}
+
+/// Returns the type of a boxed `Send` future with the given output and lifetime.
+fn boxed_future(output: &str, lifetime: &str, prost_path: &str) -> String {
+    format!(
+        "::core::pin::Pin<{}::alloc::boxed::Box<dyn ::core::future::Future<Output = {}> + ::core::marker::Send + {}>>",
+        prost_path, output, lifetime
+    )
+}
+
+impl ServiceGenerator for AsyncServiceGenerator {
+    fn generate(&mut self, service: Service, buf: &mut String) {
+        self.append_service(&service, "::prost", buf);
+    }
+
+    fn generate_with_context(
+        &mut self,
+        service: Service,
+        context: &ServiceContext<'_>,
+        buf: &mut String,
+    ) {
+        self.append_service(&service, context.prost_path(), buf);
+    }
+}
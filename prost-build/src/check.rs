@@ -0,0 +1,221 @@
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use log::trace;
+use similar::TextDiff;
+
+use crate::config::write_file_if_changed;
+
+/// The first line of every file written by `prost-build`.
+const GENERATED_HEADER: &str = "// This file is @generated by prost-build.";
+
+/// The error returned by [`Config::check_only`][1] when generated files on disk are out of date.
+///
+/// The error is wrapped in the [`std::io::Error`] returned by `compile_protos` and
+/// `compile_fds`, and can be retrieved with [`std::io::Error::get_ref`] and `downcast_ref`.
+///
+/// [1]: crate::Config::check_only
+#[derive(Debug, Default)]
+pub struct CheckError {
+    stale: Vec<StaleFile>,
+    missing: Vec<PathBuf>,
+    extra: Vec<PathBuf>,
+}
+
+impl CheckError {
+    /// The files whose contents differ from the generated code.
+    pub fn stale(&self) -> &[StaleFile] {
+        &self.stale
+    }
+
+    /// The generated files which do not exist on disk.
+    pub fn missing(&self) -> &[PathBuf] {
+        &self.missing
+    }
+
+    /// The files on disk which were written by `prost-build`, but are no longer generated.
+    ///
+    /// The output directory is searched for extra files, except for the paths passed to
+    /// [`Config::check_only_ignore`](crate::Config::check_only_ignore).
+    pub fn extra(&self) -> &[PathBuf] {
+        &self.extra
+    }
+
+    fn is_empty(&self) -> bool {
+        self.stale.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("generated files are out of date")?;
+        for file in &self.stale {
+            write!(f, "\nstale: {}", file.path.display())?;
+        }
+        for path in &self.missing {
+            write!(f, "\nmissing: {}", path.display())?;
+        }
+        for path in &self.extra {
+            write!(f, "\nextra: {}", path.display())?;
+        }
+        for file in &self.stale {
+            write!(f, "\n\n{}", file.diff)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for CheckError {}
+
+/// A generated file whose contents on disk are out of date.
+#[derive(Debug)]
+pub struct StaleFile {
+    path: PathBuf,
+    diff: String,
+}
+
+impl StaleFile {
+    fn new(path: &Path, previous_content: &[u8], content: &[u8]) -> StaleFile {
+        let previous_content = String::from_utf8_lossy(previous_content);
+        let content = String::from_utf8_lossy(content);
+        let name = path.display().to_string();
+        let diff = TextDiff::from_lines(&previous_content, &content)
+            .unified_diff()
+            .header(&name, &name)
+            .to_string();
+        StaleFile {
+            path: path.to_path_buf(),
+            diff,
+        }
+    }
+
+    /// The path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A unified diff from the contents on disk to the generated code.
+    pub fn diff(&self) -> &str {
+        &self.diff
+    }
+}
+
+/// The destination of generated files, which are either written, or compared to the files on
+/// disk in check mode.
+pub(crate) struct Output {
+    check: Option<Check>,
+}
+
+struct Check {
+    written: HashSet<PathBuf>,
+    error: CheckError,
+}
+
+impl Output {
+    pub(crate) fn new(check_only: bool) -> Output {
+        Output {
+            check: check_only.then(|| Check {
+                written: HashSet::new(),
+                error: CheckError::default(),
+            }),
+        }
+    }
+
+    /// Creates a directory and its parents, unless in check mode.
+    pub(crate) fn create_dir_all(&self, path: &Path) -> Result<()> {
+        match self.check {
+            Some(_) => Ok(()),
+            None => fs::create_dir_all(path),
+        }
+    }
+
+    /// Writes `content` to the file at `path`, unless the file already has this content.
+    ///
+    /// In check mode, the file is compared to `content` instead.
+    pub(crate) fn write(&mut self, path: &Path, content: &[u8]) -> Result<()> {
+        let check = match &mut self.check {
+            Some(check) => check,
+            None => return write_file_if_changed(path, content),
+        };
+
+        check.written.insert(path.to_path_buf());
+        match fs::read(path) {
+            Ok(previous_content) if previous_content == content => {
+                trace!("up to date: {}", path.display());
+            }
+            Ok(previous_content) => {
+                trace!("stale: {}", path.display());
+                check
+                    .error
+                    .stale
+                    .push(StaleFile::new(path, &previous_content, content));
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                trace!("missing: {}", path.display());
+                check.error.missing.push(path.to_path_buf());
+            }
+            Err(error) => return Err(error),
+        }
+        Ok(())
+    }
+
+    /// Finishes the output to `target`.
+    ///
+    /// In check mode, this looks for files in `target` which were written by `prost-build` but
+    /// not generated this time, except for the `ignored` paths relative to `target`, and returns
+    /// a [`CheckError`] if any file is out of date.
+    pub(crate) fn finish(self, target: &Path, ignored: &[PathBuf]) -> Result<()> {
+        let mut check = match self.check {
+            Some(check) => check,
+            None => return Ok(()),
+        };
+
+        let ignored = ignored
+            .iter()
+            .map(|path| target.join(path))
+            .collect::<Vec<_>>();
+        find_extra_files(target, &check.written, &ignored, &mut check.error.extra)?;
+        check.error.stale.sort_by(|a, b| a.path.cmp(&b.path));
+        check.error.missing.sort();
+        check.error.extra.sort();
+
+        if check.error.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::Other, check.error))
+        }
+    }
+}
+
+fn find_extra_files(
+    dir: &Path,
+    written: &HashSet<PathBuf>,
+    ignored: &[PathBuf],
+    extra: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        if ignored.iter().any(|ignored| path.starts_with(ignored)) {
+            continue;
+        } else if path.is_dir() {
+            find_extra_files(&path, written, ignored, extra)?;
+        } else if path.extension().is_some_and(|extension| extension == "rs")
+            && !written.contains(&path)
+            && fs::read_to_string(&path).is_ok_and(|content| content.starts_with(GENERATED_HEADER))
+        {
+            trace!("extra: {}", path.display());
+            extra.push(path);
+        }
+    }
+    Ok(())
+}
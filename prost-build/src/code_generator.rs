@@ -6,7 +6,9 @@ use std::iter;
 use itertools::{Either, Itertools};
 use log::debug;
 use multimap::MultiMap;
+use prost::Message;
 use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::file_options::OptimizeMode;
 use prost_types::source_code_info::Location;
 use prost_types::{
     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
@@ -14,11 +16,13 @@ use prost_types::{
     SourceCodeInfo,
 };
 
-use crate::ast::{Comments, Method, Service};
+use crate::ast::{Comments, Method, Service, ServiceContext};
 use crate::extern_paths::ExternPaths;
-use crate::ident::{strip_enum_prefix, to_snake, to_upper_camel};
+use crate::ident::{strip_enum_prefix, to_json_name, to_snake, to_upper_camel, to_upper_snake};
 use crate::message_graph::MessageGraph;
-use crate::Config;
+use crate::{CodeGeneratorHook, Config, HookContext, HookOutput};
+
+mod builder;
 
 mod c_escaping;
 use c_escaping::unescape_c_escape_string;
@@ -32,6 +36,7 @@ pub struct CodeGenerator<'a> {
     type_path: Vec<String>,
     source_info: Option<SourceCodeInfo>,
     syntax: Syntax,
+    optimize_for_code_size: bool,
     message_graph: &'a MessageGraph,
     extern_paths: &'a ExternPaths,
     depth: u8,
@@ -95,6 +100,9 @@ impl<'a> CodeGenerator<'a> {
         file: FileDescriptorProto,
         buf: &mut String,
     ) {
+        // Service generators get the unmodified file descriptor as context.
+        let file_descriptor = config.service_generator.as_ref().map(|_| file.clone());
+
         let source_info = file.source_code_info.map(|mut s| {
             s.location.retain(|loc| {
                 let len = loc.path.len();
@@ -110,6 +118,10 @@ impl<'a> CodeGenerator<'a> {
             type_path: Vec::new(),
             source_info,
             syntax: file.syntax.as_deref().into(),
+            optimize_for_code_size: file
+                .options
+                .as_ref()
+                .is_some_and(|options| options.optimize_for() == OptimizeMode::CodeSize),
             message_graph,
             extern_paths,
             depth: 0,
@@ -139,19 +151,43 @@ impl<'a> CodeGenerator<'a> {
         }
         code_gen.path.pop();
 
-        if code_gen.config.service_generator.is_some() {
+        if let Some(file_descriptor) = file_descriptor {
             code_gen.path.push(6);
-            for (idx, service) in file.service.into_iter().enumerate() {
-                code_gen.path.push(idx as i32);
-                code_gen.push_service(service);
-                code_gen.path.pop();
-            }
+            let services: Vec<Service> = file
+                .service
+                .into_iter()
+                .enumerate()
+                .filter_map(|(idx, service)| {
+                    if !code_gen.is_generated(&code_gen.fq_name(service.name())) {
+                        return None;
+                    }
+                    code_gen.path.push(idx as i32);
+                    let service = code_gen.resolve_service(service);
+                    code_gen.path.pop();
+                    Some(service)
+                })
+                .collect();
+            code_gen.path.pop();
 
+            let prost_path = prost_path(code_gen.config).to_string();
+            let context = ServiceContext {
+                file: &file_descriptor,
+                services: &services,
+                message_graph,
+                extern_paths,
+                renames: &code_gen.config.renames,
+                prost_path: &prost_path,
+            };
             if let Some(service_generator) = code_gen.config.service_generator.as_mut() {
+                for service in &services {
+                    service_generator.generate_with_context(
+                        service.clone(),
+                        &context,
+                        code_gen.buf,
+                    );
+                }
                 service_generator.finalize(code_gen.buf);
             }
-
-            code_gen.path.pop();
         }
     }
 
@@ -161,11 +197,23 @@ impl<'a> CodeGenerator<'a> {
         let message_name = message.name().to_string();
         let fq_message_name = self.fq_name(&message_name);
 
-        // Skip external types.
-        if self.extern_paths.resolve_ident(&fq_message_name).is_some() {
+        // Skip external types, skipped types, and types which are not reachable from
+        // `generate_only` roots.
+        if self.extern_paths.resolve_ident(&fq_message_name).is_some()
+            || !self.is_generated(&fq_message_name)
+        {
             return;
         }
 
+        let message_ident = self.type_ident(&fq_message_name, &message_name);
+        let hook_output = self.call_hook(
+            &message,
+            message.options.as_ref(),
+            &fq_message_name,
+            &message_ident,
+            |hook, context, output| hook.message(context, output),
+        );
+
         // Split the nested message types into a vector of normal nested message types, and a map
         // of the map field entry types. The path index of the nested message types is preserved so
         // that comments can be retrieved.
@@ -224,9 +272,26 @@ impl<'a> CodeGenerator<'a> {
             })
             .collect();
 
+        // Leave out skipped fields and oneofs, and fields of skipped types.
+        let fields: Vec<Field> = fields
+            .into_iter()
+            .filter(|field| !self.is_field_skipped(&fq_message_name, field, &map_types))
+            .collect();
+        let oneof_fields: Vec<OneofField> = oneof_fields
+            .into_iter()
+            .filter_map(|mut oneof| {
+                let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
+                oneof
+                    .fields
+                    .retain(|field| !self.is_field_skipped(&oneof_name, field, &map_types));
+                (!oneof.fields.is_empty()).then_some(oneof)
+            })
+            .collect();
+
         self.append_doc(&fq_message_name, None);
         self.append_type_attributes(&fq_message_name);
         self.append_message_attributes(&fq_message_name);
+        self.append_hook_lines(&hook_output.attributes);
         self.push_indent();
         self.buf.push_str(&format!(
             "#[derive(Clone, {}PartialEq, {}::Message)]\n",
@@ -238,11 +303,15 @@ impl<'a> CodeGenerator<'a> {
             prost_path(self.config)
         ));
         self.append_skip_debug(&fq_message_name);
+        self.append_message_meta(&fq_message_name);
+        self.append_max_encoded_len(&fq_message_name);
+        self.append_table_driven(&fq_message_name);
         self.push_indent();
         self.buf.push_str("pub struct ");
-        self.buf.push_str(&to_upper_camel(&message_name));
+        self.buf.push_str(&message_ident);
         self.buf.push_str(" {\n");
 
+        let mut hook_items = hook_output.items;
         self.depth += 1;
         self.path.push(2);
         for field in &fields {
@@ -253,8 +322,10 @@ impl<'a> CodeGenerator<'a> {
                 .as_ref()
                 .and_then(|type_name| map_types.get(type_name))
             {
-                Some((key, value)) => self.append_map_field(&fq_message_name, field, key, value),
-                None => self.append_field(&fq_message_name, field),
+                Some((key, value)) => {
+                    self.append_map_field(&fq_message_name, field, key, value, &mut hook_items)
+                }
+                None => self.append_field(&fq_message_name, field, &mut hook_items),
             }
             self.path.pop();
         }
@@ -271,9 +342,21 @@ impl<'a> CodeGenerator<'a> {
         self.depth -= 1;
         self.push_indent();
         self.buf.push_str("}\n");
-
-        if !message.enum_type.is_empty() || !nested_types.is_empty() || !oneof_fields.is_empty() {
-            self.push_mod(&message_name);
+        self.append_hook_lines(&hook_items);
+
+        let is_nested_generated =
+            |name: &str| self.is_generated(&format!("{}.{}", fq_message_name, name));
+        if message
+            .enum_type
+            .iter()
+            .any(|nested_enum| is_nested_generated(nested_enum.name()))
+            || nested_types
+                .iter()
+                .any(|(nested_type, _)| is_nested_generated(nested_type.name()))
+            || !oneof_fields.is_empty()
+        {
+            let module = self.module_ident(&fq_message_name, &message_name);
+            self.push_mod(&message_name, &module);
             self.path.push(3);
             for (nested_type, idx) in nested_types {
                 self.path.push(idx as i32);
@@ -297,16 +380,110 @@ impl<'a> CodeGenerator<'a> {
             self.pop_mod();
         }
 
+        if self.config.builders.get_first(&fq_message_name).is_some() {
+            self.append_builder(
+                &message_name,
+                &fq_message_name,
+                &fields,
+                &oneof_fields,
+                &map_types,
+            );
+        }
+
+        if self.config.enable_field_constants {
+            self.append_field_constants(&fq_message_name, &message_ident, &fields, &oneof_fields);
+        }
+
         if self.config.enable_type_names {
-            self.append_type_name(&message_name, &fq_message_name);
+            self.append_type_name(&message_name, &message_ident, &fq_message_name);
         }
     }
 
-    fn append_type_name(&mut self, message_name: &str, fq_message_name: &str) {
+    fn append_field_constants(
+        &mut self,
+        fq_message_name: &str,
+        message_ident: &str,
+        fields: &[Field],
+        oneof_fields: &[OneofField],
+    ) {
+        // Oneof variants are identified by their variant name, other fields by their field name.
+        let all_fields = fields
+            .iter()
+            .map(|field| (field, self.field_ident(fq_message_name, field)))
+            .chain(oneof_fields.iter().flat_map(|oneof| {
+                let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
+                oneof
+                    .fields
+                    .iter()
+                    .map(|field| (field, self.oneof_variant_ident(&oneof_name, field)))
+                    .collect::<Vec<_>>()
+            }))
+            .sorted_by_key(|(field, _)| field.path_index)
+            .collect::<Vec<_>>();
+
+        self.push_indent();
+        self.buf.push_str(&format!("impl {} {{\n", message_ident));
+        self.depth += 1;
+        for (field, _) in &all_fields {
+            let name = field.descriptor.name();
+            let const_prefix = to_upper_snake(name);
+
+            self.push_indent();
+            self.buf
+                .push_str(&format!("/// Field number of the `{}` field.\n", name));
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "pub const {}_FIELD_NUMBER: u32 = {};\n",
+                const_prefix,
+                field.descriptor.number()
+            ));
+            self.push_indent();
+            self.buf
+                .push_str(&format!("/// Name of the `{}` field.\n", name));
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "pub const {}_FIELD_NAME: &'static str = {:?};\n",
+                const_prefix, name
+            ));
+        }
+
+        let prost_path = prost_path(self.config).to_string();
+        self.push_indent();
+        self.buf.push_str("/// Number and names of all fields.\n");
+        self.push_indent();
+        self.buf.push_str(&format!(
+            "pub const FIELDS: &'static [{}::FieldInfo] = &[\n",
+            prost_path
+        ));
+        self.depth += 1;
+        for (field, rust_ident) in &all_fields {
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "{}::FieldInfo {{ number: {}, name: {:?}, json_name: {:?}, rust_ident: {:?} }},\n",
+                prost_path,
+                field.descriptor.number(),
+                field.descriptor.name(),
+                field
+                    .descriptor
+                    .json_name
+                    .clone()
+                    .unwrap_or_else(|| to_json_name(field.descriptor.name())),
+                rust_ident
+            ));
+        }
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("];\n");
+        self.depth -= 1;
+        self.push_indent();
+        self.buf.push_str("}\n");
+    }
+
+    fn append_type_name(&mut self, message_name: &str, message_ident: &str, fq_message_name: &str) {
         self.buf.push_str(&format!(
             "impl {}::Name for {} {{\n",
             self.config.prost_path.as_deref().unwrap_or("::prost"),
-            to_upper_camel(message_name)
+            message_ident
         ));
         self.depth += 1;
 
@@ -378,6 +555,71 @@ impl<'a> CodeGenerator<'a> {
         }
     }
 
+    fn should_derive_meta(&self, fq_message_name: &str) -> bool {
+        assert_eq!(b'.', fq_message_name.as_bytes()[0]);
+        self.config
+            .message_meta
+            .get(fq_message_name)
+            .next()
+            .is_some()
+    }
+
+    fn append_message_meta(&mut self, fq_message_name: &str) {
+        if self.should_derive_meta(fq_message_name) {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str("#[prost(meta)]");
+            self.buf.push('\n');
+        }
+    }
+
+    fn append_max_encoded_len(&mut self, fq_message_name: &str) {
+        assert_eq!(b'.', fq_message_name.as_bytes()[0]);
+        if self
+            .config
+            .max_encoded_len
+            .get(fq_message_name)
+            .next()
+            .is_some()
+        {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str("#[prost(max_encoded_len)]");
+            self.buf.push('\n');
+        }
+    }
+
+    /// Returns the `name` item of the `prost` attribute of a field, if the message implements
+    /// `MessageMeta` and the Protobuf name of the field is not the one inferred by the derive.
+    ///
+    /// `ident` is the Rust field, or `None` for a oneof variant, whose name is always added since
+    /// the name inferred from the variant is a guess.
+    fn meta_name(&self, fq_message_name: &str, name: &str, ident: Option<&str>) -> String {
+        let inferred = match ident {
+            Some(ident) => ident.trim_start_matches("r#") == name,
+            None => false,
+        };
+        if self.should_derive_meta(fq_message_name) && !inferred {
+            format!(", name=\"{}\"", name)
+        } else {
+            String::new()
+        }
+    }
+
+    fn append_table_driven(&mut self, fq_message_name: &str) {
+        assert_eq!(b'.', fq_message_name.as_bytes()[0]);
+        if self.optimize_for_code_size
+            || self
+                .config
+                .table_driven
+                .get(fq_message_name)
+                .next()
+                .is_some()
+        {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str("#[prost(table_driven)]");
+            self.buf.push('\n');
+        }
+    }
+
     fn append_enum_attributes(&mut self, fq_message_name: &str) {
         assert_eq!(b'.', fq_message_name.as_bytes()[0]);
         for attribute in self.config.enum_attributes.get(fq_message_name) {
@@ -400,20 +642,109 @@ impl<'a> CodeGenerator<'a> {
         }
     }
 
-    fn append_field(&mut self, fq_message_name: &str, field: &Field) {
+    /// Calls the code generator hook, if any, for an element of the schema at the current path.
+    fn call_hook<D, O: Message>(
+        &mut self,
+        descriptor: &D,
+        options: Option<&O>,
+        fq_name: &str,
+        rust_type: &str,
+        call: impl FnOnce(&mut dyn CodeGeneratorHook, &HookContext<'_, D>, &mut HookOutput),
+    ) -> HookOutput {
+        let mut output = HookOutput::default();
+        let mut hook = match self.config.code_generator_hook.take() {
+            Some(hook) => hook,
+            None => return output,
+        };
+
+        let comments = self
+            .location()
+            .map(Comments::from_location)
+            .unwrap_or_default();
+        let encoded_options;
+        let raw_options = match self.config.raw_options.get(fq_name) {
+            Some(raw_options) => raw_options.as_slice(),
+            None => {
+                encoded_options = options.map(Message::encode_to_vec).unwrap_or_default();
+                encoded_options.as_slice()
+            }
+        };
+        let context = HookContext {
+            descriptor,
+            fq_name,
+            rust_type,
+            comments: &comments,
+            raw_options,
+        };
+        call(hook.as_mut(), &context, &mut output);
+
+        self.config.code_generator_hook = Some(hook);
+        output
+    }
+
+    /// Appends the attributes or items returned by the code generator hook, one per line.
+    fn append_hook_lines(&mut self, lines: &[String]) {
+        for line in lines {
+            push_indent(self.buf, self.depth);
+            self.buf.push_str(line);
+            self.buf.push('\n');
+        }
+    }
+
+    fn append_field(&mut self, fq_message_name: &str, field: &Field, hook_items: &mut Vec<String>) {
         let type_ = field.descriptor.r#type();
         let repeated = field.descriptor.label == Some(Label::Repeated as i32);
         let deprecated = self.deprecated(&field.descriptor);
         let optional = self.optional(&field.descriptor);
-        let boxed = self.boxed(&field.descriptor, fq_message_name, None);
-        let ty = self.resolve_type(&field.descriptor, fq_message_name);
+        let shared = self.shared(&field.descriptor, fq_message_name, None);
+        let boxed = !shared && self.boxed(&field.descriptor, fq_message_name, None);
+        let ty = self.resolve_field_type(&field.descriptor, fq_message_name, None);
+        let max_count = if repeated {
+            self.max_count(&field.descriptor, fq_message_name)
+        } else {
+            None
+        };
 
         debug!(
-            "    field: {:?}, type: {:?}, boxed: {}",
+            "    field: {:?}, type: {:?}, boxed: {}, shared: {}",
             field.descriptor.name(),
             ty,
-            boxed
+            boxed,
+            shared
+        );
+
+        let prost_path = prost_path(self.config);
+        let mut field_type = String::new();
+        if max_count.is_some() {
+            field_type.push_str(&format!("{}::heapless::Vec<", prost_path));
+        } else if repeated {
+            field_type.push_str(&format!("{}::alloc::vec::Vec<", prost_path));
+        } else if optional {
+            field_type.push_str("::core::option::Option<");
+        }
+        if boxed {
+            field_type.push_str(&format!("{}::alloc::boxed::Box<", prost_path));
+        } else if shared {
+            field_type.push_str(&format!("{}::alloc::sync::Arc<", prost_path));
+        }
+        field_type.push_str(&ty);
+        if boxed || shared {
+            field_type.push('>');
+        }
+        if let Some(count) = max_count {
+            field_type.push_str(&format!(", {}>", count));
+        } else if repeated || optional {
+            field_type.push('>');
+        }
+
+        let hook_output = self.call_hook(
+            &field.descriptor,
+            field.descriptor.options.as_ref(),
+            &format!("{}.{}", fq_message_name, field.descriptor.name()),
+            &field_type,
+            |hook, context, output| hook.field(context, output),
         );
+        hook_items.extend(hook_output.items);
 
         self.append_doc(fq_message_name, Some(field.descriptor.name()));
 
@@ -462,6 +793,9 @@ impl<'a> CodeGenerator<'a> {
         if boxed {
             self.buf.push_str(", boxed");
         }
+        if shared {
+            self.buf.push_str(", shared");
+        }
         self.buf.push_str(", tag=\"");
         self.buf.push_str(&field.descriptor.number().to_string());
 
@@ -476,52 +810,28 @@ impl<'a> CodeGenerator<'a> {
                 }
                 self.buf.push_str("\\\"");
             } else if type_ == Type::Enum {
-                let mut enum_value = to_upper_camel(default);
-                if self.config.strip_enum_prefix {
-                    // Field types are fully qualified, so we extract
-                    // the last segment and strip it from the left
-                    // side of the default value.
-                    let enum_type = field
-                        .descriptor
-                        .type_name
-                        .as_ref()
-                        .and_then(|ty| ty.split('.').last())
-                        .unwrap();
-
-                    enum_value = strip_enum_prefix(&to_upper_camel(enum_type), &enum_value)
-                }
+                let enum_value = self.enum_variant_ident(field.descriptor.type_name(), default);
                 self.buf.push_str(&enum_value);
             } else {
                 self.buf.push_str(&default.escape_default().to_string());
             }
         }
 
-        self.buf.push_str("\")]\n");
+        let field_ident = self.field_ident(fq_message_name, field);
+        self.buf.push('"');
+        self.buf.push_str(&self.meta_name(
+            fq_message_name,
+            field.descriptor.name(),
+            Some(&field_ident),
+        ));
+        self.buf.push_str(")]\n");
         self.append_field_attributes(fq_message_name, field.descriptor.name());
+        self.append_hook_lines(&hook_output.attributes);
         self.push_indent();
         self.buf.push_str("pub ");
-        self.buf.push_str(&field.rust_name());
+        self.buf.push_str(&field_ident);
         self.buf.push_str(": ");
-
-        let prost_path = prost_path(self.config);
-
-        if repeated {
-            self.buf
-                .push_str(&format!("{}::alloc::vec::Vec<", prost_path));
-        } else if optional {
-            self.buf.push_str("::core::option::Option<");
-        }
-        if boxed {
-            self.buf
-                .push_str(&format!("{}::alloc::boxed::Box<", prost_path));
-        }
-        self.buf.push_str(&ty);
-        if boxed {
-            self.buf.push('>');
-        }
-        if repeated || optional {
-            self.buf.push('>');
-        }
+        self.buf.push_str(&field_type);
         self.buf.push_str(",\n");
     }
 
@@ -531,6 +841,7 @@ impl<'a> CodeGenerator<'a> {
         field: &Field,
         key: &FieldDescriptorProto,
         value: &FieldDescriptorProto,
+        hook_items: &mut Vec<String>,
     ) {
         let key_ty = self.resolve_type(key, fq_message_name);
         let value_ty = self.resolve_type(value, fq_message_name);
@@ -542,34 +853,56 @@ impl<'a> CodeGenerator<'a> {
             value_ty
         );
 
-        self.append_doc(fq_message_name, Some(field.descriptor.name()));
-        self.push_indent();
-
         let map_type = self
             .config
             .map_type
             .get_first_field(fq_message_name, field.descriptor.name())
             .copied()
             .unwrap_or_default();
+        let hasher = self
+            .config
+            .map_hasher
+            .get_first_field(fq_message_name, field.descriptor.name())
+            .filter(|_| map_type.has_hasher())
+            .map(|hasher| format!(", {}", hasher))
+            .unwrap_or_default();
+        let field_type = format!(
+            "{}<{}, {}{}>",
+            map_type.rust_type(),
+            key_ty,
+            value_ty,
+            hasher
+        );
+
+        let hook_output = self.call_hook(
+            &field.descriptor,
+            field.descriptor.options.as_ref(),
+            &format!("{}.{}", fq_message_name, field.descriptor.name()),
+            &field_type,
+            |hook, context, output| hook.field(context, output),
+        );
+        hook_items.extend(hook_output.items);
+
+        self.append_doc(fq_message_name, Some(field.descriptor.name()));
+        self.push_indent();
+
         let key_tag = self.field_type_tag(key);
         let value_tag = self.map_value_type_tag(value);
 
+        let field_ident = self.field_ident(fq_message_name, field);
         self.buf.push_str(&format!(
-            "#[prost({}=\"{}, {}\", tag=\"{}\")]\n",
+            "#[prost({}=\"{}, {}\", tag=\"{}\"{})]\n",
             map_type.annotation(),
             key_tag,
             value_tag,
-            field.descriptor.number()
+            field.descriptor.number(),
+            self.meta_name(fq_message_name, field.descriptor.name(), Some(&field_ident)),
         ));
         self.append_field_attributes(fq_message_name, field.descriptor.name());
+        self.append_hook_lines(&hook_output.attributes);
         self.push_indent();
-        self.buf.push_str(&format!(
-            "pub {}: {}<{}, {}>,\n",
-            field.rust_name(),
-            map_type.rust_type(),
-            key_ty,
-            value_ty
-        ));
+        self.buf
+            .push_str(&format!("pub {}: {},\n", field_ident, field_type));
     }
 
     fn append_oneof_field(
@@ -578,41 +911,47 @@ impl<'a> CodeGenerator<'a> {
         fq_message_name: &str,
         oneof: &OneofField,
     ) {
-        let type_name = format!(
-            "{}::{}",
-            to_snake(message_name),
-            to_upper_camel(oneof.descriptor.name())
-        );
+        let type_name = self.oneof_type_path(message_name, fq_message_name, oneof);
+        let oneof_ident = self.oneof_ident(fq_message_name, oneof);
         self.append_doc(fq_message_name, None);
         self.push_indent();
         self.buf.push_str(&format!(
-            "#[prost(oneof=\"{}\", tags=\"{}\")]\n",
+            "#[prost(oneof=\"{}\", tags=\"{}\"{})]\n",
             type_name,
             oneof
                 .fields
                 .iter()
                 .map(|field| field.descriptor.number())
                 .join(", "),
+            self.meta_name(fq_message_name, oneof.descriptor.name(), Some(&oneof_ident)),
         ));
         self.append_field_attributes(fq_message_name, oneof.descriptor.name());
         self.push_indent();
         self.buf.push_str(&format!(
             "pub {}: ::core::option::Option<{}>,\n",
-            oneof.rust_name(),
-            type_name
+            oneof_ident, type_name
         ));
     }
 
     fn append_oneof(&mut self, fq_message_name: &str, oneof: &OneofField) {
+        let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
+
         self.path.push(8);
         self.path.push(oneof.path_index);
+        let hook_output = self.call_hook(
+            &oneof.descriptor,
+            oneof.descriptor.options.as_ref(),
+            &oneof_name,
+            &self.oneof_type_ident(fq_message_name, oneof),
+            |hook, context, output| hook.oneof(context, output),
+        );
         self.append_doc(fq_message_name, None);
         self.path.pop();
         self.path.pop();
 
-        let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
         self.append_type_attributes(&oneof_name);
         self.append_enum_attributes(&oneof_name);
+        self.append_hook_lines(&hook_output.attributes);
         self.push_indent();
 
         let can_oneof_derive_copy = oneof.fields.iter().all(|field| {
@@ -625,62 +964,88 @@ impl<'a> CodeGenerator<'a> {
             prost_path(self.config)
         ));
         self.append_skip_debug(fq_message_name);
+        self.append_message_meta(fq_message_name);
+        self.append_max_encoded_len(fq_message_name);
         self.push_indent();
         self.buf.push_str("pub enum ");
-        self.buf.push_str(&to_upper_camel(oneof.descriptor.name()));
+        self.buf
+            .push_str(&self.oneof_type_ident(fq_message_name, oneof));
         self.buf.push_str(" {\n");
 
+        let mut hook_items = hook_output.items;
         self.path.push(2);
         self.depth += 1;
         for field in &oneof.fields {
-            self.path.push(field.path_index);
-            self.append_doc(fq_message_name, Some(field.descriptor.name()));
-            self.path.pop();
-
-            self.push_indent();
-            let ty_tag = self.field_type_tag(&field.descriptor);
-            self.buf.push_str(&format!(
-                "#[prost({}, tag=\"{}\")]\n",
-                ty_tag,
-                field.descriptor.number()
-            ));
-            self.append_field_attributes(&oneof_name, field.descriptor.name());
-
-            self.push_indent();
-            let ty = self.resolve_type(&field.descriptor, fq_message_name);
+            let ty = self.resolve_field_type(
+                &field.descriptor,
+                fq_message_name,
+                Some(oneof.descriptor.name()),
+            );
 
-            let boxed = self.boxed(
+            let shared = self.shared(
                 &field.descriptor,
                 fq_message_name,
                 Some(oneof.descriptor.name()),
             );
+            let boxed = !shared
+                && self.boxed(
+                    &field.descriptor,
+                    fq_message_name,
+                    Some(oneof.descriptor.name()),
+                );
 
             debug!(
-                "    oneof: {:?}, type: {:?}, boxed: {}",
+                "    oneof: {:?}, type: {:?}, boxed: {}, shared: {}",
                 field.descriptor.name(),
                 ty,
-                boxed
+                boxed,
+                shared
             );
 
-            if boxed {
-                self.buf.push_str(&format!(
-                    "{}(::prost::alloc::boxed::Box<{}>),\n",
-                    to_upper_camel(field.descriptor.name()),
-                    ty
-                ));
+            let field_type = if boxed {
+                format!("::prost::alloc::boxed::Box<{}>", ty)
+            } else if shared {
+                format!("{}::alloc::sync::Arc<{}>", prost_path(self.config), ty)
             } else {
-                self.buf.push_str(&format!(
-                    "{}({}),\n",
-                    to_upper_camel(field.descriptor.name()),
-                    ty
-                ));
-            }
+                ty
+            };
+
+            self.path.push(field.path_index);
+            let hook_output = self.call_hook(
+                &field.descriptor,
+                field.descriptor.options.as_ref(),
+                &format!("{}.{}", fq_message_name, field.descriptor.name()),
+                &field_type,
+                |hook, context, output| hook.field(context, output),
+            );
+            hook_items.extend(hook_output.items);
+            self.append_doc(fq_message_name, Some(field.descriptor.name()));
+            self.path.pop();
+
+            self.push_indent();
+            let ty_tag = self.field_type_tag(&field.descriptor);
+            self.buf.push_str(&format!(
+                "#[prost({}, tag=\"{}\"{})]\n",
+                ty_tag,
+                field.descriptor.number(),
+                self.meta_name(fq_message_name, field.descriptor.name(), None),
+            ));
+            self.append_field_attributes(&oneof_name, field.descriptor.name());
+            self.append_hook_lines(&hook_output.attributes);
+
+            self.push_indent();
+            self.buf.push_str(&format!(
+                "{}({}),\n",
+                self.oneof_variant_ident(&oneof_name, field),
+                field_type
+            ));
         }
         self.depth -= 1;
         self.path.pop();
 
         self.push_indent();
         self.buf.push_str("}\n");
+        self.append_hook_lines(&hook_items);
     }
 
     fn location(&self) -> Option<&Location> {
@@ -712,8 +1077,6 @@ impl<'a> CodeGenerator<'a> {
         debug!("  enum: {:?}", desc.name());
 
         let proto_enum_name = desc.name();
-        let enum_name = to_upper_camel(proto_enum_name);
-
         let enum_values = &desc.value;
         let fq_proto_enum_name = self.fq_name(proto_enum_name);
 
@@ -721,13 +1084,25 @@ impl<'a> CodeGenerator<'a> {
             .extern_paths
             .resolve_ident(&fq_proto_enum_name)
             .is_some()
+            || !self.is_generated(&fq_proto_enum_name)
         {
             return;
         }
 
+        let enum_name = self.type_ident(&fq_proto_enum_name, proto_enum_name);
+
+        let hook_output = self.call_hook(
+            &desc,
+            desc.options.as_ref(),
+            &fq_proto_enum_name,
+            &enum_name,
+            |hook, context, output| hook.enumeration(context, output),
+        );
+
         self.append_doc(&fq_proto_enum_name, None);
         self.append_type_attributes(&fq_proto_enum_name);
         self.append_enum_attributes(&fq_proto_enum_name);
+        self.append_hook_lines(&hook_output.attributes);
         self.push_indent();
         let dbg = if self.should_skip_debug(&fq_proto_enum_name) {
             ""
@@ -746,16 +1121,27 @@ impl<'a> CodeGenerator<'a> {
         self.buf.push_str(&enum_name);
         self.buf.push_str(" {\n");
 
-        let variant_mappings =
-            build_enum_value_mappings(&enum_name, self.config.strip_enum_prefix, enum_values);
+        let variant_mappings = self.build_enum_value_mappings(&fq_proto_enum_name, enum_values);
 
+        let mut hook_items = hook_output.items;
         self.depth += 1;
         self.path.push(2);
         for variant in variant_mappings.iter() {
             self.path.push(variant.path_idx as i32);
 
+            let value = &enum_values[variant.path_idx];
+            let hook_output = self.call_hook(
+                value,
+                value.options.as_ref(),
+                &format!("{}.{}", fq_proto_enum_name, variant.proto_name),
+                &variant.generated_variant_name,
+                |hook, context, output| hook.enum_value(context, output),
+            );
+            hook_items.extend(hook_output.items);
+
             self.append_doc(&fq_proto_enum_name, Some(variant.proto_name));
             self.append_field_attributes(&fq_proto_enum_name, variant.proto_name);
+            self.append_hook_lines(&hook_output.attributes);
             self.push_indent();
             self.buf.push_str(&variant.generated_variant_name);
             self.buf.push_str(" = ");
@@ -854,12 +1240,15 @@ impl<'a> CodeGenerator<'a> {
         self.depth -= 1;
         self.push_indent();
         self.buf.push_str("}\n"); // End of impl
+
+        self.append_hook_lines(&hook_items);
     }
 
-    fn push_service(&mut self, service: ServiceDescriptorProto) {
+    fn resolve_service(&mut self, service: ServiceDescriptorProto) -> Service {
         let name = service.name().to_owned();
         debug!("  service: {:?}", name);
 
+        let fq_service_name = self.fq_name(&name);
         let comments = self
             .location()
             .map(Comments::from_location)
@@ -870,9 +1259,17 @@ impl<'a> CodeGenerator<'a> {
             .method
             .into_iter()
             .enumerate()
-            .map(|(idx, mut method)| {
+            .filter_map(|(idx, mut method)| {
                 debug!("  method: {:?}", method.name());
 
+                // Leave out methods whose request or response type is skipped.
+                let skip = &self.config.skip;
+                if skip.get_first(method.input_type()).is_some()
+                    || skip.get_first(method.output_type()).is_some()
+                {
+                    return None;
+                }
+
                 self.path.push(idx as i32);
                 let comments = self
                     .location()
@@ -888,7 +1285,7 @@ impl<'a> CodeGenerator<'a> {
                 let client_streaming = method.client_streaming();
                 let server_streaming = method.server_streaming();
 
-                Method {
+                Some(Method {
                     name: to_snake(&name),
                     proto_name: name,
                     comments,
@@ -899,22 +1296,18 @@ impl<'a> CodeGenerator<'a> {
                     options: method.options.unwrap_or_default(),
                     client_streaming,
                     server_streaming,
-                }
+                })
             })
             .collect();
         self.path.pop();
 
-        let service = Service {
-            name: to_upper_camel(&name),
+        Service {
+            name: self.type_ident(&fq_service_name, &name),
             proto_name: name,
             package: self.package.clone(),
             comments,
             methods,
             options: service.options.unwrap_or_default(),
-        };
-
-        if let Some(service_generator) = self.config.service_generator.as_mut() {
-            service_generator.generate(service, self.buf)
         }
     }
 
@@ -922,18 +1315,18 @@ impl<'a> CodeGenerator<'a> {
         push_indent(self.buf, self.depth);
     }
 
-    fn push_mod(&mut self, module: &str) {
+    fn push_mod(&mut self, message_name: &str, module: &str) {
         self.push_indent();
         self.buf.push_str("/// Nested message and enum types in `");
-        self.buf.push_str(module);
+        self.buf.push_str(message_name);
         self.buf.push_str("`.\n");
 
         self.push_indent();
         self.buf.push_str("pub mod ");
-        self.buf.push_str(&to_snake(module));
+        self.buf.push_str(module);
         self.buf.push_str(" {\n");
 
-        self.type_path.push(module.into());
+        self.type_path.push(message_name.into());
 
         self.depth += 1;
     }
@@ -965,46 +1358,95 @@ impl<'a> CodeGenerator<'a> {
                 .unwrap_or_default()
                 .rust_type()
                 .to_owned(),
-            Type::Group | Type::Message => self.resolve_ident(field.type_name()),
+            Type::Message => self
+                .resolve_wrapper_type(field, fq_message_name)
+                .unwrap_or_else(|| self.resolve_ident(field.type_name())),
+            Type::Group => self.resolve_ident(field.type_name()),
         }
     }
 
-    fn resolve_ident(&self, pb_ident: &str) -> String {
-        // protoc should always give fully qualified identifiers.
-        assert_eq!(".", &pb_ident[..1]);
-
-        if let Some(proto_ident) = self.extern_paths.resolve_ident(pb_ident) {
-            return proto_ident;
-        }
-
-        let mut local_path = self
-            .package
-            .split('.')
-            .chain(self.type_path.iter().map(String::as_str))
-            .peekable();
-
-        // If no package is specified the start of the package name will be '.'
-        // and split will return an empty string ("") which breaks resolution
-        // The fix to this is to ignore the first item if it is empty.
-        if local_path.peek().map_or(false, |s| s.is_empty()) {
-            local_path.next();
+    /// Returns the Rust type of a message or `oneof` field, which for `string` and `bytes` fields
+    /// is a fixed-capacity type if `Config::max_len` matches the field.
+    pub(crate) fn resolve_field_type(
+        &self,
+        field: &FieldDescriptorProto,
+        fq_message_name: &str,
+        oneof: Option<&str>,
+    ) -> String {
+        let config_path = match oneof {
+            None => Cow::Borrowed(fq_message_name),
+            Some(ooname) => Cow::Owned(format!("{fq_message_name}.{ooname}")),
+        };
+        let max_len = self
+            .config
+            .max_len
+            .get_first_field(&config_path, field.name())
+            .copied();
+        match (field.r#type(), max_len) {
+            (Type::String, Some(len)) => {
+                format!("{}::heapless::String<{}>", prost_path(self.config), len)
+            }
+            (Type::Bytes, Some(len)) => {
+                format!("{}::heapless::Vec<u8, {}>", prost_path(self.config), len)
+            }
+            _ => self.resolve_type(field, fq_message_name),
         }
+    }
 
-        let mut ident_path = pb_ident[1..].split('.');
-        let ident_type = ident_path.next_back().unwrap();
-        let mut ident_path = ident_path.peekable();
+    /// Returns the capacity of a fixed-capacity repeated field, if `Config::max_count` matches it.
+    pub(crate) fn max_count(
+        &self,
+        field: &FieldDescriptorProto,
+        fq_message_name: &str,
+    ) -> Option<usize> {
+        self.config
+            .max_count
+            .get_first_field(fq_message_name, field.name())
+            .copied()
+    }
 
-        // Skip path elements in common.
-        while local_path.peek().is_some() && local_path.peek() == ident_path.peek() {
-            local_path.next();
-            ident_path.next();
+    /// Returns the primitive Rust type for fields of a well-known wrapper type, if
+    /// `Config::primitive_wrapper_types` is enabled.
+    fn resolve_wrapper_type(
+        &self,
+        field: &FieldDescriptorProto,
+        fq_message_name: &str,
+    ) -> Option<String> {
+        if !self.config.primitive_wrapper_types {
+            return None;
         }
+        let ty = match field.type_name() {
+            ".google.protobuf.BoolValue" => String::from("bool"),
+            ".google.protobuf.BytesValue" => self
+                .config
+                .bytes_type
+                .get_first_field(fq_message_name, field.name())
+                .copied()
+                .unwrap_or_default()
+                .rust_type()
+                .to_owned(),
+            ".google.protobuf.DoubleValue" => String::from("f64"),
+            ".google.protobuf.FloatValue" => String::from("f32"),
+            ".google.protobuf.Int32Value" => String::from("i32"),
+            ".google.protobuf.Int64Value" => String::from("i64"),
+            ".google.protobuf.StringValue" => {
+                format!("{}::alloc::string::String", prost_path(self.config))
+            }
+            ".google.protobuf.UInt32Value" => String::from("u32"),
+            ".google.protobuf.UInt64Value" => String::from("u64"),
+            _ => return None,
+        };
+        Some(ty)
+    }
 
-        local_path
-            .map(|_| "super".to_string())
-            .chain(ident_path.map(to_snake))
-            .chain(iter::once(to_upper_camel(ident_type)))
-            .join("::")
+    fn resolve_ident(&self, pb_ident: &str) -> String {
+        resolve_ident(
+            self.extern_paths,
+            &self.config.renames,
+            &self.package,
+            &self.type_path,
+            pb_ident,
+        )
     }
 
     fn field_type_tag(&self, field: &FieldDescriptorProto) -> Cow<'static, str> {
@@ -1101,12 +1543,32 @@ impl<'a> CodeGenerator<'a> {
         false
     }
 
+    /// Returns whether the Rust type for this field needs to be `Arc<_>`.
+    ///
+    /// This is configured with `Config::shared`, and only applies to message fields.
+    fn shared(
+        &self,
+        field: &FieldDescriptorProto,
+        fq_message_name: &str,
+        oneof: Option<&str>,
+    ) -> bool {
+        let fd_type = field.r#type();
+        if fd_type != Type::Message && fd_type != Type::Group {
+            return false;
+        }
+        let config_path = match oneof {
+            None => Cow::Borrowed(fq_message_name),
+            Some(ooname) => Cow::Owned(format!("{fq_message_name}.{ooname}")),
+        };
+        self.config
+            .shared
+            .get_first_field(&config_path, field.name())
+            .is_some()
+    }
+
     /// Returns `true` if the field options includes the `deprecated` option.
     fn deprecated(&self, field: &FieldDescriptorProto) -> bool {
-        field
-            .options
-            .as_ref()
-            .map_or(false, FieldOptions::deprecated)
+        field.options.as_ref().is_some_and(FieldOptions::deprecated)
     }
 
     /// Returns the fully-qualified name, starting with a dot
@@ -1120,6 +1582,232 @@ impl<'a> CodeGenerator<'a> {
             message_name,
         )
     }
+
+    /// Returns `true` if code is generated for the message, enum or service `fq_name`, that is if
+    /// it is not skipped with `Config::skip`, and reachable from `Config::generate_only` roots.
+    fn is_generated(&self, fq_name: &str) -> bool {
+        self.message_graph.is_generated(fq_name) && self.config.skip.get_first(fq_name).is_none()
+    }
+
+    /// Returns `true` if the field of the message or `oneof` at `path` is skipped, either by
+    /// `Config::skip`, or because its type (or the value type of a map field) is skipped.
+    fn is_field_skipped(
+        &self,
+        path: &str,
+        field: &Field,
+        map_types: &HashMap<String, (FieldDescriptorProto, FieldDescriptorProto)>,
+    ) -> bool {
+        let skip = &self.config.skip;
+        if skip
+            .get_first_field(path, field.descriptor.name())
+            .is_some()
+        {
+            return true;
+        }
+        let type_name = match map_types.get(field.descriptor.type_name()) {
+            Some((_, value)) => value.type_name(),
+            None => field.descriptor.type_name(),
+        };
+        !type_name.is_empty() && skip.get_first(type_name).is_some()
+    }
+
+    /// Returns the Rust identifier of the message, enum or service `fq_name`.
+    fn type_ident(&self, fq_name: &str, name: &str) -> String {
+        type_ident(&self.config.renames, fq_name, name)
+    }
+
+    /// Returns the name of the module with the nested types of the message `fq_message_name`.
+    fn module_ident(&self, fq_message_name: &str, message_name: &str) -> String {
+        module_ident(&self.config.renames, fq_message_name, message_name)
+    }
+
+    /// Returns the Rust identifier of a field of the message at `fq_message_name`.
+    fn field_ident(&self, fq_message_name: &str, field: &Field) -> String {
+        self.config
+            .renames
+            .get(&format!("{}.{}", fq_message_name, field.descriptor.name()))
+            .cloned()
+            .unwrap_or_else(|| field.rust_name())
+    }
+
+    /// Returns the Rust identifier of the field of a `oneof`.
+    fn oneof_ident(&self, fq_message_name: &str, oneof: &OneofField) -> String {
+        self.config
+            .renames
+            .get(&format!("{}.{}", fq_message_name, oneof.descriptor.name()))
+            .cloned()
+            .unwrap_or_else(|| oneof.rust_name())
+    }
+
+    /// Returns the Rust identifier of the enum of a `oneof`, which is named after its field.
+    fn oneof_type_ident(&self, fq_message_name: &str, oneof: &OneofField) -> String {
+        let renamed =
+            self.config
+                .renames
+                .get(&format!("{}.{}", fq_message_name, oneof.descriptor.name()));
+        to_upper_camel(renamed.map_or(oneof.descriptor.name(), String::as_str))
+    }
+
+    /// Returns the path of the enum of a `oneof`, relative to the module of the message.
+    fn oneof_type_path(
+        &self,
+        message_name: &str,
+        fq_message_name: &str,
+        oneof: &OneofField,
+    ) -> String {
+        format!(
+            "{}::{}",
+            self.module_ident(fq_message_name, message_name),
+            self.oneof_type_ident(fq_message_name, oneof)
+        )
+    }
+
+    /// Returns the Rust identifier of the variant for a field of the `oneof` at `oneof_name`.
+    fn oneof_variant_ident(&self, oneof_name: &str, field: &Field) -> String {
+        self.config
+            .renames
+            .get(&format!("{}.{}", oneof_name, field.descriptor.name()))
+            .cloned()
+            .unwrap_or_else(|| to_upper_camel(field.descriptor.name()))
+    }
+
+    /// Returns the Rust identifier of the variant for the value `value_name` of the enum
+    /// `fq_enum_name`.
+    fn enum_variant_ident(&self, fq_enum_name: &str, value_name: &str) -> String {
+        if let Some(ident) = self
+            .config
+            .renames
+            .get(&format!("{}.{}", fq_enum_name, value_name))
+        {
+            return ident.clone();
+        }
+        let variant_ident = to_upper_camel(value_name);
+        if self.config.strip_enum_prefix {
+            // The prefix is the Protobuf name of the enum, even if the enum is renamed.
+            let enum_name = fq_enum_name.rsplit('.').next().unwrap();
+            strip_enum_prefix(&to_upper_camel(enum_name), &variant_ident)
+        } else {
+            variant_ident
+        }
+    }
+
+    fn build_enum_value_mappings<'b>(
+        &self,
+        fq_enum_name: &str,
+        enum_values: &'b [EnumValueDescriptorProto],
+    ) -> Vec<EnumVariantMapping<'b>> {
+        let mut numbers = HashSet::new();
+        let mut generated_names = HashMap::new();
+        let mut mappings = Vec::new();
+
+        for (idx, value) in enum_values.iter().enumerate() {
+            if self
+                .config
+                .skip
+                .get_first_field(fq_enum_name, value.name())
+                .is_some()
+            {
+                continue;
+            }
+
+            // Skip duplicate enum values. Protobuf allows this when the
+            // 'allow_alias' option is set.
+            if !numbers.insert(value.number()) {
+                continue;
+            }
+
+            let generated_variant_name = self.enum_variant_ident(fq_enum_name, value.name());
+
+            if let Some(old_v) =
+                generated_names.insert(generated_variant_name.to_owned(), value.name())
+            {
+                panic!("Generated enum variant names overlap: `{}` variant name to be used both by `{}` and `{}` ProtoBuf enum values",
+                    generated_variant_name, old_v, value.name());
+            }
+
+            mappings.push(EnumVariantMapping {
+                path_idx: idx,
+                proto_name: value.name(),
+                proto_number: value.number(),
+                generated_variant_name,
+            })
+        }
+        mappings
+    }
+}
+
+/// Returns the Rust identifier of the message, enum or service `fq_name`, as configured with
+/// `Config::rename`, or derived from its Protobuf `name`.
+fn type_ident(renames: &HashMap<String, String>, fq_name: &str, name: &str) -> String {
+    renames
+        .get(fq_name)
+        .cloned()
+        .unwrap_or_else(|| to_upper_camel(name))
+}
+
+/// Returns the name of the module with the nested types of the message `fq_message_name`.
+fn module_ident(
+    renames: &HashMap<String, String>,
+    fq_message_name: &str,
+    message_name: &str,
+) -> String {
+    to_snake(
+        renames
+            .get(fq_message_name)
+            .map_or(message_name, String::as_str),
+    )
+}
+
+/// Resolves the Rust path of the Protobuf type `pb_ident`, relative to the module of `package` and
+/// the nested `type_path`.
+pub(crate) fn resolve_ident(
+    extern_paths: &ExternPaths,
+    renames: &HashMap<String, String>,
+    package: &str,
+    type_path: &[String],
+    pb_ident: &str,
+) -> String {
+    // protoc should always give fully qualified identifiers.
+    assert_eq!(".", &pb_ident[..1]);
+
+    if let Some(proto_ident) = extern_paths.resolve_ident(pb_ident) {
+        return proto_ident;
+    }
+
+    let mut local_path = package
+        .split('.')
+        .chain(type_path.iter().map(String::as_str))
+        .peekable();
+
+    // If no package is specified the start of the package name will be '.'
+    // and split will return an empty string ("") which breaks resolution
+    // The fix to this is to ignore the first item if it is empty.
+    if local_path.peek().is_some_and(|s| s.is_empty()) {
+        local_path.next();
+    }
+
+    let mut ident_path = pb_ident[1..].split('.');
+    let ident_type = ident_path.next_back().unwrap();
+    let mut ident_path = ident_path.peekable();
+
+    // Skip path elements in common.
+    let mut fq_path = String::new();
+    while local_path.peek().is_some() && local_path.peek() == ident_path.peek() {
+        local_path.next();
+        fq_path.push('.');
+        fq_path.push_str(ident_path.next().unwrap());
+    }
+
+    // The remaining path elements are packages and messages, whose modules may be renamed.
+    local_path
+        .map(|_| "super".to_string())
+        .chain(ident_path.map(|segment| {
+            fq_path.push('.');
+            fq_path.push_str(segment);
+            module_ident(renames, &fq_path, segment)
+        }))
+        .chain(iter::once(type_ident(renames, pb_ident, ident_type)))
+        .join("::")
 }
 
 /// Returns `true` if the repeated field type can be packed.
@@ -1149,41 +1837,3 @@ struct EnumVariantMapping<'a> {
     proto_number: i32,
     generated_variant_name: String,
 }
-
-fn build_enum_value_mappings<'a>(
-    generated_enum_name: &str,
-    do_strip_enum_prefix: bool,
-    enum_values: &'a [EnumValueDescriptorProto],
-) -> Vec<EnumVariantMapping<'a>> {
-    let mut numbers = HashSet::new();
-    let mut generated_names = HashMap::new();
-    let mut mappings = Vec::new();
-
-    for (idx, value) in enum_values.iter().enumerate() {
-        // Skip duplicate enum values. Protobuf allows this when the
-        // 'allow_alias' option is set.
-        if !numbers.insert(value.number()) {
-            continue;
-        }
-
-        let mut generated_variant_name = to_upper_camel(value.name());
-        if do_strip_enum_prefix {
-            generated_variant_name =
-                strip_enum_prefix(generated_enum_name, &generated_variant_name);
-        }
-
-        if let Some(old_v) = generated_names.insert(generated_variant_name.to_owned(), value.name())
-        {
-            panic!("Generated enum variant names overlap: `{}` variant name to be used both by `{}` and `{}` ProtoBuf enum values",
-                generated_variant_name, old_v, value.name());
-        }
-
-        mappings.push(EnumVariantMapping {
-            path_idx: idx,
-            proto_name: value.name(),
-            proto_number: value.number(),
-            generated_variant_name,
-        })
-    }
-    mappings
-}
@@ -0,0 +1,434 @@
+//! Generation of message builders, see `Config::generate_builders`.
+
+use std::collections::HashMap;
+
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::FieldDescriptorProto;
+
+use super::{prost_path, CodeGenerator, Field, OneofField};
+use crate::ident::to_snake;
+
+/// The parameter type of a builder setter, and the expression converting the parameter to the
+/// type of the message field.
+struct Setter {
+    param: String,
+    /// The conversion expression, with `{}` in place of the parameter name.
+    expr: String,
+}
+
+impl Setter {
+    fn convert(&self, param_name: &str) -> String {
+        self.expr.replace("{}", param_name)
+    }
+}
+
+impl CodeGenerator<'_> {
+    pub(super) fn append_builder(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        fields: &[Field],
+        oneof_fields: &[OneofField],
+        map_types: &HashMap<String, (FieldDescriptorProto, FieldDescriptorProto)>,
+    ) {
+        let message_ident = self.type_ident(fq_message_name, message_name);
+        let builder_ident = format!("{}Builder", message_ident);
+        let required: Vec<&Field> = fields
+            .iter()
+            .filter(|field| field.descriptor.label() == Label::Required)
+            .collect();
+
+        // The derive generates an accessor named after optional scalar and enumeration fields.
+        let builder_fn = if fields.iter().any(|field| {
+            self.field_ident(fq_message_name, field) == "builder"
+                && !map_types.contains_key(field.descriptor.type_name())
+                && match field.descriptor.r#type() {
+                    Type::Enum => true,
+                    Type::Message | Type::Group => false,
+                    _ => self.optional(&field.descriptor),
+                }
+        }) {
+            "new_builder"
+        } else {
+            "builder"
+        };
+
+        self.push_line(&format!("impl {} {{", message_ident));
+        self.depth += 1;
+        self.push_line(&format!("/// Returns a builder for [`{}`].", message_ident));
+        self.push_line(&format!("pub fn {}() -> {} {{", builder_fn, builder_ident));
+        self.depth += 1;
+        self.push_line(&format!("{}::default()", builder_ident));
+        self.depth -= 1;
+        self.push_line("}");
+        self.depth -= 1;
+        self.push_line("}");
+
+        self.push_line(&format!("/// Builder for [`{}`].", message_ident));
+        if required.is_empty() {
+            self.push_line("#[derive(Clone, Debug, Default)]");
+        } else {
+            self.push_line("#[derive(Clone, Debug)]");
+        }
+        self.push_line(&format!("pub struct {} {{", builder_ident));
+        self.depth += 1;
+        self.push_line(&format!("inner: {},", message_ident));
+        if !required.is_empty() {
+            self.push_line(&format!("required: [bool; {}],", required.len()));
+        }
+        self.depth -= 1;
+        self.push_line("}");
+
+        if !required.is_empty() {
+            self.push_line(&format!(
+                "impl ::core::default::Default for {} {{",
+                builder_ident
+            ));
+            self.depth += 1;
+            self.push_line("fn default() -> Self {");
+            self.depth += 1;
+            self.push_line("Self {");
+            self.depth += 1;
+            self.push_line("inner: ::core::default::Default::default(),");
+            self.push_line(&format!("required: [false; {}],", required.len()));
+            self.depth -= 1;
+            self.push_line("}");
+            self.depth -= 1;
+            self.push_line("}");
+            self.depth -= 1;
+            self.push_line("}");
+        }
+
+        self.push_line("#[allow(deprecated)]");
+        self.push_line(&format!("impl {} {{", builder_ident));
+        self.depth += 1;
+
+        for field in fields {
+            let required_index = required
+                .iter()
+                .position(|r| r.descriptor.number() == field.descriptor.number());
+            match field
+                .descriptor
+                .type_name
+                .as_ref()
+                .and_then(|type_name| map_types.get(type_name))
+            {
+                Some((key, value)) => {
+                    self.append_builder_map_setter(fq_message_name, field, key, value)
+                }
+                None => self.append_builder_field_setters(fq_message_name, field, required_index),
+            }
+        }
+
+        for oneof in oneof_fields {
+            self.append_builder_oneof_setters(message_name, fq_message_name, oneof);
+        }
+
+        self.append_builder_build(&message_ident, fq_message_name, &required);
+
+        self.depth -= 1;
+        self.push_line("}");
+    }
+
+    fn append_builder_field_setters(
+        &mut self,
+        fq_message_name: &str,
+        field: &Field,
+        required_index: Option<usize>,
+    ) {
+        let rust_name = self.field_ident(fq_message_name, field);
+        let shared = self.shared(&field.descriptor, fq_message_name, None);
+        let boxed = !shared && self.boxed(&field.descriptor, fq_message_name, None);
+        let ty = self.resolve_field_type(&field.descriptor, fq_message_name, None);
+        let setter = self.builder_setter(&field.descriptor, ty, boxed, shared);
+        let deprecated = self.deprecated(&field.descriptor);
+
+        if field.descriptor.label() == Label::Repeated {
+            let name = rust_name.trim_start_matches("r#");
+
+            self.push_line(&format!(
+                "/// Appends a value to the `{}` field.",
+                field.descriptor.name()
+            ));
+            self.push_deprecated(deprecated);
+            self.push_line(&format!(
+                "pub fn push_{}(mut self, value: {}) -> Self {{",
+                name, setter.param
+            ));
+            self.depth += 1;
+            if self.max_count(&field.descriptor, fq_message_name).is_some() {
+                // `push` on a fixed-capacity vector returns a `Result`, so the value is appended
+                // with `Extend`, which panics if the vector is full.
+                self.push_line(&format!(
+                    "::core::iter::Extend::extend(&mut self.inner.{}, ::core::iter::once({}));",
+                    rust_name,
+                    setter.convert("value")
+                ));
+            } else {
+                self.push_line(&format!(
+                    "self.inner.{}.push({});",
+                    rust_name,
+                    setter.convert("value")
+                ));
+            }
+            self.push_line("self");
+            self.depth -= 1;
+            self.push_line("}");
+
+            self.push_line(&format!(
+                "/// Appends values to the `{}` field.",
+                field.descriptor.name()
+            ));
+            self.push_deprecated(deprecated);
+            self.push_line(&format!(
+                "pub fn extend_{}(mut self, values: impl ::core::iter::IntoIterator<Item = {}>) -> Self {{",
+                name, setter.param
+            ));
+            self.depth += 1;
+            self.push_line(&format!(
+                "self.inner.{}.extend(values.into_iter().map(|value| {}));",
+                rust_name,
+                setter.convert("value")
+            ));
+            self.push_line("self");
+            self.depth -= 1;
+            self.push_line("}");
+            return;
+        }
+
+        let expr = if self.optional(&field.descriptor) {
+            format!("::core::option::Option::Some({})", setter.convert("value"))
+        } else {
+            setter.convert("value")
+        };
+
+        self.push_line(&format!(
+            "/// Sets the `{}` field.",
+            field.descriptor.name()
+        ));
+        self.push_deprecated(deprecated);
+        self.push_line(&format!(
+            "pub fn {}(mut self, value: {}) -> Self {{",
+            setter_name(&rust_name),
+            setter.param
+        ));
+        self.depth += 1;
+        self.push_line(&format!("self.inner.{} = {};", rust_name, expr));
+        if let Some(index) = required_index {
+            self.push_line(&format!("self.required[{}] = true;", index));
+        }
+        self.push_line("self");
+        self.depth -= 1;
+        self.push_line("}");
+    }
+
+    fn append_builder_map_setter(
+        &mut self,
+        fq_message_name: &str,
+        field: &Field,
+        key: &FieldDescriptorProto,
+        value: &FieldDescriptorProto,
+    ) {
+        let rust_name = self.field_ident(fq_message_name, field);
+        let key_ty = self.resolve_type(key, fq_message_name);
+        let value_ty = self.resolve_type(value, fq_message_name);
+        let key = self.builder_setter(key, key_ty, false, false);
+        let value = self.builder_setter(value, value_ty, false, false);
+        let deprecated = self.deprecated(&field.descriptor);
+
+        self.push_line(&format!(
+            "/// Inserts an entry into the `{}` map field.",
+            field.descriptor.name()
+        ));
+        self.push_deprecated(deprecated);
+        self.push_line(&format!(
+            "pub fn insert_{}(mut self, key: {}, value: {}) -> Self {{",
+            rust_name.trim_start_matches("r#"),
+            key.param,
+            value.param
+        ));
+        self.depth += 1;
+        self.push_line(&format!(
+            "self.inner.{}.insert({}, {});",
+            rust_name,
+            key.convert("key"),
+            value.convert("value")
+        ));
+        self.push_line("self");
+        self.depth -= 1;
+        self.push_line("}");
+    }
+
+    fn append_builder_oneof_setters(
+        &mut self,
+        message_name: &str,
+        fq_message_name: &str,
+        oneof: &OneofField,
+    ) {
+        let oneof_type = self.oneof_type_path(message_name, fq_message_name, oneof);
+        let oneof_name = format!("{}.{}", fq_message_name, oneof.descriptor.name());
+        for field in &oneof.fields {
+            let shared = self.shared(
+                &field.descriptor,
+                fq_message_name,
+                Some(oneof.descriptor.name()),
+            );
+            let boxed = !shared
+                && self.boxed(
+                    &field.descriptor,
+                    fq_message_name,
+                    Some(oneof.descriptor.name()),
+                );
+            let ty = self.resolve_field_type(
+                &field.descriptor,
+                fq_message_name,
+                Some(oneof.descriptor.name()),
+            );
+            let setter = self.builder_setter(&field.descriptor, ty, boxed, shared);
+            let variant_ident = self.oneof_variant_ident(&oneof_name, field);
+            // The setter of a renamed variant is named after its identifier.
+            let rust_name = if self.config.renames.contains_key(&format!(
+                "{}.{}",
+                oneof_name,
+                field.descriptor.name()
+            )) {
+                to_snake(&variant_ident)
+            } else {
+                field.rust_name()
+            };
+
+            self.push_line(&format!(
+                "/// Sets the `{}` oneof to the `{}` variant.",
+                oneof.descriptor.name(),
+                field.descriptor.name()
+            ));
+            self.push_deprecated(self.deprecated(&field.descriptor));
+            self.push_line(&format!(
+                "pub fn {}(mut self, value: {}) -> Self {{",
+                setter_name(&rust_name),
+                setter.param
+            ));
+            self.depth += 1;
+            self.push_line(&format!(
+                "self.inner.{} = ::core::option::Option::Some({}::{}({}));",
+                self.oneof_ident(fq_message_name, oneof),
+                oneof_type,
+                variant_ident,
+                setter.convert("value")
+            ));
+            self.push_line("self");
+            self.depth -= 1;
+            self.push_line("}");
+        }
+    }
+
+    fn append_builder_build(
+        &mut self,
+        message_ident: &str,
+        fq_message_name: &str,
+        required: &[&Field],
+    ) {
+        if required.is_empty() {
+            self.push_line(&format!("/// Builds the [`{}`].", message_ident));
+            self.push_line(&format!("pub fn build(self) -> {} {{", message_ident));
+            self.depth += 1;
+            self.push_line("self.inner");
+            self.depth -= 1;
+            self.push_line("}");
+            return;
+        }
+
+        let prost_path = prost_path(self.config).to_string();
+        self.push_line(&format!(
+            "/// Builds the [`{}`], or returns an error if a required field is not set.",
+            message_ident
+        ));
+        self.push_line(&format!(
+            "pub fn build(self) -> ::core::result::Result<{}, {}::MissingRequiredField> {{",
+            message_ident, prost_path
+        ));
+        self.depth += 1;
+        self.push_line(&format!(
+            "const REQUIRED: [&str; {}] = [{}];",
+            required.len(),
+            required
+                .iter()
+                .map(|field| format!("{:?}", field.descriptor.name()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        self.push_line("match self.required.iter().position(|set| !set) {");
+        self.depth += 1;
+        self.push_line(&format!(
+            "::core::option::Option::Some(index) => ::core::result::Result::Err({}::MissingRequiredField {{",
+            prost_path
+        ));
+        self.depth += 1;
+        self.push_line(&format!(
+            "message: {:?},",
+            fq_message_name.trim_start_matches('.')
+        ));
+        self.push_line("field: REQUIRED[index],");
+        self.depth -= 1;
+        self.push_line("}),");
+        self.push_line("::core::option::Option::None => ::core::result::Result::Ok(self.inner),");
+        self.depth -= 1;
+        self.push_line("}");
+        self.depth -= 1;
+        self.push_line("}");
+    }
+
+    /// Returns the setter parameter type and conversion expression for a (non-repeated) value of
+    /// the field type.
+    fn builder_setter(
+        &self,
+        field: &FieldDescriptorProto,
+        ty: String,
+        boxed: bool,
+        shared: bool,
+    ) -> Setter {
+        match field.r#type() {
+            Type::Enum => Setter {
+                param: self.resolve_ident(field.type_name()),
+                expr: String::from("{} as i32"),
+            },
+            Type::String | Type::Bytes => Setter {
+                param: format!("impl ::core::convert::Into<{}>", ty),
+                expr: String::from("{}.into()"),
+            },
+            Type::Message | Type::Group if boxed => Setter {
+                expr: format!("{}::alloc::boxed::Box::new({{}})", prost_path(self.config)),
+                param: ty,
+            },
+            Type::Message | Type::Group if shared => Setter {
+                expr: format!("{}::alloc::sync::Arc::new({{}})", prost_path(self.config)),
+                param: ty,
+            },
+            _ => Setter {
+                param: ty,
+                expr: String::from("{}"),
+            },
+        }
+    }
+
+    fn push_deprecated(&mut self, deprecated: bool) {
+        if deprecated {
+            self.push_line("#[deprecated]");
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.push_indent();
+        self.buf.push_str(line);
+        self.buf.push('\n');
+    }
+}
+
+/// Returns the setter method name for a field, avoiding a collision with `build`.
+fn setter_name(rust_name: &str) -> String {
+    if rust_name == "build" {
+        String::from("set_build")
+    } else {
+        rust_name.to_string()
+    }
+}
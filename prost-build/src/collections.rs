@@ -7,6 +7,10 @@ pub(crate) enum MapType {
     HashMap,
     /// The [`std::collections::BTreeMap`] type.
     BTreeMap,
+    /// The [`indexmap::IndexMap`] type, re-exported by `prost` with the `indexmap` feature.
+    IndexMap,
+    /// The [`hashbrown::HashMap`] type, re-exported by `prost` with the `hashbrown` feature.
+    Hashbrown,
 }
 
 /// The bytes collection type to output for Protobuf `bytes` fields.
@@ -26,6 +30,8 @@ impl MapType {
         match self {
             MapType::HashMap => "map",
             MapType::BTreeMap => "btree_map",
+            MapType::IndexMap => "index_map",
+            MapType::Hashbrown => "hashbrown_map",
         }
     }
 
@@ -34,8 +40,15 @@ impl MapType {
         match self {
             MapType::HashMap => "::std::collections::HashMap",
             MapType::BTreeMap => "::prost::alloc::collections::BTreeMap",
+            MapType::IndexMap => "::prost::indexmap::IndexMap",
+            MapType::Hashbrown => "::prost::hashbrown::HashMap",
         }
     }
+
+    /// Returns `true` if the map type is generic over a [`std::hash::BuildHasher`].
+    pub fn has_hasher(&self) -> bool {
+        !matches!(self, MapType::BTreeMap)
+    }
 }
 
 impl BytesType {
@@ -12,8 +12,10 @@ use log::debug;
 use log::trace;
 
 use prost::Message;
+use prost_types::field_descriptor_proto::Type;
 use prost_types::{FileDescriptorProto, FileDescriptorSet};
 
+use crate::check::Output;
 use crate::code_generator::CodeGenerator;
 use crate::extern_paths::ExternPaths;
 use crate::message_graph::MessageGraph;
@@ -22,6 +24,7 @@ use crate::BytesType;
 use crate::MapType;
 use crate::Module;
 use crate::ServiceGenerator;
+use crate::{hook, CodeGeneratorHook};
 
 /// Configuration options for Protobuf code generation.
 ///
@@ -29,29 +32,52 @@ use crate::ServiceGenerator;
 pub struct Config {
     pub(crate) file_descriptor_set_path: Option<PathBuf>,
     pub(crate) service_generator: Option<Box<dyn ServiceGenerator>>,
+    pub(crate) code_generator_hook: Option<Box<dyn CodeGeneratorHook>>,
+    pub(crate) raw_options: HashMap<String, Vec<u8>>,
     pub(crate) map_type: PathMap<MapType>,
+    pub(crate) map_hasher: PathMap<String>,
     pub(crate) bytes_type: PathMap<BytesType>,
     pub(crate) type_attributes: PathMap<String>,
     pub(crate) message_attributes: PathMap<String>,
     pub(crate) enum_attributes: PathMap<String>,
     pub(crate) field_attributes: PathMap<String>,
     pub(crate) boxed: PathMap<()>,
+    pub(crate) shared: PathMap<()>,
+    pub(crate) max_len: PathMap<usize>,
+    pub(crate) max_count: PathMap<usize>,
+    pub(crate) builders: PathMap<()>,
     pub(crate) prost_types: bool,
+    pub(crate) primitive_wrapper_types: bool,
     pub(crate) strip_enum_prefix: bool,
     pub(crate) out_dir: Option<PathBuf>,
     pub(crate) extern_paths: Vec<(String, String)>,
     pub(crate) default_package_filename: String,
     pub(crate) enable_type_names: bool,
+    pub(crate) enable_field_constants: bool,
     pub(crate) type_name_domains: PathMap<String>,
     pub(crate) protoc_args: Vec<OsString>,
     pub(crate) protoc_executable: PathBuf,
     pub(crate) disable_comments: PathMap<()>,
     pub(crate) skip_debug: PathMap<()>,
+    pub(crate) message_meta: PathMap<()>,
+    pub(crate) max_encoded_len: PathMap<()>,
+    pub(crate) table_driven: PathMap<()>,
+    pub(crate) generate_only: Vec<String>,
+    pub(crate) skip: PathMap<()>,
+    pub(crate) renames: HashMap<String, String>,
     pub(crate) skip_protoc_run: bool,
+    pub(crate) emit_rerun_if_changed: bool,
     pub(crate) include_file: Option<PathBuf>,
+    pub(crate) file_per_proto: bool,
+    pub(crate) check_only: bool,
+    pub(crate) check_only_ignore: Vec<PathBuf>,
     pub(crate) prost_path: Option<String>,
+    #[cfg(feature = "config-file")]
+    pub(crate) config_files: Vec<PathBuf>,
     #[cfg(feature = "format")]
     pub(crate) fmt: bool,
+    #[cfg(feature = "expand-derives")]
+    pub(crate) expand_derives: bool,
 }
 
 impl Config {
@@ -60,6 +86,88 @@ impl Config {
         Config::default()
     }
 
+    /// Creates a new code generator configuration with the options of a TOML file.
+    ///
+    /// See [`load_file`](#method.load_file) for the format of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// prost_build::Config::from_file("prost.toml")?
+    ///     .compile_protos(&["src/frontend.proto"], &["src"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "config-file")]
+    pub fn from_file<P>(path: P) -> Result<Config>
+    where
+        P: AsRef<Path>,
+    {
+        let mut config = Config::new();
+        config.load_file(path)?;
+        Ok(config)
+    }
+
+    /// Adds the options of a TOML file to this configuration.
+    ///
+    /// Every key of the file is named after the method which sets the same option. Options
+    /// which take paths are either an array of paths, or a table keyed by path for options
+    /// which take a value for each path. Options which take no arguments are booleans, and
+    /// `protoc_args` is an array of arguments. Unknown keys and values of the wrong type are
+    /// reported as errors, with the location in the file.
+    ///
+    /// The options are added to the options already set: paths, attributes, extern paths,
+    /// renames and `protoc` arguments are appended, and the other options are replaced. Options
+    /// can still be set after loading the file, although methods taking a set of paths, like
+    /// [`btree_map`](#method.btree_map), replace the paths of the file. The service generator and
+    /// code generator hook can only be set with their methods.
+    ///
+    /// Paths in the file are used as given, as if they were passed to the methods, so relative
+    /// paths are relative to the working directory rather than to the file.
+    ///
+    /// # Examples
+    ///
+    /// A `prost.toml` file:
+    ///
+    /// ```toml
+    /// btree_map = ["."]
+    /// bytes = [".my_messages.MyMessageType.my_bytes_field"]
+    /// boxed = [".my_messages.MyMessageType.my_field"]
+    /// include_file = "_includes.rs"
+    /// enable_type_names = true
+    ///
+    /// [type_attribute]
+    /// ".my_messages" = "#[derive(Eq)]"
+    /// ".my_messages.MyMessageType" = ["#[derive(Hash)]", "#[non_exhaustive]"]
+    ///
+    /// [extern_path]
+    /// ".uuid" = "::uuid"
+    ///
+    /// [type_name_domain]
+    /// "." = "type.googleapis.com"
+    /// ```
+    ///
+    /// In `build.rs`:
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut config = prost_build::Config::new();
+    /// config
+    ///     .load_file("prost.toml")?
+    ///     .type_attribute(".my_messages.MyMessageType", "#[derive(Default)]");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "config-file")]
+    pub fn load_file<P>(&mut self, path: P) -> Result<&mut Self>
+    where
+        P: AsRef<Path>,
+    {
+        crate::config_file::load(self, path.as_ref())?;
+        Ok(self)
+    }
+
     /// Configure the code generator to generate Rust [`BTreeMap`][1] fields for Protobuf
     /// [`map`][2] type fields.
     ///
@@ -113,14 +221,116 @@ impl Config {
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.map_type.clear();
+        self.set_map_type(paths, MapType::BTreeMap)
+    }
+
+    /// Configure the code generator to generate Rust [`IndexMap`][1] fields for Protobuf
+    /// [`map`][2] type fields.
+    ///
+    /// `IndexMap` preserves the order in which entries were decoded from the wire, and encodes
+    /// them back in the same order. The generated code requires the `indexmap` feature of the
+    /// `prost` crate.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use a Rust
+    /// `IndexMap` for Protobuf `map` fields. For details about matching fields see
+    /// [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Use an `IndexMap` for a specific field, and a `BTreeMap` for all other map fields.
+    /// config.btree_map(&["."]);
+    /// config.index_map(&[".my_messages.MyMessageType.my_map_field"]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/indexmap/latest/indexmap/map/struct.IndexMap.html
+    /// [2]: https://developers.google.com/protocol-buffers/docs/proto3#maps
+    pub fn index_map<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.set_map_type(paths, MapType::IndexMap)
+    }
+
+    /// Configure the code generator to generate [`hashbrown::HashMap`][1] fields for Protobuf
+    /// [`map`][2] type fields.
+    ///
+    /// Unlike [`std::collections::HashMap`], the `hashbrown` map is available in `no_std`
+    /// environments. The generated code requires the `hashbrown` feature of the `prost` crate.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use a
+    /// `hashbrown::HashMap` for Protobuf `map` fields. For details about matching fields see
+    /// [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Match all map fields.
+    /// config.hashbrown_map(&["."]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/hashbrown/latest/hashbrown/struct.HashMap.html
+    /// [2]: https://developers.google.com/protocol-buffers/docs/proto3#maps
+    pub fn hashbrown_map<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.set_map_type(paths, MapType::Hashbrown)
+    }
+
+    /// Replaces the paths configured for `map_type`, leaving paths configured for other map types
+    /// untouched.
+    fn set_map_type<I, S>(&mut self, paths: I, map_type: MapType) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.map_type.retain_values(|ty| *ty != map_type);
         for matcher in paths {
-            self.map_type
-                .insert(matcher.as_ref().to_string(), MapType::BTreeMap);
+            self.map_type.insert(matcher.as_ref().to_string(), map_type);
         }
         self
     }
 
+    /// Configure the hasher type used by hash based map fields.
+    ///
+    /// Applies to fields generated as `HashMap` (the default), `IndexMap` or `hashbrown::HashMap`.
+    /// Fields generated as `BTreeMap` are not affected. The hasher type must implement
+    /// [`BuildHasher`][1] and [`Default`].
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of map fields. For details about matching fields
+    /// see [`btree_map`](#method.btree_map).
+    ///
+    /// **`hasher`** - the fully-qualified Rust path of the hasher type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.map_hasher(".", "::std::hash::BuildHasherDefault<::my_crate::FxHasher>");
+    /// ```
+    ///
+    /// [1]: https://doc.rust-lang.org/std/hash/trait.BuildHasher.html
+    pub fn map_hasher<P, H>(&mut self, path: P, hasher: H) -> &mut Self
+    where
+        P: AsRef<str>,
+        H: AsRef<str>,
+    {
+        self.map_hasher
+            .insert(path.as_ref().to_string(), hasher.as_ref().to_string());
+        self
+    }
+
     /// Configure the code generator to generate Rust [`bytes::Bytes`][1] fields for Protobuf
     /// [`bytes`][2] type fields.
     ///
@@ -373,12 +583,166 @@ impl Config {
         self
     }
 
+    /// Wrap matched message fields in an [`Arc`][1], so that large sub-messages can be shared
+    /// between parent messages and threads without deep copies.
+    ///
+    /// Applies to singular, repeated and `oneof` message fields. Merging into a shared field uses
+    /// copy-on-write semantics ([`Arc::make_mut`][2]): if the field is decoded more than once, the
+    /// existing value is cloned before it is modified, unless this is the only reference to it.
+    /// Shared fields take precedence over [`boxed`](#method.boxed) fields.
+    ///
+    /// `Rc` is not supported, because [`prost::Message`][3] requires `Send + Sync`.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific fields, messages, or packages which should use a Rust
+    /// `Arc` for message fields. For details about matching fields see
+    /// [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.shared(&[".my_messages.MyMessageType.config_snapshot"]);
+    /// ```
+    ///
+    /// [1]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+    /// [2]: https://doc.rust-lang.org/std/sync/struct.Arc.html#method.make_mut
+    /// [3]: https://docs.rs/prost/latest/prost/trait.Message.html
+    pub fn shared<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.shared.clear();
+        for matcher in paths {
+            self.shared.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Store matched `string` and `bytes` fields in fixed-capacity [`heapless::String`][1] and
+    /// [`heapless::Vec<u8, N>`][2] values, which can hold at most `len` bytes.
+    ///
+    /// This corresponds to the `max_size` option of nanopb. Decoding a value which is longer
+    /// than `len` fails with a capacity error. Together with [`max_count`](#method.max_count),
+    /// this allows messages to be decoded without heap allocation. The generated code requires
+    /// the `heapless` feature of `prost`.
+    ///
+    /// Fields of the well-known wrapper types and `map` fields are not affected.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. For details about matching fields see
+    /// [`btree_map`](#method.btree_map).
+    ///
+    /// **`len`** - the capacity of the matched fields, in bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.max_len(".my_messages.MyMessageType.name", 32);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/heapless/latest/heapless/struct.String.html
+    /// [2]: https://docs.rs/heapless/latest/heapless/struct.Vec.html
+    pub fn max_len<P>(&mut self, path: P, len: usize) -> &mut Self
+    where
+        P: AsRef<str>,
+    {
+        self.max_len.insert(path.as_ref().to_string(), len);
+        self
+    }
+
+    /// Store matched `repeated` fields in fixed-capacity [`heapless::Vec`][1] values, which can
+    /// hold at most `count` elements.
+    ///
+    /// This corresponds to the `max_count` option of nanopb. Decoding more than `count` elements
+    /// fails with a capacity error, while merging or extending past the capacity in Rust code
+    /// panics. The generated code requires the `heapless` feature of `prost`.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - a path matching any number of fields. For details about matching fields see
+    /// [`btree_map`](#method.btree_map).
+    ///
+    /// **`count`** - the capacity of the matched fields, in elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.max_count(".my_messages.MyMessageType.values", 8);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/heapless/latest/heapless/struct.Vec.html
+    pub fn max_count<P>(&mut self, path: P, count: usize) -> &mut Self
+    where
+        P: AsRef<str>,
+    {
+        self.max_count.insert(path.as_ref().to_string(), count);
+        self
+    }
+
+    /// Configure the code generator to generate a builder for matched messages.
+    ///
+    /// For a message `Foo`, a `FooBuilder` type and a `Foo::builder()` constructor are generated.
+    /// The builder has a setter for every field, which takes care of wrapping the value in
+    /// `Option`, `Box` or `Arc`, and of converting enumerations to `i32`:
+    ///
+    /// - singular fields and `oneof` variants have a setter named after the field,
+    /// - repeated fields have `push_<field>` and `extend_<field>` methods,
+    /// - map fields have an `insert_<field>` method.
+    ///
+    /// The setter of a field named `build` is named `set_build`, and if a field named `builder`
+    /// has a derived accessor, the constructor is named `Foo::new_builder()` instead.
+    ///
+    /// `FooBuilder::build()` returns the message. If the message has `proto2` required fields,
+    /// `build()` returns a [`prost::MissingRequiredField`][1] error if any of them was not set.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should get a builder. For
+    /// details about matching paths see [`btree_map`](#method.btree_map), just with the field
+    /// name omitted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Generate builders for all messages.
+    /// config.generate_builders(&["."]);
+    /// ```
+    ///
+    /// [1]: https://docs.rs/prost/latest/prost/struct.MissingRequiredField.html
+    pub fn generate_builders<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.builders.clear();
+        for matcher in paths {
+            self.builders.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
     /// Configures the code generator to use the provided service generator.
     pub fn service_generator(&mut self, service_generator: Box<dyn ServiceGenerator>) -> &mut Self {
         self.service_generator = Some(service_generator);
         self
     }
 
+    /// Configures the code generator to call the provided hook for every generated message,
+    /// field, enum, enum value and oneof.
+    ///
+    /// See [`CodeGeneratorHook`] for details.
+    pub fn code_generator_hook(&mut self, hook: Box<dyn CodeGeneratorHook>) -> &mut Self {
+        self.code_generator_hook = Some(hook);
+        self
+    }
+
     /// Configures the code generator to not use the `prost_types` crate for Protobuf well-known
     /// types, and instead generate Protobuf well-known types from their `.proto` definitions.
     pub fn compile_well_known_types(&mut self) -> &mut Self {
@@ -386,6 +750,32 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to always generate primitive Rust types for fields of the
+    /// Protobuf well-known wrapper types, such as `google.protobuf.Int32Value` or
+    /// `google.protobuf.StringValue`.
+    ///
+    /// A singular `google.protobuf.Int32Value` field becomes an `Option<i32>`, a
+    /// `google.protobuf.StringValue` field becomes an `Option<String>`, and so on. The wire format
+    /// is identical to the wrapper message. `google.protobuf.BytesValue` fields follow the
+    /// [`bytes`](#method.bytes) configuration.
+    ///
+    /// When the `prost_types` crate is used for well-known types, wrapper types are already mapped
+    /// to primitive types, except that `BytesValue` always maps to `Vec<u8>`. This option is
+    /// mostly useful together with [`compile_well_known_types`](#method.compile_well_known_types)
+    /// or [`extern_path`](#method.extern_path), where wrapper fields would otherwise use the
+    /// generated wrapper message structs.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.compile_well_known_types().primitive_wrapper_types();
+    /// ```
+    pub fn primitive_wrapper_types(&mut self) -> &mut Self {
+        self.primitive_wrapper_types = true;
+        self
+    }
+
     /// Configures the code generator to omit documentation comments on generated Protobuf types.
     ///
     /// # Example
@@ -435,6 +825,217 @@ impl Config {
         self
     }
 
+    /// Implement `prost::MessageMeta` for messages, and `prost::OneofMeta` for their oneofs.
+    ///
+    /// `MessageMeta` describes the fields of a message at runtime, which is used by the field
+    /// path utilities such as `prost::diff` and `prost_types::field_mask`. The descriptions add a
+    /// static table to every message, so they are only generated for the selected messages.
+    /// Every message nested in a selected message must be selected as well, or implement
+    /// `MessageMeta` by hand. The well-known types of `prost-types` implement it.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should implement
+    /// `MessageMeta`. For details about matching fully qualified names, see
+    /// [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Describe all messages.
+    /// config.message_meta(&["."]);
+    /// // Describe the messages of the `foo` package.
+    /// config.message_meta(&[".foo"]);
+    /// ```
+    pub fn message_meta<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.message_meta.clear();
+        for matcher in paths {
+            self.message_meta.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Implement `prost::MaxEncodedLen` for messages and their oneofs.
+    ///
+    /// `MaxEncodedLen` provides a compile-time upper bound on the encoded length of a message,
+    /// which can be used to size encoding buffers on the stack. The bound is `None` unless every
+    /// field is bounded, e.g. `string`, `bytes` and `repeated` fields need a fixed capacity, see
+    /// [`max_len`](#method.max_len) and [`max_count`](#method.max_count). Every message nested in
+    /// a selected message must be selected as well, or implement `MaxEncodedLen` by hand.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should implement
+    /// `MaxEncodedLen`. For details about matching fully qualified names, see
+    /// [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Bound the encoded length of all messages.
+    /// config.max_encoded_len(&["."]);
+    /// // Bound the encoded length of the messages of the `foo` package.
+    /// config.max_encoded_len(&[".foo"]);
+    /// ```
+    pub fn max_encoded_len<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.max_encoded_len.clear();
+        for matcher in paths {
+            self.max_encoded_len
+                .insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Encode and decode messages with a field table, for smaller binaries.
+    ///
+    /// By default, the `Message` impl of every message encodes and decodes its fields inline,
+    /// and is compiled again for every buffer type it is used with. Table driven messages instead
+    /// get a static table with an entry for each field, which is interpreted by a single
+    /// implementation in `prost`. This reduces the code size, which matters for targets like
+    /// WebAssembly and embedded devices, in exchange for slower encoding and decoding. Merging
+    /// with `Message::merge_from` also goes through the table.
+    ///
+    /// Messages in files with `option optimize_for = CODE_SIZE;` are always table driven.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific messages, or packages which should be table driven.
+    /// For details about matching fully qualified names, see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Make all messages table driven.
+    /// config.table_driven(&["."]);
+    /// // Make the messages of the `foo` package table driven.
+    /// config.table_driven(&[".foo"]);
+    /// ```
+    pub fn table_driven<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.table_driven.clear();
+        for matcher in paths {
+            self.table_driven.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Generate code only for the types reachable from the given messages and services.
+    ///
+    /// By default, code is generated for every type of the compiled `.proto` files and their
+    /// imports. When only a few types of a large API are used, this walks the types referred to
+    /// by the fields of the roots, and by the request and response types of the methods of root
+    /// services, and leaves out everything else. Files and packages without reachable types are
+    /// not written, and are left out of the [`include_file`](#method.include_file). Messages
+    /// containing a reachable nested type are generated as well, with all of their fields.
+    ///
+    /// # Arguments
+    ///
+    /// **`roots`** - fully qualified names of messages and services, with a leading `.`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.generate_only(&[".google.pubsub.v1.Publisher", ".google.pubsub.v1.PubsubMessage"]);
+    /// ```
+    pub fn generate_only<I, S>(&mut self, roots: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.generate_only = roots
+            .into_iter()
+            .map(|root| root.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Skip generating code for messages, fields, enums, enum values, oneofs and services.
+    ///
+    /// Unlike [`extern_path`](#method.extern_path), no replacement is needed for skipped types:
+    /// fields whose type is a skipped message or enum are skipped as well, and so are service
+    /// methods whose request or response type is skipped. Skipping a message also skips its
+    /// nested types, and skipping a package skips all of its types and services.
+    ///
+    /// Compiling fails if a skipped enum value is still needed: the first value of an enum is
+    /// its default, and can not be skipped, and neither can the `[default = ...]` value of a
+    /// field which is not skipped.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths to specific items, or packages whose items should be skipped. Fields
+    /// and enum values are matched as `.my_messages.MyMessage.my_field`, and fields of a `oneof`
+    /// as `.my_messages.MyMessage.my_oneof.my_field`. For details about matching fully qualified
+    /// names, see [`btree_map`](#method.btree_map).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // Skip a legacy message, and every field of other messages which refers to it.
+    /// config.skip(&[".my_messages.LegacyMessage", ".my_messages.MyMessage.legacy_field"]);
+    /// ```
+    pub fn skip<I, S>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.skip.clear();
+        for matcher in paths {
+            self.skip.insert(matcher.as_ref().to_string(), ());
+        }
+        self
+    }
+
+    /// Use `rust_ident` as the Rust identifier of a message, field, enum, enum value, oneof or
+    /// service, instead of the identifier derived from its Protobuf name.
+    ///
+    /// References to a renamed message or enum elsewhere in the generated code, such as field
+    /// types and service methods, use the new identifier. The module of the nested types of a
+    /// renamed message is named after `rust_ident`, and so is the enum of a renamed `oneof`,
+    /// whose field is named `rust_ident`. `rust_ident` is used as is, so it can also be a raw
+    /// identifier such as `r#type`.
+    ///
+    /// # Arguments
+    ///
+    /// **`path`** - the fully qualified path of the item, with a leading `.`. Fields and enum
+    /// values are named as `.my_messages.MyMessage.my_field`, and fields of a `oneof` as
+    /// `.my_messages.MyMessage.my_oneof.my_field`.
+    ///
+    /// **`rust_ident`** - the Rust identifier of the item.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config
+    ///     .rename(".my_messages.MyMessageType", "Message")
+    ///     .rename(".my_messages.MyMessageType.type", "kind")
+    ///     .rename(".my_messages.Color.COLOR_UNKNOWN", "Unspecified");
+    /// ```
+    pub fn rename<P, I>(&mut self, path: P, rust_ident: I) -> &mut Self
+    where
+        P: Into<String>,
+        I: Into<String>,
+    {
+        self.renames.insert(path.into(), rust_ident.into());
+        self
+    }
+
     /// Declare an externally provided Protobuf package or type.
     ///
     /// `extern_path` allows `prost` types in external crates to be referenced in generated code.
@@ -598,6 +1199,33 @@ impl Config {
         self
     }
 
+    /// Configures whether Cargo `rerun-if-changed` directives are emitted for the `.proto` files.
+    ///
+    /// By default, [`load_fds`](#method.load_fds) and [`compile_protos`](#method.compile_protos)
+    /// print a `cargo:rerun-if-changed` directive for every file of the `FileDescriptorSet`
+    /// which is found in the include paths, including imported files, and
+    /// `cargo:rerun-if-env-changed` directives for the `PROTOC` and `PROTOC_INCLUDE` environment
+    /// variables. Files which are only found in the `protoc` include directory, like the well
+    /// known types, are not tracked. With [`skip_protoc_run`](#method.skip_protoc_run), the
+    /// file descriptor set is tracked instead of the `.proto` files.
+    ///
+    /// Once a build script emits a `rerun-if-changed` directive, Cargo no longer reruns it when
+    /// any other file of the package changes. Disable the directives if the build script reads
+    /// other files without emitting directives for them.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # let mut config = prost_build::Config::new();
+    /// config.emit_rerun_if_changed(false);
+    /// println!("cargo:rerun-if-changed=src");
+    /// config.compile_protos(&["src/items.proto"], &["src/"]).unwrap();
+    /// ```
+    pub fn emit_rerun_if_changed(&mut self, enabled: bool) -> &mut Self {
+        self.emit_rerun_if_changed = enabled;
+        self
+    }
+
     /// Configures the code generator to not strip the enum name from variant names.
     ///
     /// Protobuf enum definitions commonly include the enum name as a prefix of every variant name.
@@ -631,6 +1259,102 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to check the generated files on disk, instead of writing
+    /// them.
+    ///
+    /// The code is generated in memory and compared to the files in the output directory. If any
+    /// file is stale or missing, compiling fails with a [`CheckError`], which lists the files
+    /// and has a unified diff for every stale file.
+    ///
+    /// The output directory is also searched for files written by `prost-build` which are no
+    /// longer generated, such as the code of a removed package. When other `Config`s write to the
+    /// same directory, exclude their files with [`check_only_ignore`](#method.check_only_ignore).
+    ///
+    /// This is meant for generated code which is checked in, typically with
+    /// [`out_dir`](#method.out_dir) set to a directory under `src/`, so that CI can fail when it
+    /// is out of date.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use prost_build::{CheckError, Config};
+    /// let result = Config::new()
+    ///     .out_dir("src/generated")
+    ///     .check_only()
+    ///     .compile_protos(&["src/frontend.proto", "src/backend.proto"], &["src"]);
+    ///
+    /// if let Err(error) = result {
+    ///     if let Some(check) = error.get_ref().and_then(|e| e.downcast_ref::<CheckError>()) {
+    ///         for file in check.stale() {
+    ///             eprintln!("stale: {}", file.path().display());
+    ///         }
+    ///     }
+    ///     panic!("{}", error);
+    /// }
+    /// ```
+    ///
+    /// [`CheckError`]: crate::CheckError
+    pub fn check_only(&mut self) -> &mut Self {
+        self.check_only = true;
+        self
+    }
+
+    /// Excludes paths in the output directory from the search for extra files in
+    /// [`check_only`](#method.check_only) mode.
+    ///
+    /// Use this for the files of other `Config`s which write to the same output directory.
+    ///
+    /// # Arguments
+    ///
+    /// **`paths`** - paths of files or directories, relative to the output directory.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// // The `vendor` directory and `other.rs` are generated by another `Config`.
+    /// config
+    ///     .out_dir("src/generated")
+    ///     .check_only()
+    ///     .check_only_ignore(["vendor", "other.rs"]);
+    /// ```
+    pub fn check_only_ignore<I, P>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.check_only_ignore = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Configures the code generator to write one Rust file for every `.proto` file.
+    ///
+    /// By default, the code of all `.proto` files of a Protobuf package is written to a single
+    /// file for the package, such as `foo.bar.rs` for the `foo.bar` package. With this option,
+    /// the code of every `.proto` file is written to its own file in a directory for the
+    /// package, and the file of the package includes them. For example, the code of
+    /// `foo/bar/baz.proto` in the `foo.bar` package is written to `foo.bar/foo/bar/baz.rs`, and
+    /// `foo.bar.rs` contains:
+    ///
+    /// ```rust,ignore
+    /// include!(concat!(env!("OUT_DIR"), "/foo.bar/foo/bar/baz.rs"));
+    /// ```
+    ///
+    /// The file of the package, and the [`include_file`](#method.include_file), are included
+    /// the same way as before. Packages spread over many `.proto` files no longer end up in a
+    /// single large file, which makes the generated code easier to read and diff.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.file_per_proto();
+    /// ```
+    pub fn file_per_proto(&mut self) -> &mut Self {
+        self.file_per_proto = true;
+        self
+    }
+
     /// Configures the code generator to include type names.
     ///
     /// Message types will implement `Name` trait, which provides type and package name.
@@ -640,6 +1364,19 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to include field number and name constants.
+    ///
+    /// For every field `bar` of a message `Foo`, the associated constants
+    /// `Foo::BAR_FIELD_NUMBER: u32` and `Foo::BAR_FIELD_NAME: &str` are generated. `Foo::FIELDS`
+    /// lists a [`prost::FieldInfo`][1] with the number, Protobuf name, JSON name and Rust
+    /// identifier of every field, in declaration order. The constants don't require `std`.
+    ///
+    /// [1]: https://docs.rs/prost/latest/prost/struct.FieldInfo.html
+    pub fn enable_field_constants(&mut self) -> &mut Self {
+        self.enable_field_constants = true;
+        self
+    }
+
     /// Specify domain names to use with message type URLs.
     ///
     /// # Domains
@@ -772,6 +1509,32 @@ impl Config {
         self
     }
 
+    /// Configures the code generator to write the implementations of the `Message`,
+    /// `Enumeration` and `Oneof` traits into the generated code, instead of deriving them.
+    ///
+    /// The derive macros of `prost` are then expanded once by the build script, rather than every
+    /// time the crate including the generated code is compiled, which can speed up the build of
+    /// crates with many messages. If all messages are generated this way, the `derive` feature of
+    /// `prost` can be disabled.
+    ///
+    /// The written implementations call internal functions of `prost`, which are not covered by
+    /// semver, so the `prost` runtime must be exactly the same version as `prost-build`. Each file
+    /// with expanded implementations asserts this at compile time.
+    ///
+    /// This requires the `expand-derives` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # let mut config = prost_build::Config::new();
+    /// config.expand_derives();
+    /// ```
+    #[cfg(feature = "expand-derives")]
+    pub fn expand_derives(&mut self) -> &mut Self {
+        self.expand_derives = true;
+        self
+    }
+
     /// Compile a [`FileDescriptorSet`] into Rust files during a Cargo build with
     /// additional code generator configuration options.
     ///
@@ -824,14 +1587,19 @@ impl Config {
             })
             .collect::<HashMap<Module, String>>();
 
-        let modules = self.generate(requests)?;
+        let mut output = Output::new(self.check_only);
+        let modules = if self.file_per_proto {
+            self.generate_per_file(requests, &target, target_is_env, &mut output)?
+        } else {
+            self.generate(requests)?
+        };
         for (module, content) in &modules {
             let file_name = file_names
                 .get(module)
                 .expect("every module should have a filename");
             let output_path = target.join(file_name);
 
-            write_file_if_changed(&output_path, content.as_bytes())?;
+            output.write(&output_path, content.as_bytes())?;
         }
 
         if let Some(ref include_file) = self.include_file {
@@ -846,10 +1614,10 @@ impl Config {
                 &file_names,
             )?;
 
-            write_file_if_changed(&path, &buffer)?;
+            output.write(&path, &buffer)?;
         }
 
-        Ok(())
+        output.finish(&target, &self.check_only_ignore)
     }
 
     /// Loads `.proto` files as a [`FileDescriptorSet`]. This allows inspection of the descriptors
@@ -882,7 +1650,6 @@ impl Config {
     ///   config.compile_fds(file_descriptor_set)
     /// }
     /// ```
-
     pub fn load_fds(
         &mut self,
         protos: &[impl AsRef<Path>],
@@ -972,10 +1739,54 @@ impl Config {
                 format!("invalid FileDescriptorSet: {}", error),
             )
         })?;
+        // Custom options are not decoded by `prost-types`, so keep the encoded options for
+        // code generator hooks.
+        self.raw_options
+            .extend(hook::raw_options(&buf).unwrap_or_default());
+
+        if self.emit_rerun_if_changed {
+            print!("{}", self.rerun_directives(&file_descriptor_set, includes));
+        }
 
         Ok(file_descriptor_set)
     }
 
+    /// Returns the Cargo directives to rerun the build script when the inputs of `protoc` change.
+    fn rerun_directives(
+        &self,
+        file_descriptor_set: &FileDescriptorSet,
+        includes: &[impl AsRef<Path>],
+    ) -> String {
+        let mut directives = String::new();
+        directives.push_str("cargo:rerun-if-env-changed=PROTOC\n");
+        directives.push_str("cargo:rerun-if-env-changed=PROTOC_INCLUDE\n");
+        let mut rerun_if_changed = |path: &Path| {
+            directives.push_str(&format!("cargo:rerun-if-changed={}\n", path.display()));
+        };
+        #[cfg(feature = "config-file")]
+        for path in &self.config_files {
+            rerun_if_changed(path);
+        }
+
+        if self.skip_protoc_run {
+            if let Some(path) = &self.file_descriptor_set_path {
+                rerun_if_changed(path);
+            }
+        } else {
+            // Resolve the files like `protoc` does, in the order of the include paths.
+            for file in &file_descriptor_set.file {
+                let source = includes
+                    .iter()
+                    .map(|include| include.as_ref().join(file.name()))
+                    .find(|path| path.is_file());
+                if let Some(path) = source {
+                    rerun_if_changed(&path);
+                }
+            }
+        }
+        directives
+    }
+
     /// Compile `.proto` files into Rust files during a Cargo build with additional code generator
     /// configuration options.
     ///
@@ -985,6 +1796,9 @@ impl Config {
     ///
     /// The `protos` and `includes` arguments are ignored if `skip_protoc_run` is specified.
     ///
+    /// Cargo is told to rerun the build script when the `.proto` files or the files they import
+    /// change, unless disabled with [`emit_rerun_if_changed`](#method.emit_rerun_if_changed).
+    ///
     /// # Example `build.rs`
     ///
     /// ```rust,no_run
@@ -1001,12 +1815,6 @@ impl Config {
         protos: &[impl AsRef<Path>],
         includes: &[impl AsRef<Path>],
     ) -> Result<()> {
-        // TODO: This should probably emit 'rerun-if-changed=PATH' directives for cargo, however
-        // according to [1] if any are output then those paths replace the default crate root,
-        // which is undesirable. Figure out how to do it in an additive way; perhaps gcc-rs has
-        // this figured out.
-        // [1]: http://doc.crates.io/build-script.html#outputs-of-the-build-script
-
         let file_descriptor_set = self.load_fds(protos, includes)?;
 
         self.compile_fds(file_descriptor_set)
@@ -1078,54 +1886,303 @@ impl Config {
         requests: Vec<(Module, FileDescriptorProto)>,
     ) -> Result<HashMap<Module, String>> {
         let mut modules = HashMap::new();
+        let (files, packages) = self.generate_files(requests)?;
+        for file in files {
+            modules
+                .entry(file.module)
+                .or_insert_with(String::new)
+                .push_str(&file.code);
+        }
+
+        self.finalize_packages(packages, &mut modules);
+
+        for buf in modules.values_mut() {
+            self.finish_file(buf)?;
+        }
+
+        Ok(modules)
+    }
+
+    /// Processes a set of modules and file descriptors like [`Self::generate()`], and writes the
+    /// code of every file descriptor to its own file in `target` with `output`.
+    ///
+    /// Returns a map of modules to the contents of their files, which include the files of the
+    /// module.
+    fn generate_per_file(
+        &mut self,
+        requests: Vec<(Module, FileDescriptorProto)>,
+        target: &Path,
+        target_is_env: bool,
+        output: &mut Output,
+    ) -> Result<HashMap<Module, String>> {
+        let mut modules = HashMap::new();
+        let (files, packages) = self.generate_files(requests)?;
+        for mut file in files {
+            let package_file_name = file.module.to_file_name_or(&self.default_package_filename);
+            let file_name = format!(
+                "{}/{}.rs",
+                package_file_name.trim_end_matches(".rs"),
+                file.name.trim_end_matches(".proto")
+            );
+
+            let include = if target_is_env {
+                format!(
+                    "include!(concat!(env!(\"OUT_DIR\"), \"/{}\"));\n",
+                    file_name
+                )
+            } else {
+                format!("include!(\"{}\");\n", file_name)
+            };
+            modules
+                .entry(file.module)
+                .or_insert_with(String::new)
+                .push_str(&include);
+
+            self.finish_file(&mut file.code)?;
+            let output_path = target.join(&file_name);
+            if let Some(parent) = output_path.parent() {
+                output.create_dir_all(parent)?;
+            }
+            output.write(&output_path, file.code.as_bytes())?;
+        }
+
+        self.finalize_packages(packages, &mut modules);
+
+        for buf in modules.values_mut() {
+            self.finish_file(buf)?;
+        }
+
+        Ok(modules)
+    }
+
+    /// Generates the code of every file descriptor, returning the files with code, and the
+    /// packages with services.
+    fn generate_files(
+        &mut self,
+        requests: Vec<(Module, FileDescriptorProto)>,
+    ) -> Result<(Vec<GeneratedFile>, HashMap<Module, String>)> {
+        let mut files = Vec::new();
         let mut packages = HashMap::new();
 
-        let message_graph = MessageGraph::new(requests.iter().map(|x| &x.1), self.boxed.clone());
+        let mut message_graph = MessageGraph::new(
+            requests.iter().map(|x| &x.1),
+            self.boxed.clone(),
+            self.shared.clone(),
+        );
+        if !self.generate_only.is_empty() {
+            let roots = self.generate_only_roots(&message_graph, &requests)?;
+            message_graph.generate_only(roots);
+        }
+        if !self.skip.is_empty() {
+            self.check_skipped_enum_values(&message_graph, &requests)?;
+        }
         let extern_paths = ExternPaths::new(&self.extern_paths, self.prost_types)
             .map_err(|error| Error::new(ErrorKind::InvalidInput, error))?;
 
         for (request_module, request_fd) in requests {
+            let is_generated = |name: &str| {
+                let fq_name = fq_name(&request_fd, name);
+                message_graph.is_generated(&fq_name) && self.skip.get_first(&fq_name).is_none()
+            };
+            let has_types = request_fd
+                .message_type
+                .iter()
+                .map(|message| message.name())
+                .chain(request_fd.enum_type.iter().map(|desc| desc.name()))
+                .any(is_generated);
+            let has_services = request_fd
+                .service
+                .iter()
+                .any(|service| is_generated(service.name()));
+            // Skip files without types reachable from the `generate_only` roots, or with only
+            // skipped types
+            if (!self.generate_only.is_empty() || !self.skip.is_empty())
+                && !has_types
+                && !has_services
+            {
+                continue;
+            }
             // Only record packages that have services
-            if !request_fd.service.is_empty() {
+            if has_services {
                 packages.insert(request_module.clone(), request_fd.package().to_string());
             }
-            let buf = modules
-                .entry(request_module.clone())
-                .or_insert_with(String::new);
-            CodeGenerator::generate(self, &message_graph, &extern_paths, request_fd, buf);
-            if buf.is_empty() {
-                // Did not generate any code, remove from list to avoid inclusion in include file or output file list
-                modules.remove(&request_module);
+            let file_name = request_fd.name().to_string();
+            let mut buf = String::new();
+            CodeGenerator::generate(self, &message_graph, &extern_paths, request_fd, &mut buf);
+            // Did not generate any code, skip the file to avoid inclusion in include file or
+            // output file list
+            if !buf.is_empty() {
+                files.push(GeneratedFile {
+                    module: request_module,
+                    name: file_name,
+                    code: buf,
+                });
             }
         }
 
+        Ok((files, packages))
+    }
+
+    /// Returns the roots of the type graph for `generate_only`, with the request and response
+    /// types of the methods of root services.
+    fn generate_only_roots(
+        &self,
+        message_graph: &MessageGraph,
+        requests: &[(Module, FileDescriptorProto)],
+    ) -> Result<Vec<String>> {
+        let mut roots = Vec::new();
+        for root in &self.generate_only {
+            roots.push(root.clone());
+            if message_graph.get_message(root).is_some() {
+                continue;
+            }
+            let service = requests.iter().find_map(|(_, file)| {
+                file.service
+                    .iter()
+                    .find(|service| fq_name(file, service.name()) == *root)
+            });
+            match service {
+                Some(service) => {
+                    for method in &service.method {
+                        roots.push(method.input_type().to_string());
+                        roots.push(method.output_type().to_string());
+                    }
+                }
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "generate_only root {} is not a fully qualified message or service",
+                            root
+                        ),
+                    ))
+                }
+            }
+        }
+        Ok(roots)
+    }
+
+    /// Checks that no enum value skipped with `skip` is still used, either as the first value of
+    /// its enum, which is the default value, or as the `[default = ...]` of a generated field.
+    fn check_skipped_enum_values(
+        &self,
+        message_graph: &MessageGraph,
+        requests: &[(Module, FileDescriptorProto)],
+    ) -> Result<()> {
+        // Values of skipped enums are not checked, since fields of skipped types are skipped too.
+        let is_value_skipped = |fq_enum_name: &str, value: &str| {
+            self.skip.get_first(fq_enum_name).is_none()
+                && self.skip.get_first_field(fq_enum_name, value).is_some()
+        };
+        let error = |fq_enum_name: &str, value: &str, usage: String| {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("skipped enum value {}.{} is {}", fq_enum_name, value, usage),
+            ))
+        };
+
+        let mut messages = Vec::new();
+        let mut enums = Vec::new();
+        for (_, file) in requests {
+            messages.extend(
+                file.message_type
+                    .iter()
+                    .map(|message| (fq_name(file, message.name()), message)),
+            );
+            enums.extend(
+                file.enum_type
+                    .iter()
+                    .map(|desc| (fq_name(file, desc.name()), desc)),
+            );
+        }
+        while let Some((fq_message_name, message)) = messages.pop() {
+            messages.extend(
+                message
+                    .nested_type
+                    .iter()
+                    .map(|nested| (format!("{}.{}", fq_message_name, nested.name()), nested)),
+            );
+            enums.extend(
+                message
+                    .enum_type
+                    .iter()
+                    .map(|desc| (format!("{}.{}", fq_message_name, desc.name()), desc)),
+            );
+            if !message_graph.is_generated(&fq_message_name) {
+                continue;
+            }
+            for field in &message.field {
+                if field.r#type() == Type::Enum
+                    && field.default_value.is_some()
+                    && is_value_skipped(field.type_name(), field.default_value())
+                    && self
+                        .skip
+                        .get_first_field(&fq_message_name, field.name())
+                        .is_none()
+                {
+                    return error(
+                        field.type_name(),
+                        field.default_value(),
+                        format!("the default of field {}.{}", fq_message_name, field.name()),
+                    );
+                }
+            }
+        }
+        for (fq_enum_name, desc) in enums {
+            if let Some(value) = desc.value.first() {
+                if message_graph.is_generated(&fq_enum_name)
+                    && is_value_skipped(&fq_enum_name, value.name())
+                {
+                    return error(
+                        &fq_enum_name,
+                        value.name(),
+                        "the first value, which is the default of the enum".to_string(),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize_packages(
+        &mut self,
+        packages: HashMap<Module, String>,
+        modules: &mut HashMap<Module, String>,
+    ) {
         if let Some(ref mut service_generator) = self.service_generator {
             for (module, package) in packages {
                 let buf = modules.get_mut(&module).unwrap();
                 service_generator.finalize_package(&package, buf);
             }
         }
+    }
+
+    /// Expands, formats and adds the header to the generated code of a file.
+    fn finish_file(&self, buf: &mut String) -> Result<()> {
+        #[cfg(feature = "expand-derives")]
+        if self.expand_derives {
+            let prost_path = self.prost_path.as_deref().unwrap_or("::prost");
+            *buf = crate::expand::expand_derives(buf, prost_path)?;
+        }
 
         #[cfg(feature = "format")]
         if self.fmt {
-            for buf in modules.values_mut() {
-                let file = syn::parse_file(buf).unwrap();
-                let formatted = prettyplease::unparse(&file);
-                *buf = formatted;
-            }
+            let file = syn::parse_file(buf).unwrap();
+            let formatted = prettyplease::unparse(&file);
+            *buf = formatted;
         }
 
-        self.add_generated_modules(&mut modules);
-
-        Ok(modules)
+        *buf = "// This file is @generated by prost-build.\n".to_string() + buf;
+        Ok(())
     }
+}
 
-    fn add_generated_modules(&mut self, modules: &mut HashMap<Module, String>) {
-        for buf in modules.values_mut() {
-            let with_generated = "// This file is @generated by prost-build.\n".to_string() + buf;
-            *buf = with_generated;
-        }
-    }
+/// The generated code of a file descriptor.
+struct GeneratedFile {
+    module: Module,
+    /// The name of the file descriptor, such as `foo/bar.proto`.
+    name: String,
+    code: String,
 }
 
 /// Write a slice as the entire contents of a file.
@@ -1133,7 +2190,7 @@ impl Config {
 /// This function will create a file if it does not exist,
 /// and will entirely replace its contents if it does. When
 /// the contents is already correct, it doesn't touch to the file.
-fn write_file_if_changed(path: &Path, content: &[u8]) -> std::io::Result<()> {
+pub(crate) fn write_file_if_changed(path: &Path, content: &[u8]) -> std::io::Result<()> {
     let previous_content = fs::read(path);
 
     if previous_content
@@ -1148,59 +2205,110 @@ fn write_file_if_changed(path: &Path, content: &[u8]) -> std::io::Result<()> {
     }
 }
 
+/// Returns the fully qualified name of the top level type `name` of `file`.
+fn fq_name(file: &FileDescriptorProto, name: &str) -> String {
+    if file.package().is_empty() {
+        format!(".{}", name)
+    } else {
+        format!(".{}.{}", file.package(), name)
+    }
+}
+
 impl default::Default for Config {
     fn default() -> Config {
         Config {
             file_descriptor_set_path: None,
             service_generator: None,
+            code_generator_hook: None,
+            raw_options: HashMap::new(),
             map_type: PathMap::default(),
+            map_hasher: PathMap::default(),
             bytes_type: PathMap::default(),
             type_attributes: PathMap::default(),
             message_attributes: PathMap::default(),
             enum_attributes: PathMap::default(),
             field_attributes: PathMap::default(),
             boxed: PathMap::default(),
+            shared: PathMap::default(),
+            max_len: PathMap::default(),
+            max_count: PathMap::default(),
+            builders: PathMap::default(),
             prost_types: true,
+            primitive_wrapper_types: false,
             strip_enum_prefix: true,
             out_dir: None,
             extern_paths: Vec::new(),
             default_package_filename: "_".to_string(),
             enable_type_names: false,
+            enable_field_constants: false,
             type_name_domains: PathMap::default(),
             protoc_args: Vec::new(),
             protoc_executable: protoc_from_env(),
             disable_comments: PathMap::default(),
             skip_debug: PathMap::default(),
+            message_meta: PathMap::default(),
+            max_encoded_len: PathMap::default(),
+            table_driven: PathMap::default(),
+            generate_only: Vec::new(),
+            skip: PathMap::default(),
+            renames: HashMap::new(),
             skip_protoc_run: false,
+            emit_rerun_if_changed: true,
             include_file: None,
+            file_per_proto: false,
+            check_only: false,
+            check_only_ignore: Vec::new(),
             prost_path: None,
+            #[cfg(feature = "config-file")]
+            config_files: Vec::new(),
             #[cfg(feature = "format")]
             fmt: true,
+            #[cfg(feature = "expand-derives")]
+            expand_derives: false,
         }
     }
 }
 
 impl fmt::Debug for Config {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt.debug_struct("Config")
+        let mut debug = fmt.debug_struct("Config");
+        debug
             .field("file_descriptor_set_path", &self.file_descriptor_set_path)
             .field("service_generator", &self.service_generator.is_some())
+            .field("code_generator_hook", &self.code_generator_hook.is_some())
             .field("map_type", &self.map_type)
+            .field("map_hasher", &self.map_hasher)
             .field("bytes_type", &self.bytes_type)
             .field("type_attributes", &self.type_attributes)
             .field("field_attributes", &self.field_attributes)
+            .field("max_len", &self.max_len)
+            .field("max_count", &self.max_count)
             .field("prost_types", &self.prost_types)
+            .field("primitive_wrapper_types", &self.primitive_wrapper_types)
             .field("strip_enum_prefix", &self.strip_enum_prefix)
             .field("out_dir", &self.out_dir)
             .field("extern_paths", &self.extern_paths)
             .field("default_package_filename", &self.default_package_filename)
             .field("enable_type_names", &self.enable_type_names)
+            .field("enable_field_constants", &self.enable_field_constants)
             .field("type_name_domains", &self.type_name_domains)
             .field("protoc_args", &self.protoc_args)
             .field("disable_comments", &self.disable_comments)
             .field("skip_debug", &self.skip_debug)
+            .field("message_meta", &self.message_meta)
+            .field("max_encoded_len", &self.max_encoded_len)
+            .field("table_driven", &self.table_driven)
+            .field("generate_only", &self.generate_only)
+            .field("skip", &self.skip)
+            .field("renames", &self.renames)
             .field("prost_path", &self.prost_path)
-            .finish()
+            .field("emit_rerun_if_changed", &self.emit_rerun_if_changed)
+            .field("file_per_proto", &self.file_per_proto)
+            .field("check_only", &self.check_only)
+            .field("check_only_ignore", &self.check_only_ignore);
+        #[cfg(feature = "expand-derives")]
+        debug.field("expand_derives", &self.expand_derives);
+        debug.finish()
     }
 }
 
@@ -1345,4 +2453,49 @@ mod tests {
             .unwrap_err();
         assert_eq!(err.to_string(), "OUT_DIR environment variable is not set")
     }
+
+    #[test]
+    fn test_rerun_directives() {
+        let includes = [
+            "path-does-not-exist",
+            "src/fixtures/file_per_proto",
+            "src/fixtures/imports_empty",
+        ];
+        let mut config = Config::new();
+        let fds = config
+            .load_fds(
+                &[
+                    "src/fixtures/file_per_proto/root.proto",
+                    "src/fixtures/imports_empty/imports_empty.proto",
+                ],
+                &includes,
+            )
+            .unwrap();
+
+        // Imports are tracked, except for the well known types of `protoc`.
+        let mut expected = "cargo:rerun-if-env-changed=PROTOC\n\
+                            cargo:rerun-if-env-changed=PROTOC_INCLUDE\n"
+            .to_string();
+        for (include, file) in [
+            ("src/fixtures/file_per_proto", "shop/items.proto"),
+            ("src/fixtures/file_per_proto", "shop/orders.proto"),
+            ("src/fixtures/file_per_proto", "root.proto"),
+            ("src/fixtures/imports_empty", "imports_empty.proto"),
+        ] {
+            let path = Path::new(include).join(file);
+            expected += &format!("cargo:rerun-if-changed={}\n", path.display());
+        }
+        assert_eq!(config.rerun_directives(&fds, &includes), expected);
+
+        // Without `protoc`, the file descriptor set is tracked.
+        config
+            .skip_protoc_run()
+            .file_descriptor_set_path("descriptors.bin");
+        assert_eq!(
+            config.rerun_directives(&fds, &includes),
+            "cargo:rerun-if-env-changed=PROTOC\n\
+             cargo:rerun-if-env-changed=PROTOC_INCLUDE\n\
+             cargo:rerun-if-changed=descriptors.bin\n"
+        );
+    }
 }
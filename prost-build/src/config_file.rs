@@ -0,0 +1,290 @@
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::{BytesType, Config, MapType};
+
+/// The contents of a configuration file loaded with [`Config::load_file`].
+///
+/// Every key is named after the [`Config`] method it corresponds to.
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    btree_map: Vec<String>,
+    index_map: Vec<String>,
+    hashbrown_map: Vec<String>,
+    map_hasher: Paths<String>,
+    bytes: Vec<String>,
+    field_attribute: Paths<Attributes>,
+    type_attribute: Paths<Attributes>,
+    message_attribute: Paths<Attributes>,
+    enum_attribute: Paths<Attributes>,
+    boxed: Vec<String>,
+    shared: Vec<String>,
+    max_len: Paths<usize>,
+    max_count: Paths<usize>,
+    generate_builders: Vec<String>,
+    compile_well_known_types: Option<bool>,
+    primitive_wrapper_types: Option<bool>,
+    disable_comments: Vec<String>,
+    skip_debug: Vec<String>,
+    message_meta: Vec<String>,
+    max_encoded_len: Vec<String>,
+    table_driven: Vec<String>,
+    generate_only: Vec<String>,
+    skip: Vec<String>,
+    rename: Paths<String>,
+    extern_path: Paths<String>,
+    file_descriptor_set_path: Option<PathBuf>,
+    skip_protoc_run: Option<bool>,
+    emit_rerun_if_changed: Option<bool>,
+    retain_enum_prefix: Option<bool>,
+    out_dir: Option<PathBuf>,
+    default_package_filename: Option<String>,
+    check_only: Option<bool>,
+    check_only_ignore: Vec<PathBuf>,
+    file_per_proto: Option<bool>,
+    enable_type_names: Option<bool>,
+    enable_field_constants: Option<bool>,
+    type_name_domain: Paths<String>,
+    prost_path: Option<String>,
+    protoc_args: Vec<String>,
+    protoc_executable: Option<PathBuf>,
+    include_file: Option<PathBuf>,
+    format: Option<bool>,
+    expand_derives: Option<bool>,
+}
+
+/// Reads the configuration file at `path`, and adds its settings to `config`.
+pub(crate) fn load(config: &mut Config, path: &Path) -> Result<()> {
+    let contents = fs::read_to_string(path).map_err(|error| {
+        Error::new(
+            error.kind(),
+            format!("failed to read config file {}: {}", path.display(), error),
+        )
+    })?;
+    let file: ConfigFile = toml::from_str(&contents).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid config file {}: {}", path.display(), error),
+        )
+    })?;
+    config.config_files.push(path.to_path_buf());
+    file.apply(config).map_err(|message| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid config file {}: {}", path.display(), message),
+        )
+    })
+}
+
+impl ConfigFile {
+    fn apply(self, config: &mut Config) -> std::result::Result<(), String> {
+        for (paths, map_type) in [
+            (self.btree_map, MapType::BTreeMap),
+            (self.index_map, MapType::IndexMap),
+            (self.hashbrown_map, MapType::Hashbrown),
+        ] {
+            for path in paths {
+                config.map_type.insert(path, map_type);
+            }
+        }
+        for (path, hasher) in self.map_hasher.0 {
+            config.map_hasher.insert(path, hasher);
+        }
+        for path in self.bytes {
+            config.bytes_type.insert(path, BytesType::Bytes);
+        }
+        for (attributes, paths) in [
+            (&mut config.field_attributes, self.field_attribute),
+            (&mut config.type_attributes, self.type_attribute),
+            (&mut config.message_attributes, self.message_attribute),
+            (&mut config.enum_attributes, self.enum_attribute),
+        ] {
+            for (path, Attributes(values)) in paths.0 {
+                for value in values {
+                    attributes.insert(path.clone(), value);
+                }
+            }
+        }
+        for (set, paths) in [
+            (&mut config.boxed, self.boxed),
+            (&mut config.shared, self.shared),
+            (&mut config.builders, self.generate_builders),
+            (&mut config.disable_comments, self.disable_comments),
+            (&mut config.skip_debug, self.skip_debug),
+            (&mut config.message_meta, self.message_meta),
+            (&mut config.max_encoded_len, self.max_encoded_len),
+            (&mut config.table_driven, self.table_driven),
+            (&mut config.skip, self.skip),
+        ] {
+            for path in paths {
+                set.insert(path, ());
+            }
+        }
+        for (limits, paths) in [
+            (&mut config.max_len, self.max_len),
+            (&mut config.max_count, self.max_count),
+        ] {
+            for (path, limit) in paths.0 {
+                limits.insert(path, limit);
+            }
+        }
+        config.generate_only.extend(self.generate_only);
+        config.check_only_ignore.extend(self.check_only_ignore);
+        config.renames.extend(self.rename.0);
+        config.extern_paths.extend(self.extern_path.0);
+        for (path, domain) in self.type_name_domain.0 {
+            config.type_name_domains.insert(path, domain);
+        }
+        config
+            .protoc_args
+            .extend(self.protoc_args.into_iter().map(Into::into));
+
+        if let Some(enabled) = self.compile_well_known_types {
+            config.prost_types = !enabled;
+        }
+        if let Some(enabled) = self.retain_enum_prefix {
+            config.strip_enum_prefix = !enabled;
+        }
+        for (option, enabled) in [
+            (
+                &mut config.primitive_wrapper_types,
+                self.primitive_wrapper_types,
+            ),
+            (&mut config.skip_protoc_run, self.skip_protoc_run),
+            (
+                &mut config.emit_rerun_if_changed,
+                self.emit_rerun_if_changed,
+            ),
+            (&mut config.check_only, self.check_only),
+            (&mut config.file_per_proto, self.file_per_proto),
+            (&mut config.enable_type_names, self.enable_type_names),
+            (
+                &mut config.enable_field_constants,
+                self.enable_field_constants,
+            ),
+        ] {
+            if let Some(enabled) = enabled {
+                *option = enabled;
+            }
+        }
+
+        if let Some(path) = self.file_descriptor_set_path {
+            config.file_descriptor_set_path = Some(path);
+        }
+        if let Some(path) = self.out_dir {
+            config.out_dir = Some(path);
+        }
+        if let Some(filename) = self.default_package_filename {
+            config.default_package_filename = filename;
+        }
+        if let Some(path) = self.prost_path {
+            config.prost_path = Some(path);
+        }
+        if let Some(executable) = self.protoc_executable {
+            config.protoc_executable = executable;
+        }
+        if let Some(path) = self.include_file {
+            config.include_file = Some(path);
+        }
+
+        match self.format {
+            #[cfg(feature = "format")]
+            Some(enabled) => config.fmt = enabled,
+            #[cfg(not(feature = "format"))]
+            Some(true) => return Err(feature_required("format", "format")),
+            _ => {}
+        }
+        match self.expand_derives {
+            #[cfg(feature = "expand-derives")]
+            Some(enabled) => config.expand_derives = enabled,
+            #[cfg(not(feature = "expand-derives"))]
+            Some(true) => return Err(feature_required("expand_derives", "expand-derives")),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(all(feature = "format", feature = "expand-derives")))]
+fn feature_required(key: &str, feature: &str) -> String {
+    format!(
+        "`{}` requires the `{}` feature of prost-build",
+        key, feature
+    )
+}
+
+/// A table from Protobuf paths to values, in the order of the file.
+struct Paths<V>(Vec<(String, V)>);
+
+impl<V> Default for Paths<V> {
+    fn default() -> Self {
+        Paths(Vec::new())
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for Paths<V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct PathsVisitor<V>(PhantomData<V>);
+
+        impl<'de, V: Deserialize<'de>> Visitor<'de> for PathsVisitor<V> {
+            type Value = Paths<V>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a table keyed by Protobuf paths")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut paths = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    paths.push(entry);
+                }
+                Ok(Paths(paths))
+            }
+        }
+
+        deserializer.deserialize_map(PathsVisitor(PhantomData))
+    }
+}
+
+/// One or more attributes for a path.
+struct Attributes(Vec<String>);
+
+impl<'de> Deserialize<'de> for Attributes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct AttributesVisitor;
+
+        impl<'de> Visitor<'de> for AttributesVisitor {
+            type Value = Attributes;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an attribute or an array of attributes")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<Self::Value, E> {
+                Ok(Attributes(vec![value.to_string()]))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> std::result::Result<Self::Value, A::Error> {
+                let mut attributes = Vec::new();
+                while let Some(attribute) = seq.next_element()? {
+                    attributes.push(attribute);
+                }
+                Ok(Attributes(attributes))
+            }
+        }
+
+        deserializer.deserialize_any(AttributesVisitor)
+    }
+}
@@ -0,0 +1,179 @@
+//! Expansion of the `prost` derive macros into the generated code.
+
+use std::io::{Error, ErrorKind, Result};
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Attribute, Fields, Item, Path, Token};
+
+/// A derive macro of `prost`.
+#[derive(Clone, Copy)]
+enum Derive {
+    Message,
+    Enumeration,
+    Oneof,
+}
+
+/// Replaces the `Message`, `Enumeration` and `Oneof` derives of all types in the generated code
+/// with the impls they expand to.
+pub(crate) fn expand_derives(code: &str, prost_path: &str) -> Result<String> {
+    let mut file = syn::parse_file(code).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to parse generated code: {}", error),
+        )
+    })?;
+    let prost_path = normalize(prost_path);
+    if expand_items(&mut file.items, &prost_path)? {
+        file.items.insert(0, version_assertion(&prost_path)?);
+    }
+    Ok(file.into_token_stream().to_string())
+}
+
+/// Returns an assertion that the code is compiled against the version of `prost` which it was
+/// expanded by, since the expanded impls use its internal, semver exempt functions.
+fn version_assertion(prost_path: &str) -> Result<Item> {
+    let prost_path: Path = syn::parse_str(prost_path).map_err(|error| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid prost path: {}", error),
+        )
+    })?;
+    let version = env!("CARGO_PKG_VERSION");
+    let message = format!(
+        "the code was generated by `prost-build` {} with `expand_derives`, \
+         and requires exactly the same version of `prost`",
+        version
+    );
+    Ok(parse_quote! {
+        const _: () = ::core::assert!(#prost_path::encoding::is_version(#version), #message);
+    })
+}
+
+/// Expands the derives of the items, returning whether any was expanded.
+fn expand_items(items: &mut Vec<Item>, prost_path: &str) -> Result<bool> {
+    let mut expanded_any = false;
+    for mut item in std::mem::take(items) {
+        let expanded = match &mut item {
+            Item::Mod(module) => {
+                if let Some((_, items)) = &mut module.content {
+                    expanded_any |= expand_items(items, prost_path)?;
+                }
+                Vec::new()
+            }
+            Item::Struct(item_struct) => {
+                let derives = take_derives(&mut item_struct.attrs, prost_path);
+                if derives.is_empty() {
+                    Vec::new()
+                } else {
+                    let expanded = expand(item_struct.to_token_stream(), &derives)?;
+                    strip_attributes(&mut item_struct.attrs);
+                    strip_fields(&mut item_struct.fields);
+                    expanded
+                }
+            }
+            Item::Enum(item_enum) => {
+                let derives = take_derives(&mut item_enum.attrs, prost_path);
+                if derives.is_empty() {
+                    Vec::new()
+                } else {
+                    let expanded = expand(item_enum.to_token_stream(), &derives)?;
+                    strip_attributes(&mut item_enum.attrs);
+                    for variant in &mut item_enum.variants {
+                        strip_attributes(&mut variant.attrs);
+                        strip_fields(&mut variant.fields);
+                    }
+                    expanded
+                }
+            }
+            _ => Vec::new(),
+        };
+        expanded_any |= !expanded.is_empty();
+        items.push(item);
+        items.extend(expanded);
+    }
+    Ok(expanded_any)
+}
+
+/// Removes the `prost` derives from the `#[derive]` attributes, returning them.
+fn take_derives(attrs: &mut Vec<Attribute>, prost_path: &str) -> Vec<Derive> {
+    let mut derives = Vec::new();
+    attrs.retain_mut(|attr| {
+        if !attr.path().is_ident("derive") {
+            return true;
+        }
+        let paths = match attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated) {
+            Ok(paths) => paths,
+            Err(_) => return true,
+        };
+        let remaining: Punctuated<Path, Token![,]> = paths
+            .into_iter()
+            .filter(|path| {
+                let derive = match normalize(&path.to_token_stream().to_string()) {
+                    path if path == format!("{}::Message", prost_path) => Derive::Message,
+                    path if path == format!("{}::Enumeration", prost_path) => Derive::Enumeration,
+                    path if path == format!("{}::Oneof", prost_path) => Derive::Oneof,
+                    _ => return true,
+                };
+                derives.push(derive);
+                false
+            })
+            .collect();
+        if remaining.is_empty() {
+            return false;
+        }
+        *attr = parse_quote!(#[derive(#remaining)]);
+        true
+    });
+    derives
+}
+
+fn expand(input: TokenStream, derives: &[Derive]) -> Result<Vec<Item>> {
+    let mut items = Vec::new();
+    for derive in derives {
+        let expanded = match derive {
+            Derive::Message => prost_derive_impl::try_message(input.clone()),
+            Derive::Enumeration => prost_derive_impl::try_enumeration(input.clone()),
+            Derive::Oneof => prost_derive_impl::try_oneof(input.clone()),
+        }
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error.to_string()))?;
+        let file: syn::File = syn::parse2(expanded).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("failed to parse expanded derive: {}", error),
+            )
+        })?;
+        items.extend(file.items.into_iter().map(allow_lints));
+    }
+    Ok(items)
+}
+
+/// Allows the lints which the expanded code triggers, and which are not reported for code
+/// expanded from a macro.
+fn allow_lints(mut item: Item) -> Item {
+    let attr: Attribute = parse_quote!(#[allow(unused_mut, clippy::all)]);
+    match &mut item {
+        Item::Impl(item_impl) => item_impl.attrs.push(attr),
+        Item::Const(item_const) => item_const.attrs.push(attr),
+        Item::Fn(item_fn) => item_fn.attrs.push(attr),
+        Item::Mod(item_mod) => item_mod.attrs.push(attr),
+        _ => {}
+    }
+    item
+}
+
+/// Removes the `#[prost(..)]` helper attributes, which are only valid with the derives.
+fn strip_attributes(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|attr| !attr.path().is_ident("prost"));
+}
+
+fn strip_fields(fields: &mut Fields) {
+    for field in fields.iter_mut() {
+        strip_attributes(&mut field.attrs);
+    }
+}
+
+fn normalize(path: &str) -> String {
+    path.split_whitespace().collect()
+}
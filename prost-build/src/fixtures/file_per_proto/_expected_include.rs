@@ -0,0 +1,5 @@
+// This file is @generated by prost-build.
+include!("_.rs");
+pub mod shop {
+    include!("shop.rs");
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use bytes::Buf;
+use prost::encoding::{decode_key, decode_varint, WireType};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    OneofDescriptorProto,
+};
+
+use crate::ast::Comments;
+
+/// A code generator hook adds attributes and items to the generated code, based on the contents
+/// of the Protobuf schema.
+///
+/// Unlike [`Config::type_attribute`] and [`Config::field_attribute`], which add fixed attributes
+/// to the types and fields matching a path, a hook can inspect every element it is called for,
+/// including its options, comments and Rust type, and decide what to generate.
+///
+/// Hooks are registered with a code generator using the [`Config::code_generator_hook`] method.
+/// Each method is called once for every generated element of its kind. The default
+/// implementations are empty and do nothing.
+///
+/// Attributes added through a hook are placed after the attributes configured by path. Items are
+/// placed in the same module as the generated type: after the message struct for messages and
+/// fields, after the enum for enums and enum values, and after the oneof enum for oneofs and
+/// their variants.
+///
+/// # Example
+///
+/// A hook deriving `serde::Serialize` for every message whose comments contain `@serialize`:
+///
+/// ```rust
+/// use prost_build::{CodeGeneratorHook, HookContext, HookOutput};
+/// use prost_types::DescriptorProto;
+///
+/// struct SerializeHook;
+///
+/// impl CodeGeneratorHook for SerializeHook {
+///     fn message(&mut self, message: &HookContext<'_, DescriptorProto>, output: &mut HookOutput) {
+///         if message.comments.leading.iter().any(|line| line.contains("@serialize")) {
+///             output.attribute("#[derive(serde::Serialize)]");
+///         }
+///     }
+/// }
+///
+/// let mut config = prost_build::Config::new();
+/// config.code_generator_hook(Box::new(SerializeHook));
+/// ```
+///
+/// [`Config::type_attribute`]: crate::Config::type_attribute
+/// [`Config::field_attribute`]: crate::Config::field_attribute
+/// [`Config::code_generator_hook`]: crate::Config::code_generator_hook
+pub trait CodeGeneratorHook {
+    /// Called for every generated message struct.
+    fn message(&mut self, _message: &HookContext<'_, DescriptorProto>, _output: &mut HookOutput) {}
+
+    /// Called for every field of a generated message, including map fields and the variants of
+    /// oneofs.
+    ///
+    /// Attributes are added to the struct field, or to the oneof variant.
+    fn field(&mut self, _field: &HookContext<'_, FieldDescriptorProto>, _output: &mut HookOutput) {}
+
+    /// Called for every generated enum.
+    fn enumeration(
+        &mut self,
+        _enumeration: &HookContext<'_, EnumDescriptorProto>,
+        _output: &mut HookOutput,
+    ) {
+    }
+
+    /// Called for every variant of a generated enum.
+    fn enum_value(
+        &mut self,
+        _value: &HookContext<'_, EnumValueDescriptorProto>,
+        _output: &mut HookOutput,
+    ) {
+    }
+
+    /// Called for every generated oneof enum.
+    fn oneof(&mut self, _oneof: &HookContext<'_, OneofDescriptorProto>, _output: &mut HookOutput) {}
+}
+
+/// The element of the Protobuf schema a [`CodeGeneratorHook`] is called for.
+#[derive(Debug)]
+pub struct HookContext<'a, D> {
+    /// The descriptor of the element.
+    pub descriptor: &'a D,
+
+    /// The fully qualified Protobuf name of the element, such as `.my.package.Message.field`.
+    ///
+    /// Enum values are named within their enum, such as `.my.package.Enum.VALUE`.
+    pub fq_name: &'a str,
+
+    /// The Rust type generated for the element.
+    ///
+    /// This is the name of the generated struct or enum for messages, enums and oneofs, the name
+    /// of the variant for enum values, and the full type of the struct field or the oneof
+    /// variant value for fields.
+    pub rust_type: &'a str,
+
+    /// The comments on the element.
+    pub comments: &'a Comments,
+
+    /// The encoded options message of the element, as found in the file descriptor set.
+    ///
+    /// Unlike the `options` of the descriptor, which are decoded with the types from
+    /// `prost-types`, this includes custom options defined as extensions. These can be read by
+    /// decoding the bytes with a message type declaring the extension fields.
+    ///
+    /// The bytes are only available if the file descriptor set was produced by
+    /// [`Config::compile_protos`] or [`Config::load_fds`]. Otherwise the known options of the
+    /// descriptor are re-encoded.
+    ///
+    /// [`Config::compile_protos`]: crate::Config::compile_protos
+    /// [`Config::load_fds`]: crate::Config::load_fds
+    pub raw_options: &'a [u8],
+}
+
+/// The attributes and items generated by a [`CodeGeneratorHook`] for an element.
+#[derive(Debug, Default)]
+pub struct HookOutput {
+    pub(crate) attributes: Vec<String>,
+    pub(crate) items: Vec<String>,
+}
+
+impl HookOutput {
+    /// Adds an attribute, such as `#[derive(Eq)]`, to the generated element.
+    pub fn attribute(&mut self, attribute: impl Into<String>) -> &mut Self {
+        self.attributes.push(attribute.into());
+        self
+    }
+
+    /// Adds an item, such as an `impl` block, next to the generated type.
+    pub fn item(&mut self, item: impl Into<String>) -> &mut Self {
+        self.items.push(item.into());
+        self
+    }
+}
+
+/// Collects the encoded options of all messages, fields, oneofs, enums and enum values in an
+/// encoded `FileDescriptorSet`, keyed by their fully qualified name.
+///
+/// Returns `None` if the descriptor set can't be parsed.
+pub(crate) fn raw_options(mut fds: &[u8]) -> Option<HashMap<String, Vec<u8>>> {
+    let mut options = HashMap::new();
+    for (tag, file) in length_delimited_fields(&mut fds)? {
+        if tag != 1 {
+            continue;
+        }
+        let fields = length_delimited_fields(&mut &file[..])?;
+        let package = fields
+            .iter()
+            .find(|(tag, _)| *tag == 2)
+            .map(|(_, package)| std::str::from_utf8(package))
+            .transpose()
+            .ok()?
+            .unwrap_or_default();
+        let scope = if package.is_empty() {
+            String::new()
+        } else {
+            format!(".{}", package)
+        };
+        for (tag, value) in &fields {
+            match tag {
+                4 => message_options(&scope, value, &mut options)?,
+                5 => enum_options(&scope, value, &mut options)?,
+                _ => {}
+            }
+        }
+    }
+    Some(options)
+}
+
+fn message_options(scope: &str, buf: &[u8], options: &mut HashMap<String, Vec<u8>>) -> Option<()> {
+    let fields = length_delimited_fields(&mut &buf[..])?;
+    let fq_name = format!("{}.{}", scope, name(&fields)?);
+    for (tag, value) in &fields {
+        match tag {
+            2 | 8 => {
+                // Fields and oneofs store their options in fields 8 and 2 respectively.
+                let options_tag = if *tag == 2 { 8 } else { 2 };
+                let fields = length_delimited_fields(&mut &value[..])?;
+                insert(&fq_name, &fields, options_tag, options)?;
+            }
+            3 => message_options(&fq_name, value, options)?,
+            4 => enum_options(&fq_name, value, options)?,
+            7 => {
+                options.insert(fq_name.clone(), value.to_vec());
+            }
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+fn enum_options(scope: &str, buf: &[u8], options: &mut HashMap<String, Vec<u8>>) -> Option<()> {
+    let fields = length_delimited_fields(&mut &buf[..])?;
+    let fq_name = format!("{}.{}", scope, name(&fields)?);
+    for (tag, value) in &fields {
+        match tag {
+            2 => {
+                let fields = length_delimited_fields(&mut &value[..])?;
+                insert(&fq_name, &fields, 3, options)?;
+            }
+            3 => {
+                options.insert(fq_name.clone(), value.to_vec());
+            }
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+/// Inserts the options stored in field `options_tag` of an element named within `scope`.
+fn insert(
+    scope: &str,
+    fields: &[(u32, &[u8])],
+    options_tag: u32,
+    options: &mut HashMap<String, Vec<u8>>,
+) -> Option<()> {
+    if let Some((_, value)) = fields.iter().find(|(tag, _)| *tag == options_tag) {
+        options.insert(format!("{}.{}", scope, name(fields)?), value.to_vec());
+    }
+    Some(())
+}
+
+fn name<'a>(fields: &[(u32, &'a [u8])]) -> Option<&'a str> {
+    let (_, name) = fields.iter().find(|(tag, _)| *tag == 1)?;
+    std::str::from_utf8(name).ok()
+}
+
+/// Returns the length-delimited fields of an encoded message, skipping all others.
+fn length_delimited_fields<'a>(buf: &mut &'a [u8]) -> Option<Vec<(u32, &'a [u8])>> {
+    let mut fields = Vec::new();
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(buf).ok()?;
+        let len = match wire_type {
+            WireType::Varint => {
+                decode_varint(buf).ok()?;
+                continue;
+            }
+            WireType::SixtyFourBit => 8,
+            WireType::ThirtyTwoBit => 4,
+            WireType::LengthDelimited => {
+                let len = usize::try_from(decode_varint(buf).ok()?).ok()?;
+                if len > buf.len() {
+                    return None;
+                }
+                fields.push((tag, &buf[..len]));
+                len
+            }
+            WireType::StartGroup | WireType::EndGroup => return None,
+        };
+        if len > buf.len() {
+            return None;
+        }
+        buf.advance(len);
+    }
+    Some(fields)
+}
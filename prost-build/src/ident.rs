@@ -1,6 +1,6 @@
 //! Utility functions for working with identifiers.
 
-use heck::{ToSnakeCase, ToUpperCamelCase};
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
 
 pub fn sanitize_identifier(s: impl AsRef<str>) -> String {
     let ident = s.as_ref();
@@ -38,6 +38,30 @@ pub fn to_upper_camel(s: impl AsRef<str>) -> String {
     sanitize_identifier(s.as_ref().to_upper_camel_case())
 }
 
+/// Converts a Protobuf field name to its default JSON name, like `protoc` does: underscores are
+/// removed, and the letter following an underscore is capitalized.
+pub fn to_json_name(s: impl AsRef<str>) -> String {
+    let mut json_name = String::new();
+    let mut capitalize_next = false;
+    for c in s.as_ref().chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+/// Converts a `snake_case` or `camelCase` identifier to an `UPPER_SNAKE` case Rust constant
+/// identifier.
+pub fn to_upper_snake(s: impl AsRef<str>) -> String {
+    sanitize_identifier(s.as_ref().to_shouty_snake_case())
+}
+
 /// Strip an enum's type name from the prefix of an enum value.
 ///
 /// This function assumes that both have been formatted to Rust's
@@ -243,6 +267,26 @@ mod tests {
         assert_eq!("Self_", &to_upper_camel("self"));
     }
 
+    #[test]
+    fn test_to_json_name() {
+        assert_eq!("", &to_json_name(""));
+        assert_eq!("foo", &to_json_name("foo"));
+        assert_eq!("fooBar", &to_json_name("foo_bar"));
+        assert_eq!("fooBar", &to_json_name("fooBar"));
+        assert_eq!("FooBar", &to_json_name("_foo_bar"));
+        assert_eq!("foo2Bar", &to_json_name("foo2_bar"));
+    }
+
+    #[test]
+    fn test_to_upper_snake() {
+        assert_eq!("", &to_upper_snake(""));
+        assert_eq!("FOO", &to_upper_snake("foo"));
+        assert_eq!("FOO_BAR", &to_upper_snake("foo_bar"));
+        assert_eq!("FOO_BAR", &to_upper_snake("fooBar"));
+        assert_eq!("FOO_BAR", &to_upper_snake("FooBar"));
+        assert_eq!("FOO_BAR2", &to_upper_snake("foo_bar2"));
+    }
+
     #[test]
     fn test_strip_enum_prefix() {
         assert_eq!(strip_enum_prefix("Foo", "FooBar"), "Bar");
@@ -84,6 +84,8 @@
 //! ## Feature Flags
 //! - `format`: Format the generated output. This feature is enabled by default.
 //! - `cleanup-markdown`: Clean up Markdown in protobuf docs. Enable this to clean up protobuf files from third parties.
+//! - `expand-derives`: Allow writing the impls of the `prost` derives into the generated code with `Config::expand_derives()`.
+//! - `config-file`: Allow loading the configuration from a TOML file with `Config::from_file()`.
 //!
 //! ### Cleaning up Markdown in code docs
 //!
@@ -141,13 +143,25 @@ use std::path::Path;
 use prost_types::FileDescriptorSet;
 
 mod ast;
-pub use crate::ast::{Comments, Method, Service};
+pub use crate::ast::{Comments, Method, Service, ServiceContext};
+
+mod async_service;
+pub use async_service::AsyncServiceGenerator;
+
+mod check;
+pub use check::{CheckError, StaleFile};
 
 mod collections;
 pub(crate) use collections::{BytesType, MapType};
 
 mod code_generator;
+#[cfg(feature = "config-file")]
+mod config_file;
+#[cfg(feature = "expand-derives")]
+mod expand;
 mod extern_paths;
+mod hook;
+pub use hook::{CodeGeneratorHook, HookContext, HookOutput};
 mod ident;
 mod message_graph;
 mod path;
@@ -179,6 +193,24 @@ pub trait ServiceGenerator {
     /// result to `buf`.
     fn generate(&mut self, service: Service, buf: &mut String);
 
+    /// Generates a Rust interface or implementation for a service with access to its context,
+    /// writing the result to `buf`.
+    ///
+    /// The context gives access to the file descriptor, the other services in the file, the
+    /// message descriptors of the method input and output types, and the resolution of Protobuf
+    /// types to Rust paths.
+    ///
+    /// This is the method called by the code generator. The default implementation ignores the
+    /// context and calls [`generate`](#tymethod.generate).
+    fn generate_with_context(
+        &mut self,
+        service: Service,
+        _context: &ServiceContext<'_>,
+        buf: &mut String,
+    ) {
+        self.generate(service, buf)
+    }
+
     /// Finalizes the generation process.
     ///
     /// In case there's something that needs to be output at the end of the generation process, it
@@ -210,7 +242,8 @@ pub trait ServiceGenerator {
 /// The generated `.rs` files are written to the Cargo `OUT_DIR` directory, suitable for use with
 /// the [include!][1] macro. See the [Cargo `build.rs` code generation][2] example for more info.
 ///
-/// This function should be called in a project's `build.rs`.
+/// This function should be called in a project's `build.rs`. It tells Cargo to rerun the build
+/// script when any of the compiled or imported `.proto` files found in **`includes`** change.
 ///
 /// # Arguments
 ///
@@ -417,6 +450,112 @@ mod tests {
         assert_eq!(state.finalized, 3);
     }
 
+    /// Implements `ServiceGenerator`, recording the context of every service.
+    struct ContextServiceGenerator {
+        contexts: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl ServiceGenerator for ContextServiceGenerator {
+        fn generate(&mut self, _service: Service, _buf: &mut String) {
+            unreachable!("the code generator calls generate_with_context");
+        }
+
+        fn generate_with_context(
+            &mut self,
+            service: Service,
+            context: &ServiceContext<'_>,
+            _buf: &mut String,
+        ) {
+            let method = &service.methods[0];
+            let input = context.message(&method.input_proto_type).unwrap();
+            self.contexts.borrow_mut().push(format!(
+                "{} in {}: {:?}, input fields: {:?}, output: {} {:?}, prost: {}",
+                service.name,
+                context.file().name(),
+                context
+                    .services()
+                    .iter()
+                    .map(|service| service.name.as_str())
+                    .collect::<Vec<_>>(),
+                input
+                    .field
+                    .iter()
+                    .map(|field| field.name())
+                    .collect::<Vec<_>>(),
+                context.resolve_ident(&method.output_proto_type),
+                context.resolve_extern(&method.output_proto_type),
+                context.prost_path(),
+            ));
+        }
+    }
+
+    #[test]
+    fn test_service_generator_context() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+        let contexts = Rc::new(RefCell::new(Vec::new()));
+
+        Config::new()
+            .service_generator(Box::new(ContextServiceGenerator {
+                contexts: Rc::clone(&contexts),
+            }))
+            .extern_path(".helloworld.Response", "::responses::Response")
+            .prost_path("::my_prost")
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &[
+                    "src/fixtures/helloworld/hello.proto",
+                    "src/fixtures/helloworld/goodbye.proto",
+                ],
+                &["src/fixtures/helloworld"],
+            )
+            .unwrap();
+
+        assert_eq!(
+            *contexts.borrow(),
+            [
+                "Greeting in hello.proto: [\"Greeting\"], input fields: [\"say\"], output: ::responses::Response Some(\"::responses::Response\"), prost: ::my_prost",
+                "Farewell in goodbye.proto: [\"Farewell\"], input fields: [\"say\"], output: ::responses::Response Some(\"::responses::Response\"), prost: ::my_prost",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_async_service_generator() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .service_generator(Box::new(AsyncServiceGenerator::new()))
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/helloworld/hello.proto"],
+                &["src/fixtures/helloworld"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("helloworld.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "pubconstGREETING_SERVICE_NAME:&str=\"helloworld.Greeting\";",
+            "pubconstGREETING_METHODS:&[&str]=&[\"Hello\"];",
+            "pubtraitGreeting:::core::marker::Send+::core::marker::Sync{",
+            "fnhello(&self,request:Message",
+            "\"Hello\"=>{letrequest=<Messageas::prost::Message>::decode(request)",
+            "::core::result::Result::Err(::prost::DispatchError::UnknownMethod(method.into())",
+        ] {
+            assert!(
+                contents.contains(expected),
+                "missing {expected} in {contents}"
+            );
+        }
+    }
+
     #[test]
     fn test_generate_message_attributes() {
         let _ = env_logger::try_init();
@@ -522,6 +661,532 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generate_map_types() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .btree_map(["Maps.ordered"])
+            .index_map(["Maps.indexed"])
+            .hashbrown_map(["Maps.hashbrown", "Maps.hashed"])
+            .map_hasher("Maps.hashed", "::my_hasher::Hasher")
+            .map_hasher("Maps.ordered", "::my_hasher::Hasher")
+            .compile_protos(
+                &["src/fixtures/map_types/map_types.proto"],
+                &["src/fixtures/map_types"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("map_types.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "#[prost(map=\"int32,int32\",tag=\"1\")]pubdefault_map:::std::collections::HashMap<i32,i32>,",
+            "#[prost(btree_map=\"int32,int32\",tag=\"2\")]pubordered:::prost::alloc::collections::BTreeMap<i32,i32>,",
+            "#[prost(index_map=\"int32,int32\",tag=\"3\")]pubindexed:::prost::indexmap::IndexMap<i32,i32>,",
+            "#[prost(hashbrown_map=\"int32,int32\",tag=\"4\")]pubhashbrown:::prost::hashbrown::HashMap<i32,i32>,",
+            "#[prost(hashbrown_map=\"int32,int32\",tag=\"5\")]pubhashed:::prost::hashbrown::HashMap<i32,i32,::my_hasher::Hasher>,",
+        ] {
+            assert!(contents.contains(expected), "missing {expected} in {contents}");
+        }
+    }
+
+    #[test]
+    fn test_generate_shared_fields() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .boxed("Container.unshared")
+            .shared([
+                "Container.snapshot",
+                "Container.history",
+                "Container.data.current",
+                "Container.data.name",
+            ])
+            .compile_protos(
+                &["src/fixtures/shared/shared.proto"],
+                &["src/fixtures/shared"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("shared.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "#[prost(message,optional,shared,tag=\"1\")]pubsnapshot:::core::option::Option<::prost::alloc::sync::Arc<Snapshot>>,",
+            "#[prost(message,repeated,shared,tag=\"2\")]pubhistory:::prost::alloc::vec::Vec<::prost::alloc::sync::Arc<Snapshot>>,",
+            "#[prost(message,optional,boxed,tag=\"5\")]pubunshared:::core::option::Option<::prost::alloc::boxed::Box<Snapshot>>,",
+            "Current(::prost::alloc::sync::Arc<super::Snapshot>),",
+            "Name(::prost::alloc::string::String),",
+        ] {
+            assert!(contents.contains(expected), "missing {expected} in {contents}");
+        }
+    }
+
+    /// Implements `CodeGeneratorHook`, recording the elements it is called for.
+    struct MockHook {
+        calls: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl MockHook {
+        fn record<D>(&self, kind: &str, context: &HookContext<'_, D>) {
+            self.calls.borrow_mut().push(format!(
+                "{} {} {}",
+                kind, context.fq_name, context.rust_type
+            ));
+        }
+    }
+
+    /// Returns the value of a custom `bool` or `string` option from the encoded options.
+    fn custom_option(mut raw_options: &[u8], number: u32) -> Option<String> {
+        use prost::encoding::{self, DecodeContext, WireType};
+
+        while !raw_options.is_empty() {
+            let (tag, wire_type) = encoding::decode_key(&mut raw_options).unwrap();
+            if tag == number {
+                return Some(match wire_type {
+                    WireType::Varint => encoding::decode_varint(&mut raw_options)
+                        .unwrap()
+                        .to_string(),
+                    _ => {
+                        let mut value = String::new();
+                        encoding::string::merge(
+                            wire_type,
+                            &mut value,
+                            &mut raw_options,
+                            DecodeContext::default(),
+                        )
+                        .unwrap();
+                        value
+                    }
+                });
+            }
+            encoding::skip_field(wire_type, tag, &mut raw_options, DecodeContext::default())
+                .unwrap();
+        }
+        None
+    }
+
+    impl CodeGeneratorHook for MockHook {
+        fn message(
+            &mut self,
+            message: &HookContext<'_, prost_types::DescriptorProto>,
+            output: &mut HookOutput,
+        ) {
+            self.record("message", message);
+            assert_eq!(message.comments.leading, [" An account."]);
+            if let Some(table) = custom_option(message.raw_options, 50001) {
+                output.item(format!(
+                    "impl {} {{ pub const TABLE: &'static str = {:?}; }}",
+                    message.rust_type, table
+                ));
+            }
+        }
+
+        fn field(
+            &mut self,
+            field: &HookContext<'_, prost_types::FieldDescriptorProto>,
+            output: &mut HookOutput,
+        ) {
+            self.record("field", field);
+            if custom_option(field.raw_options, 50000).as_deref() == Some("1") {
+                output.attribute("#[cfg_attr(feature = \"serde\", serde(skip))]");
+            }
+        }
+
+        fn enumeration(
+            &mut self,
+            enumeration: &HookContext<'_, prost_types::EnumDescriptorProto>,
+            output: &mut HookOutput,
+        ) {
+            self.record("enum", enumeration);
+            output.item(format!(
+                "impl {} {{ pub const COUNT: usize = {}; }}",
+                enumeration.rust_type,
+                enumeration.descriptor.value.len()
+            ));
+        }
+
+        fn enum_value(
+            &mut self,
+            value: &HookContext<'_, prost_types::EnumValueDescriptorProto>,
+            output: &mut HookOutput,
+        ) {
+            self.record("enum_value", value);
+            if value
+                .descriptor
+                .options
+                .as_ref()
+                .is_some_and(|options| options.deprecated())
+            {
+                output.attribute("#[deprecated]");
+            }
+        }
+
+        fn oneof(
+            &mut self,
+            oneof: &HookContext<'_, prost_types::OneofDescriptorProto>,
+            output: &mut HookOutput,
+        ) {
+            self.record("oneof", oneof);
+            output.attribute("#[non_exhaustive]");
+        }
+    }
+
+    #[test]
+    fn test_code_generator_hook() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+        let calls = Rc::new(RefCell::new(Vec::new()));
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .code_generator_hook(Box::new(MockHook {
+                calls: calls.clone(),
+            }))
+            .compile_protos(&["src/fixtures/hooks/hooks.proto"], &["src/fixtures/hooks"])
+            .unwrap();
+
+        assert_eq!(
+            *calls.borrow(),
+            [
+                "message .hooks.Account Account",
+                "field .hooks.Account.name ::prost::alloc::string::String",
+                "field .hooks.Account.password ::prost::alloc::string::String",
+                "field .hooks.Account.labels ::std::collections::HashMap<::prost::alloc::string::String, ::prost::alloc::string::String>",
+                "field .hooks.Account.status i32",
+                "oneof .hooks.Account.contact Contact",
+                "field .hooks.Account.email ::prost::alloc::string::String",
+                "field .hooks.Account.phone ::prost::alloc::string::String",
+                "enum .hooks.Status Status",
+                "enum_value .hooks.Status.STATUS_ACTIVE Active",
+                "enum_value .hooks.Status.STATUS_CLOSED Closed",
+            ]
+        );
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("hooks.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "#[cfg_attr(feature=\"serde\",serde(skip))]pubpassword:",
+            "}implAccount{pubconstTABLE:&'staticstr=\"accounts\";}",
+            "#[non_exhaustive]#[derive(Clone,PartialEq,::prost::Oneof)]pubenumContact{",
+            "#[cfg_attr(feature=\"serde\",serde(skip))]Phone(",
+            "#[deprecated]Closed=1,",
+            "implStatus{pubconstCOUNT:usize=2;}",
+        ] {
+            assert!(
+                contents.contains(expected),
+                "missing {expected} in {contents}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_table_driven() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .table_driven([".speed.Compact"])
+            .compile_protos(
+                &[
+                    "src/fixtures/table_driven/code_size.proto",
+                    "src/fixtures/table_driven/speed.proto",
+                ],
+                &["src/fixtures/table_driven"],
+            )
+            .unwrap();
+
+        let read = |name: &str| {
+            let mut contents = String::new();
+            File::open(tempdir.path().join(name))
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents.split_whitespace().collect::<String>()
+        };
+
+        let code_size = read("code_size.rs");
+        for expected in [
+            "#[prost(table_driven)]pubstructSmall{",
+            "#[prost(table_driven)]pubstructNested{",
+        ] {
+            assert!(
+                code_size.contains(expected),
+                "missing {expected} in {code_size}"
+            );
+        }
+
+        let speed = read("speed.rs");
+        assert!(!speed.contains("#[prost(table_driven)]pubstructFast{"));
+        assert!(speed.contains("#[prost(table_driven)]pubstructCompact{"));
+    }
+
+    #[test]
+    fn test_generate_message_meta() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .message_meta([".message_meta.Described"])
+            .rename(".message_meta.Described.renamed", "other_name")
+            .compile_protos(
+                &["src/fixtures/message_meta/message_meta.proto"],
+                &["src/fixtures/message_meta"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("message_meta.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "#[prost(meta)]pubstructDescribed{",
+            "#[prost(meta)]pubenumChoice{",
+            // The Protobuf name is added when it differs from the Rust field.
+            "#[prost(string,tag=\"1\")]pubname:",
+            "#[prost(string,tag=\"5\",name=\"self\")]pubself_:",
+            "#[prost(uint32,tag=\"6\",name=\"camelCase\")]pubcamel_case:",
+            "#[prost(string,tag=\"7\",name=\"renamed\")]pubother_name:",
+            "#[prost(map=\"string,string\",tag=\"8\")]publabels:",
+            "#[prost(oneof=\"described::Choice\",tags=\"3,4\")]pubchoice:",
+            // The names of oneof variants are always added.
+            "#[prost(string,tag=\"3\",name=\"text\")]Text(",
+        ] {
+            assert!(
+                contents.contains(expected),
+                "missing {expected} in {contents}"
+            );
+        }
+        assert!(!contents.contains("#[prost(meta)]pubstructOther{"));
+        assert!(contents.contains("#[prost(string,tag=\"1\")]pubvalue:"));
+    }
+
+    #[test]
+    #[cfg(feature = "expand-derives")]
+    fn test_expand_derives() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .expand_derives()
+            .compile_protos(&["src/fixtures/hooks/hooks.proto"], &["src/fixtures/hooks"])
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("hooks.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        let version = format!(
+            "const_:()=::core::assert!(::prost::encoding::is_version(\"{}\"),",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert!(
+            contents.contains(&version),
+            "missing {version} in {contents}"
+        );
+        for expected in [
+            "#[derive(Clone,PartialEq)]pubstructAccount{",
+            "impl::prost::MessageforAccount{",
+            "impl::core::fmt::DebugforAccount{",
+            "implContact{///Encodesthemessagetoabuffer.pubfnencode(",
+            "impl::core::convert::TryFrom<i32>forStatus{",
+        ] {
+            assert!(
+                contents.contains(expected),
+                "missing {expected} in {contents}"
+            );
+        }
+        assert!(!contents.contains("::prost::Message)]"));
+        assert!(!contents.contains("::prost::Oneof)]"));
+        assert!(!contents.contains("::prost::Enumeration)]"));
+        assert!(!contents.contains("#[prost("));
+    }
+
+    #[test]
+    fn test_generate_heapless_fields() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .max_len("Reading.name", 16)
+            .max_len("Reading.unit", 4)
+            .max_len("Reading.key", 32)
+            .max_len("Reading.tags", 8)
+            .max_len("Reading.source.sensor", 12)
+            .max_count("Reading.values", 10)
+            .max_count("Reading.tags", 3)
+            .max_encoded_len(["."])
+            .generate_builders(["Reading"])
+            .compile_protos(
+                &["src/fixtures/heapless/heapless.proto"],
+                &["src/fixtures/heapless"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("heapless.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "pubname:::prost::heapless::String<16>,",
+            "#[prost(string,optional,tag=\"2\",default=\"C\")]pubunit:::core::option::Option<::prost::heapless::String<4>>,",
+            "#[prost(bytes=\"vec\",optional,tag=\"3\")]pubkey:::core::option::Option<::prost::heapless::Vec<u8,32>>,",
+            "pubvalues:::prost::heapless::Vec<f32,10>,",
+            "pubtags:::prost::heapless::Vec<::prost::heapless::String<8>,3>,",
+            "Sensor(::prost::heapless::String<12>),",
+            "publabels:::std::collections::HashMap<::prost::alloc::string::String,::prost::alloc::string::String",
+            "::core::iter::Extend::extend(&mutself.inner.values,::core::iter::once(value));",
+            "#[prost(max_encoded_len)]pubstructReading{",
+            "#[prost(max_encoded_len)]pubenumSource{",
+        ] {
+            assert!(contents.contains(expected), "missing {expected} in {contents}");
+        }
+    }
+
+    #[test]
+    fn test_generate_primitive_wrapper_types() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .compile_well_known_types()
+            .primitive_wrapper_types()
+            .bytes(["Wrappers.bytes"])
+            .compile_protos(
+                &["src/fixtures/wrappers/wrappers.proto"],
+                &["src/fixtures/wrappers"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("wrappers.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "pubint32:::core::option::Option<i32>,",
+            "pubstring:::core::option::Option<::prost::alloc::string::String>,",
+            "pubbytes:::core::option::Option<::prost::bytes::Bytes>,",
+            "pubbools:::prost::alloc::vec::Vec<bool>,",
+            "#[prost(map=\"string,message\",tag=\"5\")]pubdoubles:::std::collections::HashMap<::prost::alloc::string::String,f64>,",
+            "#[prost(message,tag=\"6\")]Uint64(u64),",
+        ] {
+            assert!(contents.contains(expected), "missing {expected} in {contents}");
+        }
+    }
+
+    #[test]
+    fn test_generate_builders() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .generate_builders([".builders.Node", ".builders.Factory"])
+            .rename(".builders.Node.value.legacy_id", "OldId")
+            .compile_protos(
+                &["src/fixtures/builders/builders.proto"],
+                &["src/fixtures/builders"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("builders.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "pubfnbuilder()->NodeBuilder{NodeBuilder::default()}",
+            "pubstructNodeBuilder{inner:Node,required:[bool;2],}",
+            "pubfncolor(mutself,value:Color)->Self{self.inner.color=::core::option::Option::Some(valueasi32);self}",
+            "pubfnparent(mutself,value:Node)->Self{self.inner.parent=::core::option::Option::Some(::prost::alloc::boxed::Box::new(value)",
+            "pubfnextend_palette(mutself,values:impl::core::iter::IntoIterator<Item=Color>",
+            "pubfninsert_labels(mutself,key:impl::core::convert::Into<::prost::alloc::string::String>,value:Color",
+            "self.inner.labels.insert(key.into(),valueasi32);",
+            "pubfnr#type(",
+            "pubfnset_build(mutself,value:bool)->Self",
+            "pubfnshade(mutself,value:Color)->Self{self.inner.value=::core::option::Option::Some(node::Value::Shade(valueasi32)",
+            "pubfnbuild(self)->::core::result::Result<Node,::prost::MissingRequiredField>",
+            "#[deprecated]pubfnalias(mutself,value:impl::core::convert::Into<::prost::alloc::string::String>)->Self",
+            "pubfnold_id(mutself,value:impl::core::convert::Into<::prost::alloc::vec::Vec<u8>>)->Self{self.inner.value=::core::option::Option::Some(node::Value::OldId(value.into()",
+            "implFactory{///Returnsabuilderfor[`Factory`].pubfnnew_builder()->FactoryBuilder{",
+        ] {
+            assert!(contents.contains(expected), "missing {expected} in {contents}");
+        }
+        // Nested messages are matched by the path of the parent message.
+        assert!(contents.contains("pubstructInnerBuilder{inner:Inner,}"));
+    }
+
+    #[test]
+    fn test_generate_field_constants() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .out_dir(tempdir.path())
+            .enable_field_constants()
+            .compile_protos(
+                &["src/fixtures/builders/builders.proto"],
+                &["src/fixtures/builders"],
+            )
+            .unwrap();
+
+        let mut contents = String::new();
+        File::open(tempdir.path().join("builders.rs"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        let contents: String = contents.split_whitespace().collect();
+
+        for expected in [
+            "pubconstNAME_FIELD_NUMBER:u32=1;",
+            "pubconstNAME_FIELD_NAME:&'staticstr=\"name\";",
+            "pubconstTYPE_FIELD_NUMBER:u32=8;",
+            "pubconstSHADE_FIELD_NUMBER:u32=12;",
+            "pubconstFIELDS:&'static[::prost::FieldInfo]=&[::prost::FieldInfo{number:1,name:\"name\",json_name:\"name\",rust_ident:\"name\"",
+            "::prost::FieldInfo{number:8,name:\"type\",json_name:\"type\",rust_ident:\"r#type\"",
+            "::prost::FieldInfo{number:12,name:\"shade\",json_name:\"shade\",rust_ident:\"Shade\"",
+        ] {
+            assert!(contents.contains(expected), "missing {expected} in {contents}");
+        }
+    }
+
     #[test]
     fn deterministic_include_file() {
         let _ = env_logger::try_init();
@@ -556,6 +1221,427 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_file_per_proto() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+
+        Config::new()
+            .file_per_proto()
+            .include_file("_include.rs")
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &[
+                    "src/fixtures/file_per_proto/shop/items.proto",
+                    "src/fixtures/file_per_proto/shop/orders.proto",
+                    "src/fixtures/file_per_proto/root.proto",
+                ],
+                &["src/fixtures/file_per_proto"],
+            )
+            .unwrap();
+
+        assert_eq_fixture_file!(
+            "src/fixtures/file_per_proto/_expected_include.rs",
+            tempdir.path().join("_include.rs")
+        );
+
+        let read = |name: &str| {
+            let mut contents = String::new();
+            File::open(tempdir.path().join(name))
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        };
+
+        assert_eq!(
+            read("shop.rs"),
+            "// This file is @generated by prost-build.\n\
+             include!(\"shop/shop/items.rs\");\n\
+             include!(\"shop/shop/orders.rs\");\n"
+        );
+        assert_eq!(
+            read("_.rs"),
+            "// This file is @generated by prost-build.\ninclude!(\"_/root.rs\");\n"
+        );
+        assert!(read("shop/shop/items.rs").contains("pub struct Item {"));
+        assert!(!read("shop/shop/items.rs").contains("pub struct Order {"));
+        assert!(read("shop/shop/orders.rs").contains("pub items: ::prost::alloc::vec::Vec<Item>,"));
+        assert!(read("_/root.rs").contains("::core::option::Option<shop::Order>,"));
+    }
+
+    #[test]
+    fn test_generate_only() {
+        let _ = env_logger::try_init();
+        let protos = [
+            "src/fixtures/generate_only/vendor/api.proto",
+            "src/fixtures/generate_only/vendor/common.proto",
+            "src/fixtures/generate_only/vendor/extra.proto",
+        ];
+        let includes = ["src/fixtures/generate_only"];
+
+        let tempdir = tempfile::tempdir().unwrap();
+        Config::new()
+            .generate_only([".vendor.api.Used"])
+            .include_file("_include.rs")
+            .out_dir(tempdir.path())
+            .compile_protos(&protos, &includes)
+            .unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(tempdir.path().join(name)).unwrap();
+        let api = read("vendor.api.rs");
+        for expected in [
+            "pub struct Used {",
+            "pub struct Value {",
+            "pub enum Kind {",
+            // The parent of a reachable nested message is generated with all of its fields.
+            "pub struct Outer {",
+            "pub struct Inner {",
+            "pub enum Mode {",
+        ] {
+            assert!(api.contains(expected), "missing {expected} in {api}");
+        }
+        for unexpected in ["Unused", "Skipped", "GetRequest"] {
+            assert!(
+                !api.contains(unexpected),
+                "unexpected {unexpected} in {api}"
+            );
+        }
+        assert!(read("vendor.common.rs").contains("pub struct Shared {"));
+        assert!(!tempdir.path().join("vendor.extra.rs").exists());
+        assert!(!read("_include.rs").contains("extra"));
+
+        let tempdir = tempfile::tempdir().unwrap();
+        Config::new()
+            .generate_only([".vendor.api.Api"])
+            .service_generator(Box::new(ServiceTraitGenerator))
+            .out_dir(tempdir.path())
+            .compile_protos(&protos, &includes)
+            .unwrap();
+
+        let api = std::fs::read_to_string(tempdir.path().join("vendor.api.rs")).unwrap();
+        for expected in [
+            "trait Api {",
+            "pub struct GetRequest {",
+            "pub struct Used {",
+        ] {
+            assert!(api.contains(expected), "missing {expected} in {api}");
+        }
+        assert!(!api.contains("Admin"), "unexpected Admin in {api}");
+        assert!(!api.contains("Unused"), "unexpected Unused in {api}");
+
+        let error = Config::new()
+            .generate_only(["vendor.api.Used"])
+            .out_dir(tempdir.path())
+            .compile_protos(&protos, &includes)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "generate_only root vendor.api.Used is not a fully qualified message or service"
+        );
+    }
+
+    #[test]
+    fn test_skip_and_rename() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+        Config::new()
+            .service_generator(Box::new(ServiceTraitGenerator))
+            .skip([
+                ".skip_rename.Legacy",
+                ".skip_rename.Outer.obsolete",
+                ".skip_rename.Outer.unused",
+                ".skip_rename.Outer.Mode.MODE_OBSOLETE",
+                ".skip_rename.Status.STATUS_DEPRECATED",
+                ".skip_rename.LegacyApi",
+            ])
+            .rename(".skip_rename.Outer", "Container")
+            .rename(".skip_rename.Outer.Inner", "Item")
+            .rename(".skip_rename.Outer.type", "kind")
+            .rename(".skip_rename.Outer.choice", "selection")
+            .rename(".skip_rename.Outer.choice.text", "Label")
+            .rename(".skip_rename.Outer.Mode.MODE_FAST", "Quick")
+            .rename(".skip_rename.Status", "State")
+            .rename(".skip_rename.Status.STATUS_OK", "Good")
+            .rename(".skip_rename.Api", "Backend")
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/skip_rename/skip_rename.proto"],
+                &["src/fixtures/skip_rename"],
+            )
+            .unwrap();
+
+        let code = std::fs::read_to_string(tempdir.path().join("skip_rename.rs"))
+            .unwrap()
+            .replace(char::is_whitespace, "");
+        for expected in [
+            "pubstructContainer{",
+            "pubmodcontainer{",
+            "pubstructItem{",
+            "pubinner:::core::option::Option<container::Item>,",
+            "enumeration=\"container::Mode\",optional,tag=\"2\",default=\"Quick\")]",
+            "pubkind:",
+            "oneof=\"container::Selection\",tags=\"8,10\")]",
+            "pubselection:::core::option::Option<container::Selection>,",
+            "pubenumSelection{",
+            "Label(",
+            "Item(Item),",
+            "Quick=1,",
+            "pubouter:::core::option::Option<Container>,",
+            "enumeration=\"State\",optional,tag=\"4\",default=\"Good\")]",
+            "pubenumState{",
+            "Good=1,",
+            "traitBackend{",
+            "fnget(_:Request)->Container;",
+        ] {
+            assert!(code.contains(expected), "missing {expected} in {code}");
+        }
+        for unexpected in ["legacy", "obsolete", "unused", "deprecated"] {
+            assert!(
+                !code.to_lowercase().contains(unexpected),
+                "unexpected {unexpected} in {code}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_skip_enum_value_used_as_field_default() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+        let error = Config::new()
+            .skip([".skip_rename.Outer.Mode.MODE_FAST"])
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/skip_rename/skip_rename.proto"],
+                &["src/fixtures/skip_rename"],
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            "skipped enum value .skip_rename.Outer.Mode.MODE_FAST is the default of field \
+             .skip_rename.Outer.mode"
+        );
+
+        // The value can be skipped together with the field.
+        Config::new()
+            .skip([
+                ".skip_rename.Outer.Mode.MODE_FAST",
+                ".skip_rename.Outer.mode",
+            ])
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/skip_rename/skip_rename.proto"],
+                &["src/fixtures/skip_rename"],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_skip_first_enum_value() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+        let error = Config::new()
+            .skip([".skip_rename.Status.STATUS_UNKNOWN"])
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/skip_rename/skip_rename.proto"],
+                &["src/fixtures/skip_rename"],
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            "skipped enum value .skip_rename.Status.STATUS_UNKNOWN is the first value, which is \
+             the default of the enum"
+        );
+    }
+
+    #[test]
+    fn test_check_only() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+        let compile = |check_only: bool| {
+            let mut config = Config::new();
+            config
+                .out_dir(tempdir.path())
+                .include_file("_include.rs")
+                .file_per_proto();
+            if check_only {
+                config.check_only();
+            }
+            config.compile_protos(
+                &[
+                    "src/fixtures/alphabet/a.proto",
+                    "src/fixtures/alphabet/b.proto",
+                    "src/fixtures/alphabet/c.proto",
+                ],
+                &["src/fixtures/alphabet"],
+            )
+        };
+
+        // Nothing is written in check mode.
+        let error = compile(true).unwrap_err();
+        let error = error
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<CheckError>()
+            .unwrap();
+        assert_eq!(error.missing().len(), 7);
+        assert!(!tempdir.path().join("a.rs").exists());
+
+        compile(false).unwrap();
+        compile(true).unwrap();
+
+        let a = tempdir.path().join("a/a.rs");
+        let contents = std::fs::read_to_string(&a).unwrap();
+        std::fs::write(&a, contents.replace("Alpha", "Alfa")).unwrap();
+        std::fs::remove_file(tempdir.path().join("b.rs")).unwrap();
+        // Files generated by `prost-build` are extra, but other files are not.
+        let extra = tempdir.path().join("a/old.rs");
+        std::fs::write(&extra, "// This file is @generated by prost-build.\n").unwrap();
+        std::fs::write(tempdir.path().join("a/lib.rs"), "mod a;\n").unwrap();
+
+        let error = compile(true).unwrap_err();
+        let message = error.to_string();
+        let error = error
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<CheckError>()
+            .unwrap();
+        assert_eq!(error.stale().len(), 1);
+        assert_eq!(error.stale()[0].path(), a);
+        assert_eq!(error.missing(), [tempdir.path().join("b.rs")]);
+        assert_eq!(error.extra(), [extra]);
+        assert!(message.starts_with("generated files are out of date\nstale: "));
+        assert!(error.stale()[0].diff().contains("-pub struct Alfa {"));
+        assert!(error.stale()[0].diff().contains("+pub struct Alpha {"));
+        assert!(message.contains("-pub struct Alfa {"));
+
+        // The files are unchanged.
+        assert!(std::fs::read_to_string(&a).unwrap().contains("Alfa"));
+        assert!(!tempdir.path().join("b.rs").exists());
+    }
+
+    #[test]
+    fn test_check_only_removed_package() {
+        let _ = env_logger::try_init();
+        let tempdir = tempfile::tempdir().unwrap();
+        let a = "src/fixtures/alphabet/a.proto";
+        let b = "src/fixtures/alphabet/b.proto";
+        let compile = |protos: &[&str], check_only: bool| {
+            let mut config = Config::new();
+            config.out_dir(tempdir.path()).check_only_ignore(["c.rs"]);
+            if check_only {
+                config.check_only();
+            }
+            config.compile_protos(protos, &["src/fixtures/alphabet"])
+        };
+
+        compile(&[a, b], false).unwrap();
+        // Written by another `Config`.
+        Config::new()
+            .out_dir(tempdir.path())
+            .compile_protos(
+                &["src/fixtures/alphabet/c.proto"],
+                &["src/fixtures/alphabet"],
+            )
+            .unwrap();
+        compile(&[a, b], true).unwrap();
+
+        // The `b` package is removed.
+        let error = compile(&[a], true).unwrap_err();
+        let error = error
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<CheckError>()
+            .unwrap();
+        assert!(error.stale().is_empty());
+        assert!(error.missing().is_empty());
+        assert_eq!(error.extra(), [tempdir.path().join("b.rs")]);
+    }
+
+    #[test]
+    #[cfg(feature = "config-file")]
+    fn test_config_file() {
+        let _ = env_logger::try_init();
+        let from_file = tempfile::tempdir().unwrap();
+        let from_methods = tempfile::tempdir().unwrap();
+        let protos = ["src/fixtures/map_types/map_types.proto"];
+        let includes = ["src/fixtures/map_types"];
+
+        Config::from_file("src/fixtures/config_file/prost.toml")
+            .unwrap()
+            .out_dir(from_file.path())
+            .compile_protos(&protos, &includes)
+            .unwrap();
+        Config::new()
+            .btree_map(["Maps.ordered"])
+            .index_map(["Maps.indexed"])
+            .hashbrown_map(["Maps.hashbrown", "Maps.hashed"])
+            .skip_debug([".map_types.Maps"])
+            .include_file("_include.rs")
+            .enable_type_names()
+            .protoc_arg("--experimental_allow_proto3_optional")
+            .map_hasher("Maps.hashed", "::my_hasher::Hasher")
+            .type_attribute(".map_types", "#[derive(Eq)]")
+            .type_attribute(".map_types.Maps", "#[derive(Hash)]")
+            .type_attribute(".map_types.Maps", "#[non_exhaustive]")
+            .field_attribute("Maps.ordered", "#[serde(default)]")
+            .extern_path(".uuid", "::uuid")
+            .type_name_domain(["."], "type.googleapis.com")
+            .out_dir(from_methods.path())
+            .compile_protos(&protos, &includes)
+            .unwrap();
+
+        for file in ["map_types.rs", "_include.rs"] {
+            assert_eq!(
+                std::fs::read_to_string(from_file.path().join(file)).unwrap(),
+                std::fs::read_to_string(from_methods.path().join(file)).unwrap(),
+            );
+        }
+
+        // The file is merged with the options set before loading it.
+        let mut config = Config::new();
+        config
+            .type_attribute(".map_types", "#[derive(Copy)]")
+            .load_file("src/fixtures/config_file/prost.toml")
+            .unwrap();
+        assert_eq!(
+            config.config_files,
+            [Path::new("src/fixtures/config_file/prost.toml")]
+        );
+        let attributes: Vec<_> = config.type_attributes.get(".map_types.Maps").collect();
+        assert_eq!(
+            attributes,
+            [
+                "#[derive(Copy)]",
+                "#[derive(Eq)]",
+                "#[derive(Hash)]",
+                "#[non_exhaustive]"
+            ]
+        );
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let error = |contents: &str| {
+            let path = tempdir.path().join("prost.toml");
+            std::fs::write(&path, contents).unwrap();
+            Config::from_file(&path).unwrap_err().to_string()
+        };
+        let message = error("bytes = [\".\"]\nbtre_map = [\".\"]\n");
+        assert!(message.starts_with("invalid config file "), "{message}");
+        assert!(message.contains("line 2, column 1"), "{message}");
+        assert!(message.contains("unknown field `btre_map`"), "{message}");
+        let message = error("[max_len]\n\"Maps.name\" = \"ten\"\n");
+        assert!(message.contains("line 2, column 15"), "{message}");
+        assert!(
+            message.contains("invalid type: string \"ten\""),
+            "{message}"
+        );
+        let message = error("boxed = \".Maps.data\"\n");
+        assert!(message.contains("expected a sequence"), "{message}");
+    }
+
     #[test]
     fn write_includes() {
         let modules = [
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use petgraph::algo::has_path_connecting;
 use petgraph::graph::NodeIndex;
@@ -19,18 +19,23 @@ pub struct MessageGraph {
     graph: Graph<String, ()>,
     messages: HashMap<String, DescriptorProto>,
     boxed: PathMap<()>,
+    shared: PathMap<()>,
+    generated: Option<HashSet<String>>,
 }
 
 impl MessageGraph {
     pub(crate) fn new<'a>(
         files: impl Iterator<Item = &'a FileDescriptorProto>,
         boxed: PathMap<()>,
+        shared: PathMap<()>,
     ) -> MessageGraph {
         let mut msg_graph = MessageGraph {
             index: HashMap::new(),
             graph: Graph::new(),
             messages: HashMap::new(),
             boxed,
+            shared,
+            generated: None,
         };
 
         for file in files {
@@ -81,6 +86,43 @@ impl MessageGraph {
         }
     }
 
+    /// Restricts the generated types to the types reachable from `roots`.
+    ///
+    /// A type is reachable if it is a root, if a field of a reachable message refers to it, or if
+    /// one of its nested types is reachable, since nested types are generated in the module of
+    /// their parent message.
+    pub(crate) fn generate_only(&mut self, roots: impl IntoIterator<Item = String>) {
+        let mut generated = HashSet::new();
+        let mut stack: Vec<String> = roots.into_iter().collect();
+        while let Some(name) = stack.pop() {
+            if generated.contains(&name) {
+                continue;
+            }
+            if let Some(message) = self.messages.get(&name) {
+                stack.extend(
+                    message
+                        .field
+                        .iter()
+                        .filter_map(|field| field.type_name.clone()),
+                );
+            }
+            if let Some((parent, _)) = name.rsplit_once('.') {
+                if self.messages.contains_key(parent) {
+                    stack.push(parent.to_string());
+                }
+            }
+            generated.insert(name);
+        }
+        self.generated = Some(generated);
+    }
+
+    /// Returns `true` if code is generated for the message, enum or service `fq_name`.
+    pub fn is_generated(&self, fq_name: &str) -> bool {
+        self.generated
+            .as_ref()
+            .map_or(true, |generated| generated.contains(fq_name))
+    }
+
     /// Try get a message descriptor from current message graph
     pub fn get_message(&self, message: &str) -> Option<&DescriptorProto> {
         self.messages.get(message)
@@ -122,12 +164,16 @@ impl MessageGraph {
         if field.label() == Label::Repeated {
             false
         } else if field.r#type() == Type::Message {
-            // nested and boxed messages cannot derive Copy
+            // nested, boxed and shared messages cannot derive Copy
             if self.is_nested(field.type_name(), fq_message_name)
                 || self
                     .boxed
                     .get_first_field(fq_message_name, field.name())
                     .is_some()
+                || self
+                    .shared
+                    .get_first_field(fq_message_name, field.name())
+                    .is_some()
             {
                 false
             } else {
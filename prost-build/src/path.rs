@@ -39,11 +39,21 @@ impl<T> PathMap<T> {
         self.find_best_matching(&format!("{}.{}", fq_path, field))
     }
 
+    /// Returns `true` if the path map has no matchers.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
     /// Removes all matchers from the path map.
     pub(crate) fn clear(&mut self) {
         self.matchers.clear();
     }
 
+    /// Retains only the matchers whose value satisfies the predicate.
+    pub(crate) fn retain_values(&mut self, mut f: impl FnMut(&T) -> bool) {
+        self.matchers.retain(|(_, value)| f(value));
+    }
+
     /// Returns the first value found best matching the path
     /// See [sub_path_iter()] for paths test order
     fn find_best_matching(&self, full_path: &str) -> Option<&T> {
@@ -2,7 +2,7 @@ use anyhow::{bail, Error};
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::punctuated::Punctuated;
-use syn::{Expr, ExprLit, Ident, Lit, Meta, MetaNameValue, Token};
+use syn::{Expr, ExprLit, Ident, Lit, Meta, MetaNameValue, Token, Type};
 
 use crate::field::{scalar, set_option, tag_attr};
 
@@ -10,6 +10,8 @@ use crate::field::{scalar, set_option, tag_attr};
 pub enum MapTy {
     HashMap,
     BTreeMap,
+    IndexMap,
+    Hashbrown,
 }
 
 impl MapTy {
@@ -17,6 +19,8 @@ impl MapTy {
         match s {
             "map" | "hash_map" => Some(MapTy::HashMap),
             "btree_map" => Some(MapTy::BTreeMap),
+            "index_map" => Some(MapTy::IndexMap),
+            "hashbrown_map" => Some(MapTy::Hashbrown),
             _ => None,
         }
     }
@@ -25,15 +29,25 @@ impl MapTy {
         match *self {
             MapTy::HashMap => Ident::new("hash_map", Span::call_site()),
             MapTy::BTreeMap => Ident::new("btree_map", Span::call_site()),
+            MapTy::IndexMap => Ident::new("index_map", Span::call_site()),
+            MapTy::Hashbrown => Ident::new("hashbrown_map", Span::call_site()),
         }
     }
 
-    fn lib(&self) -> TokenStream {
+    /// The fully-qualified path of the map type, without generic arguments.
+    fn rust_type(&self) -> TokenStream {
         match self {
-            MapTy::HashMap => quote! { std },
-            MapTy::BTreeMap => quote! { prost::alloc },
+            MapTy::HashMap => quote! { ::std::collections::HashMap },
+            MapTy::BTreeMap => quote! { ::prost::alloc::collections::BTreeMap },
+            MapTy::IndexMap => quote! { ::prost::indexmap::IndexMap },
+            MapTy::Hashbrown => quote! { ::prost::hashbrown::HashMap },
         }
     }
+
+    /// Returns `true` if the map type is generic over a `BuildHasher`.
+    fn has_hasher(&self) -> bool {
+        !matches!(self, MapTy::BTreeMap)
+    }
 }
 
 fn fake_scalar(ty: scalar::Ty) -> scalar::Field {
@@ -252,6 +266,24 @@ impl Field {
         quote!(#ident.clear())
     }
 
+    /// Returns a statement which inserts the entries of another map into the map, replacing
+    /// existing entries.
+    pub fn merge_from(&self, ident: TokenStream, other: TokenStream) -> TokenStream {
+        let value = match self.value_ty {
+            ValueTy::Scalar(..) => quote!(::core::clone::Clone::clone(value)),
+            ValueTy::Message => quote! {{
+                let mut merged = ::core::default::Default::default();
+                ::prost::Message::merge_from(&mut merged, value);
+                merged
+            }},
+        };
+        quote! {
+            for (key, value) in &#other {
+                #ident.insert(::core::clone::Clone::clone(key), #value);
+            }
+        }
+    }
+
     /// Returns methods to embed in the message.
     pub fn methods(&self, ident: &TokenStream) -> Option<TokenStream> {
         if let ValueTy::Scalar(scalar::Ty::Enumeration(ty)) = &self.value_ty {
@@ -293,21 +325,53 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to the `prost::meta::FieldType` of the map field.
+    pub fn field_type(&self, ty: &Type) -> TokenStream {
+        let key = self.key_ty.field_type();
+        let value = match self.value_ty {
+            ValueTy::Scalar(ref value) => value.field_type(),
+            ValueTy::Message => {
+                let message_ty = self.nested_message(ty).unwrap_or(ty);
+                quote! {
+                    ::prost::meta::FieldType::Message(::prost::meta::MessageType::of::<#message_ty>())
+                }
+            }
+        };
+        quote! {
+            ::prost::meta::FieldType::Map {
+                key: &#key,
+                value: &#value,
+            }
+        }
+    }
+
+    /// Returns the message type of the map values, if the values are messages.
+    pub fn nested_message<'a>(&self, ty: &'a Type) -> Option<&'a Type> {
+        match self.value_ty {
+            ValueTy::Scalar(..) => None,
+            ValueTy::Message => super::generic_arguments(ty, &["HashMap", "BTreeMap", "IndexMap"])
+                .get(1)
+                .copied(),
+        }
+    }
+
     /// Returns a newtype wrapper around the map, implementing nicer Debug
     ///
     /// The Debug tries to convert any enumerations met into the variants if possible, instead of
     /// outputting the raw numbers.
     pub fn debug(&self, wrapper_name: TokenStream) -> TokenStream {
-        let type_name = match self.map_ty {
-            MapTy::HashMap => Ident::new("HashMap", Span::call_site()),
-            MapTy::BTreeMap => Ident::new("BTreeMap", Span::call_site()),
+        let type_name = self.map_ty.rust_type();
+        // Hash based maps are generic over the hasher, which is not known from the attribute.
+        let (hasher_param, hasher_arg) = if self.map_ty.has_hasher() {
+            (quote!(, S), quote!(, S))
+        } else {
+            (quote!(), quote!())
         };
 
         // A fake field for generating the debug wrapper
         let key_wrapper = fake_scalar(self.key_ty.clone()).debug(quote!(KeyWrapper));
         let key = self.key_ty.rust_type();
         let value_wrapper = self.value_ty.debug();
-        let libname = self.map_ty.lib();
         let fmt = quote! {
             fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                 #key_wrapper
@@ -334,15 +398,15 @@ impl Field {
 
                 let value = ty.rust_type();
                 quote! {
-                    struct #wrapper_name<'a>(&'a ::#libname::collections::#type_name<#key, #value>);
-                    impl<'a> ::core::fmt::Debug for #wrapper_name<'a> {
+                    struct #wrapper_name<'a #hasher_param>(&'a #type_name<#key, #value #hasher_arg>);
+                    impl<'a #hasher_param> ::core::fmt::Debug for #wrapper_name<'a #hasher_arg> {
                         #fmt
                     }
                 }
             }
             ValueTy::Message => quote! {
-                struct #wrapper_name<'a, V: 'a>(&'a ::#libname::collections::#type_name<#key, V>);
-                impl<'a, V> ::core::fmt::Debug for #wrapper_name<'a, V>
+                struct #wrapper_name<'a, V: 'a #hasher_param>(&'a #type_name<#key, V #hasher_arg>);
+                impl<'a, V #hasher_param> ::core::fmt::Debug for #wrapper_name<'a, V #hasher_arg>
                 where
                     V: ::core::fmt::Debug + 'a,
                 {
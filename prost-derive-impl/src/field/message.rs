@@ -1,9 +1,9 @@
 use anyhow::{bail, Error};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
-use syn::Meta;
+use syn::{Meta, Type};
 
-use crate::field::{set_bool, set_option, tag_attr, word_attr, Label};
+use crate::field::{inline_message_type, set_bool, set_option, tag_attr, word_attr, Label};
 
 #[derive(Clone)]
 pub struct Field {
@@ -17,6 +17,7 @@ impl Field {
         let mut label = None;
         let mut tag = None;
         let mut boxed = false;
+        let mut shared = false;
 
         let mut unknown_attrs = Vec::new();
 
@@ -25,6 +26,8 @@ impl Field {
                 set_bool(&mut message, "duplicate message attribute")?;
             } else if word_attr("boxed", attr) {
                 set_bool(&mut boxed, "duplicate boxed attribute")?;
+            } else if word_attr("shared", attr) {
+                set_bool(&mut shared, "duplicate shared attribute")?;
             } else if let Some(t) = tag_attr(attr)? {
                 set_option(&mut tag, t, "duplicate tag attributes")?;
             } else if let Some(l) = Label::from_attr(attr) {
@@ -38,6 +41,10 @@ impl Field {
             return Ok(None);
         }
 
+        if boxed && shared {
+            bail!("message field can not be both boxed and shared");
+        }
+
         if !unknown_attrs.is_empty() {
             bail!(
                 "unknown attribute(s) for message field: #[prost({})]",
@@ -107,6 +114,30 @@ impl Field {
         }
     }
 
+    /// Returns a constant expression which evaluates to the maximum encoded length of the field.
+    pub fn max_encoded_len(&self, ty: &Type) -> TokenStream {
+        let tag = self.tag;
+        let message_ty = match inline_message_type(ty, self.label) {
+            Some(message_ty) => message_ty,
+            None => return quote!(::core::option::Option::None),
+        };
+        let len = quote! {
+            ::prost::encoding::max_len::length_delimited(
+                ::prost::encoding::key_len(#tag),
+                <#message_ty as ::prost::MaxEncodedLen>::MAX_ENCODED_LEN,
+            )
+        };
+        match self.label {
+            Label::Optional | Label::Required => len,
+            Label::Repeated => quote! {
+                ::prost::encoding::max_len::repeated(
+                    <#ty as ::prost::encoding::max_len::Capacity>::CAPACITY,
+                    #len,
+                )
+            },
+        }
+    }
+
     pub fn encoded_len(&self, ident: TokenStream) -> TokenStream {
         let tag = self.tag;
         match self.label {
@@ -122,6 +153,32 @@ impl Field {
         }
     }
 
+    pub fn merge_from(&self, ident: TokenStream, other: TokenStream) -> TokenStream {
+        match self.label {
+            Label::Optional => quote! {
+                if let ::core::option::Option::Some(ref other) = #other {
+                    ::prost::Message::merge_from(
+                        #ident.get_or_insert_with(::core::default::Default::default),
+                        other,
+                    );
+                }
+            },
+            Label::Required => quote! {
+                ::prost::Message::merge_from(&mut #ident, &#other);
+            },
+            Label::Repeated => quote! {
+                ::prost::encoding::extend_repeated(
+                    &mut #ident,
+                    #other.iter().map(|other| {
+                        let mut value = ::core::default::Default::default();
+                        ::prost::Message::merge_from(&mut value, other);
+                        value
+                    }),
+                );
+            },
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.label {
             Label::Optional => quote!(#ident = ::core::option::Option::None),
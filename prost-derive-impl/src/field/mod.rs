@@ -11,7 +11,10 @@ use anyhow::{bail, Error};
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::punctuated::Punctuated;
-use syn::{Attribute, Expr, ExprLit, Lit, LitBool, LitInt, Meta, MetaNameValue, Token};
+use syn::{
+    Attribute, Expr, ExprLit, GenericArgument, Lit, LitBool, LitInt, Meta, MetaNameValue,
+    PathArguments, Token, Type,
+};
 
 #[derive(Clone)]
 pub enum Field {
@@ -133,6 +136,19 @@ impl Field {
         }
     }
 
+    /// Returns a statement which merges the field of another message into the field.
+    ///
+    /// `ident` and `other` are place expressions of the field and of the other field.
+    pub fn merge_from(&self, ident: TokenStream, other: TokenStream) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.merge_from(ident, other),
+            Field::Message(ref message) => message.merge_from(ident, other),
+            Field::Map(ref map) => map.merge_from(ident, other),
+            Field::Oneof(ref oneof) => oneof.merge_from(ident, other),
+            Field::Group(ref group) => group.merge_from(ident, other),
+        }
+    }
+
     pub fn default(&self) -> TokenStream {
         match *self {
             Field::Scalar(ref scalar) => scalar.default(),
@@ -165,6 +181,95 @@ impl Field {
         }
     }
 
+    /// Returns an expression which evaluates to the `prost::meta::FieldDescriptor` of the field,
+    /// or `None` for oneof fields.
+    ///
+    /// `name` is the Protobuf name of the field or oneof variant, and `ty` its Rust type.
+    pub fn descriptor(&self, name: &str, ty: &Type, in_oneof: bool) -> Option<TokenStream> {
+        let (tag, field_type, label, packed) = match *self {
+            Field::Scalar(ref scalar) => {
+                let label = match scalar.kind {
+                    scalar::Kind::Plain(..) => None,
+                    scalar::Kind::Optional(..) => Some(Label::Optional),
+                    scalar::Kind::Required(..) => Some(Label::Required),
+                    scalar::Kind::Repeated | scalar::Kind::Packed => Some(Label::Repeated),
+                };
+                let packed = matches!(scalar.kind, scalar::Kind::Packed);
+                (scalar.tag, scalar.ty.field_type(), label, packed)
+            }
+            Field::Message(ref message) => {
+                let message_ty = message_type(ty);
+                let field_type = quote! {
+                    ::prost::meta::FieldType::Message(::prost::meta::MessageType::of::<#message_ty>())
+                };
+                (message.tag, field_type, Some(message.label), false)
+            }
+            Field::Group(ref group) => {
+                let message_ty = message_type(ty);
+                let field_type = quote! {
+                    ::prost::meta::FieldType::Group(::prost::meta::MessageType::of::<#message_ty>())
+                };
+                (group.tag, field_type, Some(group.label), false)
+            }
+            Field::Map(ref map) => (map.tag, map.field_type(ty), Some(Label::Repeated), false),
+            Field::Oneof(..) => return None,
+        };
+        let cardinality = match label {
+            _ if in_oneof => quote!(Optional),
+            None => quote!(Singular),
+            Some(Label::Optional) => quote!(Optional),
+            Some(Label::Required) => quote!(Required),
+            Some(Label::Repeated) => quote!(Repeated),
+        };
+        Some(quote! {
+            ::prost::meta::FieldDescriptor {
+                tag: #tag,
+                name: #name,
+                ty: #field_type,
+                cardinality: ::prost::meta::Cardinality::#cardinality,
+                packed: #packed,
+            }
+        })
+    }
+
+    /// Returns a constant expression which evaluates to the maximum encoded length of the field,
+    /// as an `Option<usize>`.
+    ///
+    /// `ty` is the Rust type of the field.
+    pub fn max_encoded_len(&self, ty: &Type) -> TokenStream {
+        match *self {
+            Field::Scalar(ref scalar) => scalar.max_encoded_len(ty),
+            Field::Message(ref message) => message.max_encoded_len(ty),
+            Field::Group(ref group) => group.max_encoded_len(ty),
+            Field::Oneof(ref oneof) => oneof.max_encoded_len(),
+            Field::Map(..) => quote!(::core::option::Option::None),
+        }
+    }
+
+    /// Returns the nested message type of the field whose maximum encoded length is used, which
+    /// must implement `prost::MaxEncodedLen`.
+    ///
+    /// `ty` is the Rust type of the field.
+    pub fn bounded_message<'a>(&self, ty: &'a Type) -> Option<&'a Type> {
+        match *self {
+            Field::Message(ref message) => inline_message_type(ty, message.label),
+            Field::Group(ref group) => inline_message_type(ty, group.label),
+            Field::Scalar(..) | Field::Map(..) | Field::Oneof(..) => None,
+        }
+    }
+
+    /// Returns the nested message type of the field, which must implement `prost::MessageMeta`
+    /// for the field to be described.
+    ///
+    /// `ty` is the Rust type of the field.
+    pub fn nested_message<'a>(&self, ty: &'a Type) -> Option<&'a Type> {
+        match *self {
+            Field::Message(..) | Field::Group(..) => Some(message_type(ty)),
+            Field::Map(ref map) => map.nested_message(ty),
+            Field::Scalar(..) | Field::Oneof(..) => None,
+        }
+    }
+
     pub fn methods(&self, ident: &TokenStream) -> Option<TokenStream> {
         match *self {
             Field::Scalar(ref scalar) => scalar.methods(ident),
@@ -224,7 +329,81 @@ impl fmt::Display for Label {
     }
 }
 
+/// Returns the message type of a message field, by removing the `Option` or `Vec` wrapper and any
+/// `Box`, `Arc` or `Rc` wrapper from the field type.
+fn message_type(ty: &Type) -> &Type {
+    let ty = generic_arguments(ty, &["Option", "Vec"])
+        .first()
+        .copied()
+        .unwrap_or(ty);
+    generic_arguments(ty, &["Box", "Arc", "Rc"])
+        .first()
+        .copied()
+        .unwrap_or(ty)
+}
+
+/// Returns the message type of a message field, if its values are stored inline rather than in a
+/// `Box`, `Arc`, `Rc` or growable `Vec`.
+///
+/// A message can't be stored inline in itself, so the maximum encoded length of an inline message
+/// type never depends on the message containing the field. Other message fields may be recursive,
+/// and are unbounded.
+fn inline_message_type(ty: &Type, label: Label) -> Option<&Type> {
+    let ty = match label {
+        // Fixed-capacity vectors, such as `heapless::Vec<T, N>`, have a capacity argument.
+        Label::Repeated if argument_count(ty) == 2 => *generic_arguments(ty, &["Vec"]).first()?,
+        Label::Repeated => return None,
+        Label::Optional | Label::Required => generic_arguments(ty, &["Option"])
+            .first()
+            .copied()
+            .unwrap_or(ty),
+    };
+    if generic_arguments(ty, &["Box", "Arc", "Rc"]).is_empty() {
+        Some(ty)
+    } else {
+        None
+    }
+}
+
+/// Returns the number of generic arguments of `ty`, if it is a path.
+fn argument_count(ty: &Type) -> usize {
+    match ty {
+        Type::Path(path) => match path.path.segments.last().map(|segment| &segment.arguments) {
+            Some(PathArguments::AngleBracketed(args)) => args.args.len(),
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Returns the generic type arguments of `ty`, if it is a path to one of the given types.
+fn generic_arguments<'a>(ty: &'a Type, names: &[&str]) -> Vec<&'a Type> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) => segment,
+            None => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    if !names.iter().any(|name| segment.ident == name) {
+        return Vec::new();
+    }
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 /// Get the items belonging to the 'prost' list attribute, e.g. `#[prost(foo, bar="baz")]`.
+///
+/// The `name` item is skipped, since it is read separately by [`proto_name`].
 fn prost_attrs(attrs: Vec<Attribute>) -> Result<Vec<Meta>, Error> {
     let mut result = Vec::new();
     for attr in attrs.iter() {
@@ -233,7 +412,8 @@ fn prost_attrs(attrs: Vec<Attribute>) -> Result<Vec<Meta>, Error> {
                 result.extend(
                     meta_list
                         .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?
-                        .into_iter(),
+                        .into_iter()
+                        .filter(|meta| !meta.path().is_ident("name")),
                 )
             }
         }
@@ -241,6 +421,36 @@ fn prost_attrs(attrs: Vec<Attribute>) -> Result<Vec<Meta>, Error> {
     Ok(result)
 }
 
+/// Returns the Protobuf name of a field or oneof variant, given by the `name` item of its
+/// `prost` attributes, e.g. `#[prost(string, tag = "1", name = "self")]`.
+pub fn proto_name(attrs: &[Attribute]) -> Result<Option<String>, Error> {
+    let mut name = None;
+    for attr in attrs {
+        if let Meta::List(meta_list) = &attr.meta {
+            if meta_list.path.is_ident("prost") {
+                for meta in
+                    meta_list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?
+                {
+                    if !meta.path().is_ident("name") {
+                        continue;
+                    }
+                    match meta {
+                        Meta::NameValue(MetaNameValue {
+                            value:
+                                Expr::Lit(ExprLit {
+                                    lit: Lit::Str(lit), ..
+                                }),
+                            ..
+                        }) => set_option(&mut name, lit.value(), "duplicate name attributes")?,
+                        _ => bail!("invalid name attribute: {:?}", meta),
+                    }
+                }
+            }
+        }
+    }
+    Ok(name)
+}
+
 pub fn set_option<T>(option: &mut Option<T>, value: T, message: &str) -> Result<(), Error>
 where
     T: fmt::Debug,
@@ -84,6 +84,23 @@ impl Field {
         }
     }
 
+    /// Returns a constant expression which evaluates to the maximum encoded length of the oneof
+    /// field.
+    pub fn max_encoded_len(&self) -> TokenStream {
+        let ty = &self.ty;
+        quote!(<#ty as ::prost::MaxEncodedLen>::MAX_ENCODED_LEN)
+    }
+
+    /// Returns a statement which merges the oneof field of another message into the field.
+    pub fn merge_from(&self, ident: TokenStream, other: TokenStream) -> TokenStream {
+        let ty = &self.ty;
+        quote! {
+            if let ::core::option::Option::Some(ref other) = #other {
+                #ty::merge_from(&mut #ident, other);
+            }
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         quote!(#ident = ::core::option::Option::None)
     }
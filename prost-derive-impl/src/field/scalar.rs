@@ -3,7 +3,9 @@ use std::fmt;
 use anyhow::{anyhow, bail, Error};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens, TokenStreamExt};
-use syn::{parse_str, Expr, ExprLit, Ident, Index, Lit, LitByteStr, Meta, MetaNameValue, Path};
+use syn::{
+    parse_str, Expr, ExprLit, Ident, Index, Lit, LitByteStr, Meta, MetaNameValue, Path, Type,
+};
 
 use crate::field::{bool_attr, set_option, tag_attr, Label};
 
@@ -189,6 +191,49 @@ impl Field {
         }
     }
 
+    /// Returns a constant expression which evaluates to the maximum encoded length of the field.
+    pub fn max_encoded_len(&self, ty: &Type) -> TokenStream {
+        let tag = self.tag;
+        let key_len = quote!(::prost::encoding::key_len(#tag));
+        let capacity = quote!(<#ty as ::prost::encoding::max_len::Capacity>::CAPACITY);
+        // The encoded length of a single numeric value.
+        let value_len = self
+            .ty
+            .max_encoded_len()
+            .map(|len| quote!(::core::option::Option::Some(#len)));
+        match (&self.kind, value_len) {
+            (Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..), Some(value_len)) => {
+                quote! {
+                    ::prost::encoding::max_len::sum(&[::core::option::Option::Some(#key_len), #value_len])
+                }
+            }
+            (Kind::Plain(..) | Kind::Optional(..) | Kind::Required(..), None) => quote! {
+                ::prost::encoding::max_len::length_delimited(#key_len, #capacity)
+            },
+            (Kind::Repeated, Some(value_len)) => quote! {
+                ::prost::encoding::max_len::repeated(
+                    #capacity,
+                    ::prost::encoding::max_len::sum(&[::core::option::Option::Some(#key_len), #value_len]),
+                )
+            },
+            (Kind::Repeated | Kind::Packed, None) => quote! {
+                ::prost::encoding::max_len::repeated(
+                    #capacity,
+                    ::prost::encoding::max_len::length_delimited(
+                        #key_len,
+                        <<#ty as ::prost::encoding::max_len::Capacity>::Item as ::prost::encoding::max_len::Capacity>::CAPACITY,
+                    ),
+                )
+            },
+            (Kind::Packed, Some(value_len)) => quote! {
+                ::prost::encoding::max_len::length_delimited(
+                    #key_len,
+                    ::prost::encoding::max_len::repeated(#capacity, #value_len),
+                )
+            },
+        }
+    }
+
     pub fn clear(&self, ident: TokenStream) -> TokenStream {
         match self.kind {
             Kind::Plain(ref default) | Kind::Required(ref default) => {
@@ -203,12 +248,37 @@ impl Field {
         }
     }
 
+    /// Returns a statement which merges the field of another message into the field.
+    pub fn merge_from(&self, ident: TokenStream, other: TokenStream) -> TokenStream {
+        match self.kind {
+            Kind::Plain(ref default) => {
+                let default = default.typed();
+                quote! {
+                    if #other != #default {
+                        ::core::clone::Clone::clone_from(&mut #ident, &#other);
+                    }
+                }
+            }
+            Kind::Optional(..) => quote! {
+                if #other.is_some() {
+                    ::core::clone::Clone::clone_from(&mut #ident, &#other);
+                }
+            },
+            Kind::Required(..) => quote! {
+                ::core::clone::Clone::clone_from(&mut #ident, &#other);
+            },
+            Kind::Repeated | Kind::Packed => quote! {
+                ::prost::encoding::extend_repeated(&mut #ident, #other.iter().cloned());
+            },
+        }
+    }
+
     /// Returns an expression which evaluates to the default value of the field.
     pub fn default(&self) -> TokenStream {
         match self.kind {
             Kind::Plain(ref value) | Kind::Required(ref value) => value.owned(),
             Kind::Optional(_) => quote!(::core::option::Option::None),
-            Kind::Repeated | Kind::Packed => quote!(::prost::alloc::vec::Vec::new()),
+            Kind::Repeated | Kind::Packed => quote!(::core::default::Default::default()),
         }
     }
 
@@ -240,6 +310,9 @@ impl Field {
         let wrapper = self.debug_inner(quote!(Inner));
         let inner_ty = self.ty.rust_type();
         match self.kind {
+            // Only enumeration values are wrapped, so other fields are formatted as they are,
+            // whatever their container type.
+            _ if !matches!(self.ty, Ty::Enumeration(_)) => self.debug_inner(wrapper_name),
             Kind::Plain(_) | Kind::Required(_) => self.debug_inner(wrapper_name),
             Kind::Optional(_) => quote! {
                 struct #wrapper_name<'a>(&'a ::core::option::Option<#inner_ty>);
@@ -252,7 +325,7 @@ impl Field {
             },
             Kind::Repeated | Kind::Packed => {
                 quote! {
-                    struct #wrapper_name<'a>(&'a ::prost::alloc::vec::Vec<#inner_ty>);
+                    struct #wrapper_name<'a>(&'a [#inner_ty]);
                     impl<'a> ::core::fmt::Debug for #wrapper_name<'a> {
                         fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                             let mut vec_builder = f.debug_list();
@@ -347,7 +420,7 @@ impl Field {
                         }
                         #[doc=#push_doc]
                         pub fn #push(&mut self, value: #ty) {
-                            self.#ident.push(value as i32);
+                            ::core::iter::Extend::extend(&mut self.#ident, ::core::iter::once(value as i32));
                         }
                     }
                 }
@@ -566,10 +639,47 @@ impl Ty {
         }
     }
 
+    /// Returns the maximum encoded length of a value of the scalar type, or `None` if the type is
+    /// length delimited.
+    pub fn max_encoded_len(&self) -> Option<usize> {
+        match self {
+            Ty::Bool => Some(1),
+            Ty::Float | Ty::Fixed32 | Ty::Sfixed32 => Some(4),
+            Ty::Uint32 | Ty::Sint32 => Some(5),
+            Ty::Double | Ty::Fixed64 | Ty::Sfixed64 => Some(8),
+            // Negative `int32` values are sign-extended to 64 bits, as are enumeration values.
+            Ty::Int32 | Ty::Int64 | Ty::Uint64 | Ty::Sint64 | Ty::Enumeration(..) => Some(10),
+            Ty::String | Ty::Bytes(..) => None,
+        }
+    }
+
     /// Returns false if the scalar type is length delimited (i.e., `string` or `bytes`).
     pub fn is_numeric(&self) -> bool {
         !matches!(self, Ty::String | Ty::Bytes(..))
     }
+
+    /// Returns an expression which evaluates to the `prost::meta::FieldType` of the scalar type.
+    pub fn field_type(&self) -> TokenStream {
+        let variant = match self {
+            Ty::Double => quote!(Double),
+            Ty::Float => quote!(Float),
+            Ty::Int32 => quote!(Int32),
+            Ty::Int64 => quote!(Int64),
+            Ty::Uint32 => quote!(Uint32),
+            Ty::Uint64 => quote!(Uint64),
+            Ty::Sint32 => quote!(Sint32),
+            Ty::Sint64 => quote!(Sint64),
+            Ty::Fixed32 => quote!(Fixed32),
+            Ty::Fixed64 => quote!(Fixed64),
+            Ty::Sfixed32 => quote!(Sfixed32),
+            Ty::Sfixed64 => quote!(Sfixed64),
+            Ty::Bool => quote!(Bool),
+            Ty::String => quote!(String),
+            Ty::Bytes(..) => quote!(Bytes),
+            Ty::Enumeration(path) => quote!(Enum(::prost::meta::TypeName::of::<#path>())),
+        };
+        quote!(::prost::meta::FieldType::#variant)
+    }
 }
 
 impl fmt::Debug for Ty {
@@ -782,15 +892,17 @@ impl DefaultValue {
     pub fn owned(&self) -> TokenStream {
         match *self {
             DefaultValue::String(ref value) if value.is_empty() => {
-                quote!(::prost::alloc::string::String::new())
+                quote!(::core::default::Default::default())
+            }
+            DefaultValue::String(ref value) => {
+                quote!(::core::iter::FromIterator::from_iter([#value]))
             }
-            DefaultValue::String(ref value) => quote!(#value.into()),
             DefaultValue::Bytes(ref value) if value.is_empty() => {
                 quote!(::core::default::Default::default())
             }
             DefaultValue::Bytes(ref value) => {
                 let lit = LitByteStr::new(value, Span::call_site());
-                quote!(#lit.as_ref().into())
+                quote!(::core::iter::FromIterator::from_iter(#lit.iter().copied()))
             }
 
             ref other => other.typed(),
@@ -0,0 +1,1120 @@
+#![doc(html_root_url = "https://docs.rs/prost-derive-impl/0.13.3")]
+//! The implementation of the `prost-derive` macros.
+//!
+//! This crate is used by `prost-derive`, and by `prost-build` to write the expanded impls into
+//! generated code. It is not meant to be used directly, and has no stability guarantees.
+
+// The `quote!` macro requires deep recursion.
+#![recursion_limit = "4096"]
+
+extern crate alloc;
+
+use anyhow::{bail, Error};
+use itertools::Itertools;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse_quote, punctuated::Punctuated, Data, DataEnum, DataStruct, DeriveInput, Expr, Fields,
+    FieldsNamed, FieldsUnnamed, Generics, Ident, Index, Type, Variant, WherePredicate,
+};
+
+mod field;
+use crate::field::{proto_name, Field};
+
+/// Expands `#[derive(Message)]` for the item in `input`.
+pub fn try_message(input: TokenStream) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse2(input)?;
+
+    let ident = input.ident;
+
+    syn::custom_keyword!(skip_debug);
+    let skip_debug = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<skip_debug>().is_ok());
+
+    syn::custom_keyword!(table_driven);
+    let table_driven = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<table_driven>().is_ok());
+
+    syn::custom_keyword!(meta);
+    let derive_meta = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<meta>().is_ok());
+
+    syn::custom_keyword!(max_encoded_len);
+    let derive_max_encoded_len = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<max_encoded_len>().is_ok());
+
+    let variant_data = match input.data {
+        Data::Struct(variant_data) => variant_data,
+        Data::Enum(..) => bail!("Message can not be derived for an enum"),
+        Data::Union(..) => bail!("Message can not be derived for a union"),
+    };
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (is_struct, fields) = match variant_data {
+        DataStruct {
+            fields: Fields::Named(FieldsNamed { named: fields, .. }),
+            ..
+        } => (true, fields.into_iter().collect()),
+        DataStruct {
+            fields:
+                Fields::Unnamed(FieldsUnnamed {
+                    unnamed: fields, ..
+                }),
+            ..
+        } => (false, fields.into_iter().collect()),
+        DataStruct {
+            fields: Fields::Unit,
+            ..
+        } => (false, Vec::new()),
+    };
+
+    let mut next_tag: u32 = 1;
+    let fields = fields
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, field)| {
+            let field_ident = field.ident.map(|x| quote!(#x)).unwrap_or_else(|| {
+                let index = Index {
+                    index: i as u32,
+                    span: Span::call_site(),
+                };
+                quote!(#index)
+            });
+            let field_ty = field.ty;
+            // The Protobuf name defaults to the name of the Rust field.
+            let field_name = proto_name(&field.attrs).map(|name| {
+                name.unwrap_or_else(|| field_ident.to_string().trim_start_matches("r#").to_string())
+            });
+            match field_name.and_then(|name| Ok((name, Field::new(field.attrs, Some(next_tag))?))) {
+                Ok((field_name, Some(field))) => {
+                    next_tag = field.tags().iter().max().map(|t| t + 1).unwrap_or(next_tag);
+                    Some(Ok((field_ident, field, field_ty, field_name)))
+                }
+                Ok((_, None)) => None,
+                Err(err) => Some(Err(
+                    err.context(format!("invalid message field {}.{}", ident, field_ident))
+                )),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The descriptor lists fields and oneofs in declaration order.
+    let field_descriptors = fields
+        .iter()
+        .filter_map(|(_, field, field_ty, name)| field.descriptor(name, field_ty, false));
+    let oneof_descriptors = fields.iter().filter_map(|(_, field, _, name)| match field {
+        Field::Oneof(oneof) => {
+            let oneof_ty = &oneof.ty;
+            Some(quote! {
+                ::prost::meta::OneofDescriptor {
+                    name: #name,
+                    fields: <#oneof_ty as ::prost::OneofMeta>::FIELDS,
+                }
+            })
+        }
+        _ => None,
+    });
+    let meta_generics = bounded_generics(
+        generics,
+        fields
+            .iter()
+            .filter_map(|(_, field, field_ty, _)| field.nested_message(field_ty)),
+        quote!(::prost::MessageMeta),
+    );
+    let (meta_impl_generics, _, meta_where_clause) = meta_generics.split_for_impl();
+    let meta = if derive_meta {
+        quote! {
+            impl #meta_impl_generics ::prost::MessageMeta for #ident #ty_generics #meta_where_clause {
+                const DESCRIPTOR: &'static ::prost::meta::MessageDescriptor = &::prost::meta::MessageDescriptor {
+                    name: stringify!(#ident),
+                    fields: &[#(#field_descriptors),*],
+                    oneofs: &[#(#oneof_descriptors),*],
+                };
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let max_encoded_lens = fields
+        .iter()
+        .map(|(_, field, field_ty, _)| field.max_encoded_len(field_ty));
+    let max_generics = bounded_generics(
+        generics,
+        fields
+            .iter()
+            .filter_map(|(_, field, field_ty, _)| field.bounded_message(field_ty)),
+        quote!(::prost::MaxEncodedLen),
+    );
+    let (max_impl_generics, _, max_where_clause) = max_generics.split_for_impl();
+    let max_encoded_len = if derive_max_encoded_len {
+        quote! {
+            impl #max_impl_generics ::prost::MaxEncodedLen for #ident #ty_generics #max_where_clause {
+                const MAX_ENCODED_LEN: ::core::option::Option<usize> =
+                    ::prost::encoding::max_len::sum(&[#(#max_encoded_lens),*]);
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let mut fields = fields
+        .into_iter()
+        .map(|(field_ident, field, _, _)| (field_ident, field))
+        .collect::<Vec<_>>();
+
+    // We want Debug to be in declaration order
+    let unsorted_fields = fields.clone();
+
+    // Sort the fields by tag number so that fields will be encoded in tag order.
+    // TODO: This encodes oneof fields in the position of their lowest tag,
+    // regardless of the currently occupied variant, is that consequential?
+    // See: https://developers.google.com/protocol-buffers/docs/encoding#order
+    fields.sort_by_key(|(_, field)| field.tags().into_iter().min().unwrap());
+    let fields = fields;
+
+    if let Some(duplicate_tag) = fields
+        .iter()
+        .flat_map(|(_, field)| field.tags())
+        .duplicates()
+        .next()
+    {
+        bail!(
+            "message {} has multiple fields with tag {}",
+            ident,
+            duplicate_tag
+        )
+    };
+
+    let (codec, table) = if table_driven {
+        if !generics.params.is_empty() {
+            bail!("table driven message {} can not be generic", ident);
+        }
+        table_codec(&ident, &fields)
+    } else {
+        (inline_codec(&ident, &fields), quote!())
+    };
+
+    let clear = fields
+        .iter()
+        .map(|(field_ident, field)| field.clear(quote!(self.#field_ident)));
+
+    // Table driven messages merge through the table, see `table_codec`.
+    let merge_from = if table_driven {
+        quote!()
+    } else {
+        let merge_from = fields.iter().map(|(field_ident, field)| {
+            field.merge_from(quote!(self.#field_ident), quote!(other.#field_ident))
+        });
+        quote! {
+            #[allow(unused_variables)]
+            fn merge_from(&mut self, other: &Self) {
+                #(#merge_from)*
+            }
+        }
+    };
+
+    let default = if is_struct {
+        let default = fields.iter().map(|(field_ident, field)| {
+            let value = field.default();
+            quote!(#field_ident: #value,)
+        });
+        quote! {#ident {
+            #(#default)*
+        }}
+    } else {
+        let default = fields.iter().map(|(_, field)| {
+            let value = field.default();
+            quote!(#value,)
+        });
+        quote! {#ident (
+            #(#default)*
+        )}
+    };
+
+    let methods = fields
+        .iter()
+        .flat_map(|(field_ident, field)| field.methods(field_ident))
+        .collect::<Vec<_>>();
+    let methods = if methods.is_empty() {
+        quote!()
+    } else {
+        quote! {
+            #[allow(dead_code)]
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #(#methods)*
+            }
+        }
+    };
+
+    let expanded = quote! {
+        impl #impl_generics ::prost::Message for #ident #ty_generics #where_clause {
+            #codec
+
+            #merge_from
+
+            fn clear(&mut self) {
+                #(#clear;)*
+            }
+        }
+
+        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> Self {
+                #default
+            }
+        }
+    };
+    let expanded = if skip_debug {
+        expanded
+    } else {
+        let debugs = unsorted_fields.iter().map(|(field_ident, field)| {
+            let wrapper = field.debug(quote!(self.#field_ident));
+            let call = if is_struct {
+                quote!(builder.field(stringify!(#field_ident), &wrapper))
+            } else {
+                quote!(builder.field(&wrapper))
+            };
+            quote! {
+                 let builder = {
+                     let wrapper = #wrapper;
+                     #call
+                 };
+            }
+        });
+        let debug_builder = if is_struct {
+            quote!(f.debug_struct(stringify!(#ident)))
+        } else {
+            quote!(f.debug_tuple(stringify!(#ident)))
+        };
+        quote! {
+            #expanded
+
+            impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                    let mut builder = #debug_builder;
+                    #(#debugs;)*
+                    builder.finish()
+                }
+            }
+        }
+    };
+
+    let expanded = quote! {
+        #expanded
+
+        #table
+
+        #meta
+
+        #max_encoded_len
+
+        #methods
+    };
+
+    Ok(expanded)
+}
+
+/// Returns the `encode_raw`, `merge_field` and `encoded_len` methods of a message which encodes
+/// its fields inline.
+fn inline_codec(ident: &Ident, fields: &[(TokenStream, Field)]) -> TokenStream {
+    let encoded_len = fields
+        .iter()
+        .map(|(field_ident, field)| field.encoded_len(quote!(self.#field_ident)));
+
+    let encode = fields
+        .iter()
+        .map(|(field_ident, field)| field.encode(quote!(self.#field_ident)));
+
+    let merge = fields.iter().map(|(field_ident, field)| {
+        let merge = field.merge(quote!(value));
+        let tags = field.tags().into_iter().map(|tag| quote!(#tag));
+        let tags = Itertools::intersperse(tags, quote!(|));
+
+        quote! {
+            #(#tags)* => {
+                let mut value = &mut self.#field_ident;
+                #merge.map_err(|mut error| {
+                    error.push(STRUCT_NAME, stringify!(#field_ident));
+                    error
+                })
+            },
+        }
+    });
+
+    let struct_name = if fields.is_empty() {
+        quote!()
+    } else {
+        quote!(
+            const STRUCT_NAME: &'static str = stringify!(#ident);
+        )
+    };
+
+    quote! {
+        #[allow(unused_variables)]
+        fn encode_raw(&self, buf: &mut impl ::prost::bytes::BufMut) {
+            #(#encode)*
+        }
+
+        #[allow(unused_variables)]
+        fn merge_field(
+            &mut self,
+            tag: u32,
+            wire_type: ::prost::encoding::wire_type::WireType,
+            buf: &mut impl ::prost::bytes::Buf,
+            ctx: ::prost::encoding::DecodeContext,
+        ) -> ::core::result::Result<(), ::prost::DecodeError>
+        {
+            #struct_name
+            match tag {
+                #(#merge)*
+                _ => ::prost::encoding::skip_field(wire_type, tag, buf, ctx),
+            }
+        }
+
+        #[inline]
+        fn encoded_len(&self) -> usize {
+            0 #(+ #encoded_len)*
+        }
+    }
+}
+
+/// Returns the `encode_raw`, `merge_field`, `encoded_len` and `merge_from` methods of a message
+/// which is encoded with a field table, and the `TableMessage` impl with the table.
+fn table_codec(ident: &Ident, fields: &[(TokenStream, Field)]) -> (TokenStream, TokenStream) {
+    let entries = fields.iter().map(|(field_ident, field)| {
+        let encode = field.encode(quote!(message.#field_ident));
+        let merge = field.merge(quote!(value));
+        let encoded_len = field.encoded_len(quote!(message.#field_ident));
+        let merge_from = field.merge_from(quote!(message.#field_ident), quote!(other.#field_ident));
+
+        quote! {
+            ::prost::encoding::table::FieldTable {
+                name: stringify!(#field_ident),
+                encode: |message: &Self, buf: &mut ::prost::encoding::table::DynBufMut<'_>| {
+                    #encode
+                },
+                merge: |
+                    message: &mut Self,
+                    tag: u32,
+                    wire_type: ::prost::encoding::wire_type::WireType,
+                    buf: &mut ::prost::encoding::table::DynBuf<'_>,
+                    ctx: ::prost::encoding::DecodeContext,
+                | {
+                    let mut value = &mut message.#field_ident;
+                    #merge
+                },
+                encoded_len: |message: &Self| #encoded_len,
+                merge_from: |message: &mut Self, other: &Self| {
+                    #merge_from
+                },
+            }
+        }
+    });
+
+    let mut tags = fields
+        .iter()
+        .enumerate()
+        .flat_map(|(index, (_, field))| field.tags().into_iter().map(move |tag| (tag, index)))
+        .collect::<Vec<_>>();
+    tags.sort_unstable();
+    let tags = tags.into_iter().map(|(tag, index)| quote!((#tag, #index)));
+
+    let table = quote! {
+        impl ::prost::encoding::table::TableMessage for #ident {
+            #[allow(unused_variables, unused_mut)]
+            const TABLE: &'static ::prost::encoding::table::MessageTable<Self> =
+                &::prost::encoding::table::MessageTable {
+                    name: stringify!(#ident),
+                    fields: &[#(#entries),*],
+                    tags: &[#(#tags),*],
+                };
+        }
+    };
+
+    let codec = quote! {
+        fn encode_raw(&self, buf: &mut impl ::prost::bytes::BufMut) {
+            ::prost::encoding::table::encode_raw(
+                self,
+                <Self as ::prost::encoding::table::TableMessage>::TABLE,
+                &mut ::prost::encoding::table::DynBufMut::new(buf),
+            )
+        }
+
+        fn merge_field(
+            &mut self,
+            tag: u32,
+            wire_type: ::prost::encoding::wire_type::WireType,
+            buf: &mut impl ::prost::bytes::Buf,
+            ctx: ::prost::encoding::DecodeContext,
+        ) -> ::core::result::Result<(), ::prost::DecodeError>
+        {
+            ::prost::encoding::table::merge_field(
+                self,
+                <Self as ::prost::encoding::table::TableMessage>::TABLE,
+                tag,
+                wire_type,
+                &mut ::prost::encoding::table::DynBuf::new(buf),
+                ctx,
+            )
+        }
+
+        fn encoded_len(&self) -> usize {
+            ::prost::encoding::table::encoded_len(
+                self,
+                <Self as ::prost::encoding::table::TableMessage>::TABLE,
+            )
+        }
+
+        fn merge_from(&mut self, other: &Self) {
+            ::prost::encoding::table::merge_from(
+                self,
+                other,
+                <Self as ::prost::encoding::table::TableMessage>::TABLE,
+            )
+        }
+    };
+
+    (codec, table)
+}
+
+/// Expands `#[derive(Enumeration)]` for the item in `input`.
+pub fn try_enumeration(input: TokenStream) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let ident = input.ident;
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let punctuated_variants = match input.data {
+        Data::Enum(DataEnum { variants, .. }) => variants,
+        Data::Struct(_) => bail!("Enumeration can not be derived for a struct"),
+        Data::Union(..) => bail!("Enumeration can not be derived for a union"),
+    };
+
+    // Map the variants into 'fields'.
+    let mut variants: Vec<(Ident, Expr)> = Vec::new();
+    for Variant {
+        ident,
+        fields,
+        discriminant,
+        ..
+    } in punctuated_variants
+    {
+        match fields {
+            Fields::Unit => (),
+            Fields::Named(_) | Fields::Unnamed(_) => {
+                bail!("Enumeration variants may not have fields")
+            }
+        }
+
+        match discriminant {
+            Some((_, expr)) => variants.push((ident, expr)),
+            None => bail!("Enumeration variants must have a discriminant"),
+        }
+    }
+
+    if variants.is_empty() {
+        panic!("Enumeration must have at least one variant");
+    }
+
+    let default = variants[0].0.clone();
+
+    let is_valid = variants.iter().map(|(_, value)| quote!(#value => true));
+    let from = variants
+        .iter()
+        .map(|(variant, value)| quote!(#value => ::core::option::Option::Some(#ident::#variant)));
+
+    let try_from = variants
+        .iter()
+        .map(|(variant, value)| quote!(#value => ::core::result::Result::Ok(#ident::#variant)));
+
+    let is_valid_doc = format!("Returns `true` if `value` is a variant of `{}`.", ident);
+    let from_i32_doc = format!(
+        "Converts an `i32` to a `{}`, or `None` if `value` is not a valid variant.",
+        ident
+    );
+
+    let expanded = quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #[doc=#is_valid_doc]
+            pub fn is_valid(value: i32) -> bool {
+                match value {
+                    #(#is_valid,)*
+                    _ => false,
+                }
+            }
+
+            #[deprecated = "Use the TryFrom<i32> implementation instead"]
+            #[doc=#from_i32_doc]
+            pub fn from_i32(value: i32) -> ::core::option::Option<#ident> {
+                match value {
+                    #(#from,)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+
+        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> #ident {
+                #ident::#default
+            }
+        }
+
+        impl #impl_generics ::core::convert::From::<#ident> for i32 #ty_generics #where_clause {
+            fn from(value: #ident) -> i32 {
+                value as i32
+            }
+        }
+
+        impl #impl_generics ::core::convert::TryFrom::<i32> for #ident #ty_generics #where_clause {
+            type Error = ::prost::UnknownEnumValue;
+
+            fn try_from(value: i32) -> ::core::result::Result<#ident, ::prost::UnknownEnumValue> {
+                match value {
+                    #(#try_from,)*
+                    _ => ::core::result::Result::Err(::prost::UnknownEnumValue(value)),
+                }
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+/// Expands `#[derive(Oneof)]` for the item in `input`.
+pub fn try_oneof(input: TokenStream) -> Result<TokenStream, Error> {
+    let input: DeriveInput = syn::parse2(input)?;
+
+    let ident = input.ident;
+
+    syn::custom_keyword!(skip_debug);
+    let skip_debug = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<skip_debug>().is_ok());
+
+    syn::custom_keyword!(meta);
+    let derive_meta = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<meta>().is_ok());
+
+    syn::custom_keyword!(max_encoded_len);
+    let derive_max_encoded_len = input
+        .attrs
+        .iter()
+        .any(|a| a.path().is_ident("prost") && a.parse_args::<max_encoded_len>().is_ok());
+
+    let variants = match input.data {
+        Data::Enum(DataEnum { variants, .. }) => variants,
+        Data::Struct(..) => bail!("Oneof can not be derived for a struct"),
+        Data::Union(..) => bail!("Oneof can not be derived for a union"),
+    };
+
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // Map the variants into 'fields'.
+    let mut fields: Vec<(Ident, Field)> = Vec::new();
+    let mut field_descriptors = Vec::new();
+    let mut nested_messages = Vec::new();
+    let mut max_encoded_lens = Vec::new();
+    let mut bounded_messages = Vec::new();
+    for Variant {
+        attrs,
+        ident: variant_ident,
+        fields: variant_fields,
+        ..
+    } in variants
+    {
+        let variant_fields = match variant_fields {
+            Fields::Unit => Punctuated::new(),
+            Fields::Named(FieldsNamed { named: fields, .. })
+            | Fields::Unnamed(FieldsUnnamed {
+                unnamed: fields, ..
+            }) => fields,
+        };
+        if variant_fields.len() != 1 {
+            bail!("Oneof enum variants must have a single field");
+        }
+        // Without an explicit name, the Protobuf name is assumed to be the snake case variant name.
+        let variant_name =
+            proto_name(&attrs)?.unwrap_or_else(|| to_snake(&variant_ident.to_string()));
+        match Field::new_oneof(attrs)? {
+            Some(field) => {
+                let variant_ty = &variant_fields[0].ty;
+                field_descriptors.extend(field.descriptor(&variant_name, variant_ty, true));
+                nested_messages.extend(field.nested_message(variant_ty).cloned());
+                max_encoded_lens.push(field.max_encoded_len(variant_ty));
+                bounded_messages.extend(field.bounded_message(variant_ty).cloned());
+                fields.push((variant_ident, field))
+            }
+            None => bail!("invalid oneof variant: oneof variants may not be ignored"),
+        }
+    }
+    let meta_generics = bounded_generics(
+        generics,
+        nested_messages.iter(),
+        quote!(::prost::MessageMeta),
+    );
+    let (meta_impl_generics, _, meta_where_clause) = meta_generics.split_for_impl();
+    let max_generics = bounded_generics(
+        generics,
+        bounded_messages.iter(),
+        quote!(::prost::MaxEncodedLen),
+    );
+    let (max_impl_generics, _, max_where_clause) = max_generics.split_for_impl();
+
+    // Oneof variants cannot be oneofs themselves, so it's impossible to have a field with multiple
+    // tags.
+    assert!(fields.iter().all(|(_, field)| field.tags().len() == 1));
+
+    if let Some(duplicate_tag) = fields
+        .iter()
+        .flat_map(|(_, field)| field.tags())
+        .duplicates()
+        .next()
+    {
+        bail!(
+            "invalid oneof {}: multiple variants have tag {}",
+            ident,
+            duplicate_tag
+        );
+    }
+
+    let encode = fields.iter().map(|(variant_ident, field)| {
+        let encode = field.encode(quote!(*value));
+        quote!(#ident::#variant_ident(ref value) => { #encode })
+    });
+
+    let merge = fields.iter().map(|(variant_ident, field)| {
+        let tag = field.tags()[0];
+        let merge = field.merge(quote!(value));
+        quote! {
+            #tag => {
+                match field {
+                    ::core::option::Option::Some(#ident::#variant_ident(ref mut value)) => {
+                        #merge
+                    },
+                    _ => {
+                        let mut owned_value = ::core::default::Default::default();
+                        let value = &mut owned_value;
+                        #merge.map(|_| *field = ::core::option::Option::Some(#ident::#variant_ident(owned_value)))
+                    },
+                }
+            }
+        }
+    });
+
+    let encoded_len = fields.iter().map(|(variant_ident, field)| {
+        let encoded_len = field.encoded_len(quote!(*value));
+        quote!(#ident::#variant_ident(ref value) => #encoded_len)
+    });
+
+    let merge_from = fields.iter().map(|(variant_ident, field)| {
+        let merge_from = field.merge_from(quote!(*value), quote!(*other_value));
+        quote! {
+            #ident::#variant_ident(ref other_value) => {
+                match field {
+                    ::core::option::Option::Some(#ident::#variant_ident(ref mut value)) => {
+                        #merge_from
+                    },
+                    _ => {
+                        let mut owned_value = ::core::default::Default::default();
+                        let value = &mut owned_value;
+                        #merge_from
+                        *field = ::core::option::Option::Some(#ident::#variant_ident(owned_value));
+                    },
+                }
+            }
+        }
+    });
+
+    let expanded = quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Encodes the message to a buffer.
+            pub fn encode(&self, buf: &mut impl ::prost::bytes::BufMut) {
+                match *self {
+                    #(#encode,)*
+                }
+            }
+
+            /// Decodes an instance of the message from a buffer, and merges it into self.
+            pub fn merge(
+                field: &mut ::core::option::Option<#ident #ty_generics>,
+                tag: u32,
+                wire_type: ::prost::encoding::wire_type::WireType,
+                buf: &mut impl ::prost::bytes::Buf,
+                ctx: ::prost::encoding::DecodeContext,
+            ) -> ::core::result::Result<(), ::prost::DecodeError>
+            {
+                match tag {
+                    #(#merge,)*
+                    _ => unreachable!(concat!("invalid ", stringify!(#ident), " tag: {}"), tag),
+                }
+            }
+
+            /// Returns the encoded length of the message without a length delimiter.
+            #[inline]
+            pub fn encoded_len(&self) -> usize {
+                match *self {
+                    #(#encoded_len,)*
+                }
+            }
+
+            /// Merges a oneof value of another message into self.
+            pub fn merge_from(field: &mut ::core::option::Option<#ident #ty_generics>, other: &Self) {
+                match *other {
+                    #(#merge_from,)*
+                }
+            }
+        }
+    };
+    let expanded = if derive_max_encoded_len {
+        quote! {
+            #expanded
+
+            impl #max_impl_generics ::prost::MaxEncodedLen for #ident #ty_generics #max_where_clause {
+                const MAX_ENCODED_LEN: ::core::option::Option<usize> =
+                    ::prost::encoding::max_len::max(&[#(#max_encoded_lens),*]);
+            }
+        }
+    } else {
+        expanded
+    };
+    let expanded = if derive_meta {
+        quote! {
+            #expanded
+
+            impl #meta_impl_generics ::prost::OneofMeta for #ident #ty_generics #meta_where_clause {
+                const FIELDS: &'static [::prost::meta::FieldDescriptor] = &[#(#field_descriptors),*];
+            }
+        }
+    } else {
+        expanded
+    };
+    let expanded = if skip_debug {
+        expanded
+    } else {
+        let debug = fields.iter().map(|(variant_ident, field)| {
+            let wrapper = field.debug(quote!(*value));
+            quote!(#ident::#variant_ident(ref value) => {
+                let wrapper = #wrapper;
+                f.debug_tuple(stringify!(#variant_ident))
+                    .field(&wrapper)
+                    .finish()
+            })
+        });
+        quote! {
+            #expanded
+
+            impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                    match *self {
+                        #(#debug,)*
+                    }
+                }
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
+/// Returns the generics of a `MessageMeta`, `OneofMeta` or `MaxEncodedLen` impl, which
+/// additionally require the nested message types depending on a type parameter to implement the
+/// `bound` trait.
+fn bounded_generics<'a>(
+    generics: &Generics,
+    nested_messages: impl Iterator<Item = &'a Type>,
+    bound: TokenStream,
+) -> Generics {
+    let params = generics
+        .type_params()
+        .map(|param| param.ident.to_string())
+        .collect::<Vec<_>>();
+    let mut generics = generics.clone();
+    let bounds = nested_messages
+        .filter(|ty| mentions_any(quote!(#ty), &params))
+        .map(|ty| -> WherePredicate { parse_quote!(#ty: #bound) })
+        .collect::<Vec<_>>();
+    if !bounds.is_empty() {
+        generics.make_where_clause().predicates.extend(bounds);
+    }
+    generics
+}
+
+/// Returns `true` if the tokens contain any of the identifiers.
+fn mentions_any(tokens: TokenStream, idents: &[String]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => idents.iter().any(|name| ident == name),
+        TokenTree::Group(group) => mentions_any(group.stream(), idents),
+        _ => false,
+    })
+}
+
+/// Converts an `UpperCamelCase` oneof variant name to the `snake_case` field name.
+///
+/// This is a guess, since e.g. both `foo_bar1` and `foo_bar_1` are converted to `FooBar1` by
+/// `prost-build`, which therefore adds the Protobuf name to the variants of oneofs with
+/// descriptors.
+fn to_snake(s: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{try_message, try_oneof};
+    use quote::quote;
+
+    #[test]
+    fn test_rejects_colliding_message_fields() {
+        let output = try_message(quote!(
+            struct Invalid {
+                #[prost(bool, tag = "1")]
+                a: bool,
+                #[prost(oneof = "super::Whatever", tags = "4, 5, 1")]
+                b: Option<super::Whatever>,
+            }
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject colliding message fields")
+                .to_string(),
+            "message Invalid has multiple fields with tag 1"
+        );
+    }
+
+    #[test]
+    fn test_accepts_map_types() {
+        let output = try_message(quote!(
+            struct Maps {
+                #[prost(index_map = "string, message", tag = "1")]
+                a: ::prost::indexmap::IndexMap<String, Inner>,
+                #[prost(hashbrown_map = "int32, bytes", tag = "2")]
+                b: ::prost::hashbrown::HashMap<i32, Vec<u8>>,
+            }
+        ));
+        output.expect("did not accept index_map and hashbrown_map fields");
+    }
+
+    #[test]
+    fn test_rejects_boxed_and_shared_message_field() {
+        let output = try_message(quote!(
+            struct Invalid {
+                #[prost(message, optional, boxed, shared, tag = "1")]
+                a: Option<::prost::alloc::sync::Arc<Inner>>,
+            }
+        ));
+        assert_eq!(
+            format!(
+                "{:#}",
+                output.expect_err("did not reject boxed and shared message field")
+            ),
+            "invalid message field Invalid.a: message field can not be both boxed and shared"
+        );
+    }
+
+    #[test]
+    fn test_message_descriptor_unwraps_field_types() {
+        let output = try_message(quote!(
+            #[prost(meta)]
+            struct Outer {
+                #[prost(message, optional, boxed, tag = "1")]
+                r#type: Option<::prost::alloc::boxed::Box<Inner>>,
+                #[prost(btree_map = "string, message", tag = "2")]
+                map: ::prost::alloc::collections::BTreeMap<String, Inner>,
+            }
+        ))
+        .unwrap()
+        .to_string();
+        assert!(output.contains("name : \"type\""));
+        assert!(output.contains(
+            "value : & :: prost :: meta :: FieldType :: Message (:: prost :: meta :: MessageType :: of :: < Inner > ())"
+        ));
+        assert!(!output.contains("MessageType :: of :: < Option"));
+    }
+
+    #[test]
+    fn test_message_descriptor_bounds_generic_messages() {
+        let output = try_message(quote!(
+            #[prost(meta)]
+            struct Outer<T> {
+                #[prost(message, optional, tag = "1")]
+                a: Option<T>,
+                #[prost(message, repeated, tag = "2")]
+                b: Vec<Inner>,
+                #[prost(uint32, tag = "3")]
+                c: u32,
+            }
+        ))
+        .unwrap()
+        .to_string();
+        assert!(output.contains("where T : :: prost :: MessageMeta"));
+        assert!(!output.contains("Inner : :: prost :: MessageMeta"));
+    }
+
+    #[test]
+    fn test_message_descriptor_uses_proto_names() {
+        let output = try_message(quote!(
+            #[prost(meta)]
+            struct Outer {
+                #[prost(string, tag = "1", name = "self")]
+                self_: String,
+                #[prost(oneof = "Choice", tags = "2", name = "choice_value")]
+                choice: Option<Choice>,
+            }
+        ))
+        .unwrap()
+        .to_string();
+        assert!(output.contains("name : \"self\""));
+        assert!(output.contains("name : \"choice_value\""));
+        assert!(!output.contains("\"self_\""));
+
+        let output = try_oneof(quote!(
+            #[prost(meta)]
+            enum Choice {
+                #[prost(string, tag = "2", name = "text1")]
+                Text1(String),
+                #[prost(string, tag = "3")]
+                RawData(String),
+            }
+        ))
+        .unwrap()
+        .to_string();
+        assert!(output.contains("name : \"text1\""));
+        assert!(output.contains("name : \"raw_data\""));
+    }
+
+    #[test]
+    fn test_meta_and_max_encoded_len_are_opt_in() {
+        let output = try_message(quote!(
+            struct Outer {
+                #[prost(message, optional, tag = "1")]
+                manual: Option<Manual>,
+            }
+        ))
+        .unwrap()
+        .to_string();
+        assert!(!output.contains("MessageMeta"));
+        assert!(!output.contains("MaxEncodedLen"));
+
+        let output = try_oneof(quote!(
+            enum Choice {
+                #[prost(message, tag = "1")]
+                Manual(Manual),
+            }
+        ))
+        .unwrap()
+        .to_string();
+        assert!(!output.contains("OneofMeta"));
+        assert!(!output.contains("MaxEncodedLen"));
+    }
+
+    #[test]
+    fn test_max_encoded_len_skips_indirect_messages() {
+        let output = try_message(quote!(
+            #[prost(max_encoded_len)]
+            struct Node<T> {
+                #[prost(message, optional, tag = "1")]
+                value: Option<T>,
+                #[prost(message, optional, boxed, tag = "2")]
+                parent: Option<Box<Node<T>>>,
+                #[prost(message, repeated, tag = "3")]
+                children: Vec<Node<T>>,
+                #[prost(message, repeated, tag = "4")]
+                values: heapless::Vec<T, 4>,
+            }
+        ))
+        .unwrap()
+        .to_string();
+        assert!(output.contains("where T : :: prost :: MaxEncodedLen"));
+        assert!(!output.contains("< Node < T > as :: prost :: MaxEncodedLen >"));
+    }
+
+    #[test]
+    fn test_rejects_colliding_oneof_variants() {
+        let output = try_oneof(quote!(
+            pub enum Invalid {
+                #[prost(bool, tag = "1")]
+                A(bool),
+                #[prost(bool, tag = "3")]
+                B(bool),
+                #[prost(bool, tag = "1")]
+                C(bool),
+            }
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject colliding oneof variants")
+                .to_string(),
+            "invalid oneof Invalid: multiple variants have tag 1"
+        );
+    }
+
+    #[test]
+    fn test_rejects_multiple_tags_oneof_variant() {
+        let output = try_oneof(quote!(
+            enum What {
+                #[prost(bool, tag = "1", tag = "2")]
+                A(bool),
+            }
+        ));
+        assert_eq!(
+            output
+                .expect_err("did not reject multiple tags on oneof variant")
+                .to_string(),
+            "duplicate tag attributes: 1 and 2"
+        );
+
+        let output = try_oneof(quote!(
+            enum What {
+                #[prost(bool, tag = "3")]
+                #[prost(tag = "4")]
+                A(bool),
+            }
+        ));
+        assert!(output.is_err());
+        assert_eq!(
+            output
+                .expect_err("did not reject multiple tags on oneof variant")
+                .to_string(),
+            "duplicate tag attributes: 3 and 4"
+        );
+
+        let output = try_oneof(quote!(
+            enum What {
+                #[prost(bool, tags = "5,6")]
+                A(bool),
+            }
+        ));
+        assert!(output.is_err());
+        assert_eq!(
+            output
+                .expect_err("did not reject multiple tags on oneof variant")
+                .to_string(),
+            "unknown attribute(s): #[prost(tags = \"5,6\")]"
+        );
+    }
+}
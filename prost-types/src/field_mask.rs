@@ -0,0 +1,495 @@
+//! Operations applying a [`FieldMask`] to messages.
+//!
+//! The operations work with any message implementing [`MessageMeta`], which is implemented by
+//! `#[derive(Message)]` with `#[prost(meta)]`. The paths of a field mask use the Protobuf field
+//! names of the message descriptors, which `prost-build` sets to the names in the `.proto` files.
+
+use core::fmt;
+
+use prost::alloc::collections::BTreeMap;
+use prost::alloc::string::String;
+use prost::alloc::vec::Vec;
+use prost::bytes::Buf;
+use prost::encoding::{
+    decode_key, decode_varint, encode_key, encode_varint, key_len, skip_field, DecodeContext,
+    WireType,
+};
+use prost::meta::{Cardinality, FieldType, MessageDescriptor};
+use prost::{DecodeError, Message, MessageMeta};
+
+use crate::FieldMask;
+
+impl FieldMask {
+    /// Normalizes the field mask to a canonical format.
+    ///
+    /// The paths are sorted, and duplicate paths and paths covered by another path (e.g. `a.b`,
+    /// which is covered by `a`) are removed.
+    pub fn normalize(&mut self) {
+        self.paths.sort();
+        let mut paths: Vec<String> = Vec::with_capacity(self.paths.len());
+        for path in self.paths.drain(..) {
+            if paths.last().is_some_and(|prefix| covers(prefix, &path)) {
+                continue;
+            }
+            paths.push(path);
+        }
+        self.paths = paths;
+    }
+
+    /// Returns the normalized union of two field masks, which covers every field covered by
+    /// either mask.
+    pub fn union(&self, other: &FieldMask) -> FieldMask {
+        let mut mask = FieldMask {
+            paths: self.paths.iter().chain(&other.paths).cloned().collect(),
+        };
+        mask.normalize();
+        mask
+    }
+
+    /// Returns the normalized intersection of two field masks, which covers every field covered
+    /// by both masks.
+    pub fn intersection(&self, other: &FieldMask) -> FieldMask {
+        let mut mask = FieldMask::default();
+        for path in &self.paths {
+            for other_path in &other.paths {
+                if covers(path, other_path) {
+                    mask.paths.push(other_path.clone());
+                } else if covers(other_path, path) {
+                    mask.paths.push(path.clone());
+                }
+            }
+        }
+        mask.normalize();
+        mask
+    }
+}
+
+/// Returns `true` if `prefix` is `path`, or a parent of `path`.
+fn covers(prefix: &str, path: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// A field mask path which is not valid for a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FieldMaskError {
+    /// A component of the path does not name a field of the message.
+    UnknownField(String),
+
+    /// The path continues past a field which is not a singular message field. Repeated fields
+    /// and maps may only be the last component of a path.
+    InvalidSubpath(String),
+
+    /// The masked message failed to decode, e.g. because it is nested deeper than the recursion
+    /// limit, or because a merged field exceeds the capacity of a fixed-capacity container.
+    Decode(DecodeError),
+}
+
+impl fmt::Display for FieldMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMaskError::UnknownField(path) => {
+                write!(f, "field mask path names an unknown field: {}", path)
+            }
+            FieldMaskError::InvalidSubpath(path) => write!(
+                f,
+                "field mask path continues past a field which is not a singular message: {}",
+                path
+            ),
+            FieldMaskError::Decode(error) => {
+                write!(f, "failed to decode the masked message: {}", error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FieldMaskError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FieldMaskError::Decode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for FieldMaskError {
+    fn from(error: DecodeError) -> Self {
+        FieldMaskError::Decode(error)
+    }
+}
+
+/// Checks that every path of the field mask names a field of the message `M`.
+pub fn validate_mask<M: MessageMeta>(mask: &FieldMask) -> Result<(), FieldMaskError> {
+    PathTree::new(mask, M::DESCRIPTOR).map(|_| ())
+}
+
+/// Clears every field of the message which is not covered by the field mask.
+///
+/// A message field which is covered only partially, e.g. `a` by the path `a.b`, is pruned
+/// recursively. Returns an error without modifying the message if a path is not valid for the
+/// message, or if the pruned message fails to decode.
+pub fn prune_to_mask<M: Message + MessageMeta + Default>(
+    msg: &mut M,
+    mask: &FieldMask,
+) -> Result<(), FieldMaskError> {
+    let tree = PathTree::new(mask, M::DESCRIPTOR)?;
+    let mut pruned = Vec::new();
+    tree.filter(&msg.encode_to_vec(), true, &mut pruned)?;
+    *msg = M::decode(pruned.as_slice())?;
+    Ok(())
+}
+
+/// Replaces every field of `dst` which is covered by the field mask with the field of `src`.
+///
+/// This implements the `update_mask` semantics of update APIs: a covered field which is not set in
+/// `src` is cleared in `dst`, and covered repeated, map and message fields are replaced rather
+/// than merged. Fields which are not covered by the field mask are left unchanged. Returns an error
+/// without modifying `dst` if a path is not valid for the message, or if the merged message fails
+/// to decode.
+pub fn merge_with_mask<M: Message + MessageMeta + Default>(
+    dst: &mut M,
+    src: &M,
+    mask: &FieldMask,
+) -> Result<(), FieldMaskError> {
+    let tree = PathTree::new(mask, M::DESCRIPTOR)?;
+    let mut merged = Vec::new();
+    tree.filter(&dst.encode_to_vec(), false, &mut merged)?;
+    tree.filter(&src.encode_to_vec(), true, &mut merged)?;
+    *dst = M::decode(merged.as_slice())?;
+    Ok(())
+}
+
+/// The paths of a field mask, resolved to field tags.
+#[derive(Debug, Default)]
+struct PathTree {
+    /// The covered fields by tag, with the covered subfields of partially covered fields.
+    fields: BTreeMap<u32, Option<PathTree>>,
+}
+
+impl PathTree {
+    fn new(
+        mask: &FieldMask,
+        descriptor: &'static MessageDescriptor,
+    ) -> Result<Self, FieldMaskError> {
+        let mut root = PathTree::default();
+        for path in &mask.paths {
+            let mut descriptor = descriptor;
+            let mut node = Some(&mut root);
+            let mut components = path.split('.').peekable();
+            while let Some(name) = components.next() {
+                let field = descriptor
+                    .field_by_name(name)
+                    .ok_or_else(|| FieldMaskError::UnknownField(path.clone()))?;
+                let is_last = components.peek().is_none();
+                node = node.and_then(|node| {
+                    let entry = node
+                        .fields
+                        .entry(field.tag)
+                        .or_insert_with(|| Some(PathTree::default()));
+                    if is_last {
+                        *entry = None;
+                    }
+                    entry.as_mut()
+                });
+                if is_last {
+                    break;
+                }
+                descriptor = match field.ty {
+                    FieldType::Message(ty) | FieldType::Group(ty)
+                        if field.cardinality != Cardinality::Repeated =>
+                    {
+                        ty.descriptor()
+                    }
+                    _ => return Err(FieldMaskError::InvalidSubpath(path.clone())),
+                };
+            }
+        }
+        Ok(root)
+    }
+
+    /// Copies the fields of the encoded message `buf` which are covered by the tree to `out` if
+    /// `keep` is `true`, or the fields which are not covered if `keep` is `false`.
+    ///
+    /// Skipping a group field fails if the group is nested deeper than the recursion limit.
+    fn filter(&self, mut buf: &[u8], keep: bool, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        while buf.has_remaining() {
+            let field_start = buf;
+            let (tag, wire_type) = decode_key(&mut buf)?;
+            let value_start = buf;
+            skip_field(wire_type, tag, &mut buf, DecodeContext::default())?;
+            let field = &field_start[..field_start.len() - buf.len()];
+            let mut value = &value_start[..value_start.len() - buf.len()];
+
+            match (self.fields.get(&tag), wire_type) {
+                (Some(Some(subtree)), WireType::LengthDelimited) => {
+                    decode_varint(&mut value)?;
+                    let mut inner = Vec::new();
+                    subtree.filter(value, keep, &mut inner)?;
+                    encode_key(tag, WireType::LengthDelimited, out);
+                    encode_varint(inner.len() as u64, out);
+                    out.extend_from_slice(&inner);
+                }
+                (Some(Some(subtree)), WireType::StartGroup) => {
+                    encode_key(tag, WireType::StartGroup, out);
+                    subtree.filter(&value[..value.len() - key_len(tag)], keep, out)?;
+                    encode_key(tag, WireType::EndGroup, out);
+                }
+                (Some(_), _) if keep => out.extend_from_slice(field),
+                (None, _) if !keep => out.extend_from_slice(field),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use prost::alloc::string::ToString;
+    use prost::alloc::vec;
+
+    #[derive(Clone, PartialEq, Message)]
+    #[prost(meta)]
+    struct Outer {
+        #[prost(string, tag = "1")]
+        name: String,
+        #[prost(message, optional, tag = "2")]
+        inner: Option<Inner>,
+        #[prost(int32, repeated, tag = "3")]
+        numbers: Vec<i32>,
+        #[prost(btree_map = "string, int32", tag = "4")]
+        labels: BTreeMap<String, i32>,
+        #[prost(oneof = "Choice", tags = "5, 6")]
+        choice: Option<Choice>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    #[prost(meta)]
+    struct Inner {
+        #[prost(string, tag = "1")]
+        a: String,
+        #[prost(uint32, tag = "2")]
+        b: u32,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    #[prost(meta)]
+    enum Choice {
+        #[prost(string, tag = "5")]
+        Text(String),
+        #[prost(message, tag = "6")]
+        Nested(Inner),
+    }
+
+    fn mask(paths: &[&str]) -> FieldMask {
+        FieldMask {
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+
+    fn outer() -> Outer {
+        Outer {
+            name: "outer".to_string(),
+            inner: Some(Inner {
+                a: "a".to_string(),
+                b: 1,
+            }),
+            numbers: vec![1, 2, 3],
+            labels: [("x".to_string(), 1)].into_iter().collect(),
+            choice: Some(Choice::Text("text".to_string())),
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut paths = mask(&["b", "a.b", "a", "c.d", "c.d", "c.de", "c.d.e"]);
+        paths.normalize();
+        assert_eq!(paths, mask(&["a", "b", "c.d", "c.de"]));
+    }
+
+    #[test]
+    fn test_union_and_intersection() {
+        let a = mask(&["a.b", "c", "d.e"]);
+        let b = mask(&["a", "c.d", "e"]);
+        assert_eq!(a.union(&b), mask(&["a", "c", "d.e", "e"]));
+        assert_eq!(a.intersection(&b), mask(&["a.b", "c.d"]));
+    }
+
+    #[test]
+    fn test_validate_mask() {
+        assert_eq!(
+            validate_mask::<Outer>(&mask(&["name", "inner.b", "labels", "nested.a"])),
+            Ok(())
+        );
+        assert_eq!(
+            validate_mask::<Outer>(&mask(&["inner.c"])),
+            Err(FieldMaskError::UnknownField("inner.c".to_string()))
+        );
+        assert_eq!(
+            validate_mask::<Outer>(&mask(&["inner."])),
+            Err(FieldMaskError::UnknownField("inner.".to_string()))
+        );
+        assert_eq!(
+            validate_mask::<Outer>(&mask(&["name.a"])),
+            Err(FieldMaskError::InvalidSubpath("name.a".to_string()))
+        );
+        assert_eq!(
+            validate_mask::<Outer>(&mask(&["labels.x"])),
+            Err(FieldMaskError::InvalidSubpath("labels.x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_prune_to_mask() {
+        let mut msg = outer();
+        prune_to_mask(&mut msg, &mask(&["inner.a", "numbers", "text"])).unwrap();
+        assert_eq!(
+            msg,
+            Outer {
+                inner: Some(Inner {
+                    a: "a".to_string(),
+                    b: 0,
+                }),
+                numbers: vec![1, 2, 3],
+                choice: Some(Choice::Text("text".to_string())),
+                ..Outer::default()
+            }
+        );
+
+        let mut msg = outer();
+        assert_eq!(
+            prune_to_mask(&mut msg, &mask(&["name", "missing"])),
+            Err(FieldMaskError::UnknownField("missing".to_string()))
+        );
+        assert_eq!(msg, outer());
+    }
+
+    #[test]
+    fn test_merge_with_mask() {
+        let src = Outer {
+            name: "src".to_string(),
+            inner: Some(Inner {
+                a: String::new(),
+                b: 2,
+            }),
+            numbers: vec![4],
+            labels: [("y".to_string(), 2)].into_iter().collect(),
+            choice: Some(Choice::Nested(Inner::default())),
+        };
+
+        let mut dst = outer();
+        merge_with_mask(
+            &mut dst,
+            &src,
+            &mask(&["inner.a", "inner.b", "numbers", "labels", "nested"]),
+        )
+        .unwrap();
+        assert_eq!(
+            dst,
+            Outer {
+                name: "outer".to_string(),
+                inner: Some(Inner {
+                    a: String::new(),
+                    b: 2,
+                }),
+                numbers: vec![4],
+                labels: [("y".to_string(), 2)].into_iter().collect(),
+                choice: Some(Choice::Nested(Inner::default())),
+            }
+        );
+
+        let mut dst = outer();
+        merge_with_mask(&mut dst, &Outer::default(), &mask(&["name", "inner"])).unwrap();
+        assert_eq!(
+            dst,
+            Outer {
+                name: String::new(),
+                inner: None,
+                ..outer()
+            }
+        );
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    #[prost(meta)]
+    struct Renamed {
+        #[prost(string, tag = "1", name = "self")]
+        self_: String,
+        #[prost(uint32, tag = "2", name = "fooBar")]
+        foo_bar: u32,
+        #[prost(oneof = "RenamedChoice", tags = "3", name = "choice")]
+        kind: Option<RenamedChoice>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    #[prost(meta)]
+    enum RenamedChoice {
+        #[prost(string, tag = "3", name = "value_1")]
+        Value1(String),
+    }
+
+    #[test]
+    fn test_mask_uses_proto_names() {
+        assert_eq!(
+            validate_mask::<Renamed>(&mask(&["self", "fooBar", "value_1"])),
+            Ok(())
+        );
+        assert_eq!(
+            validate_mask::<Renamed>(&mask(&["self_"])),
+            Err(FieldMaskError::UnknownField("self_".to_string()))
+        );
+
+        let mut msg = Renamed {
+            self_: "self".to_string(),
+            foo_bar: 1,
+            kind: Some(RenamedChoice::Value1("value".to_string())),
+        };
+        prune_to_mask(&mut msg, &mask(&["self", "value_1"])).unwrap();
+        assert_eq!(
+            msg,
+            Renamed {
+                self_: "self".to_string(),
+                foo_bar: 0,
+                kind: Some(RenamedChoice::Value1("value".to_string())),
+            }
+        );
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    #[prost(meta)]
+    struct Node {
+        #[prost(message, optional, boxed, tag = "1")]
+        child: Option<prost::alloc::boxed::Box<Node>>,
+        #[prost(uint32, tag = "2")]
+        depth: u32,
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let mut msg = Node::default();
+        for depth in 1..=200 {
+            msg = Node {
+                child: Some(prost::alloc::boxed::Box::new(msg)),
+                depth,
+            };
+        }
+        let original = msg.clone();
+
+        // The message is nested deeper than the recursion limit.
+        assert!(matches!(
+            prune_to_mask(&mut msg, &mask(&["child"])),
+            Err(FieldMaskError::Decode(_))
+        ));
+        assert_eq!(msg, original);
+
+        assert!(matches!(
+            merge_with_mask(&mut msg, &original, &mask(&["depth"])),
+            Err(FieldMaskError::Decode(_))
+        ));
+        assert_eq!(msg, original);
+    }
+}
@@ -72,6 +72,9 @@ mod any;
 mod duration;
 pub use duration::DurationError;
 
+pub mod field_mask;
+pub use field_mask::FieldMaskError;
+
 mod timestamp;
 pub use timestamp::TimestampError;
 
@@ -0,0 +1,100 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use bytes::buf::UninitSlice;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// The minimum length of a value which is referenced instead of copied.
+const MIN_SEGMENT_LEN: usize = 4 * 1024;
+
+/// A buffer which collects the encoded message into a list of segments.
+///
+/// Large values are added to the list as segments of their own, which for `Bytes` fields
+/// references their contents rather than copying them, since `Bytes::copy_to_bytes` is free.
+pub(crate) struct ChainBuf {
+    segments: Vec<Bytes>,
+    buf: BytesMut,
+}
+
+impl ChainBuf {
+    pub(crate) fn new() -> Self {
+        ChainBuf {
+            segments: Vec::new(),
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Returns the segments of the encoded message.
+    pub(crate) fn finish(mut self) -> Vec<Bytes> {
+        self.flush();
+        self.segments
+    }
+
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            self.segments.push(self.buf.split().freeze());
+        }
+    }
+}
+
+unsafe impl BufMut for ChainBuf {
+    fn remaining_mut(&self) -> usize {
+        self.buf.remaining_mut()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        self.buf.advance_mut(cnt)
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        self.buf.chunk_mut()
+    }
+
+    fn put<T: Buf>(&mut self, mut src: T)
+    where
+        Self: Sized,
+    {
+        if src.remaining() < MIN_SEGMENT_LEN {
+            self.buf.put(src);
+        } else {
+            self.flush();
+            let len = src.remaining();
+            self.segments.push(src.copy_to_bytes(len));
+        }
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        self.buf.put_slice(src)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec;
+
+    use bytes::Bytes;
+
+    use crate::Message;
+
+    #[test]
+    fn references_large_bytes() {
+        let value = Bytes::from(vec![7u8; 100_000]);
+        let chain = value.encode_to_chain();
+
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[1].as_ptr(), value.as_ptr());
+        assert_eq!(chain.concat(), value.encode_to_vec());
+    }
+
+    #[test]
+    fn copies_small_values() {
+        let value = Bytes::from_static(b"foo");
+        assert_eq!(value.encode_to_chain(), [value.encode_to_vec()]);
+
+        let value = String::from("foo").repeat(10_000);
+        assert_eq!(value.encode_to_chain(), [value.encode_to_vec()]);
+
+        assert!(String::new().encode_to_chain().is_empty());
+    }
+}
@@ -0,0 +1,535 @@
+//! Field by field comparison of messages.
+//!
+//! [`diff`] compares two instances of a message type implementing [`MessageMeta`], which is
+//! implemented by `#[derive(Message)]` with `#[prost(meta)]`, and reports each difference with
+//! the path of the field, e.g. `a.b[3].c` or `labels["key"]`. This is useful to explain why two
+//! large messages are not equal, for example in golden tests.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use ::bytes::Buf;
+
+use crate::encoding::{decode_key, decode_varint, skip_field, DecodeContext, WireType};
+use crate::meta::{Cardinality, FieldDescriptor, FieldType, MessageDescriptor, MessageMeta};
+use crate::Message;
+
+/// Returns the differences between two messages, using the default [`DiffOptions`].
+///
+/// Returns an empty list if the messages are equal.
+pub fn diff<M: Message + MessageMeta>(old: &M, new: &M) -> Vec<Difference> {
+    DiffOptions::new().diff(old, new)
+}
+
+/// Options for comparing messages.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffOptions {
+    unset_as_default: bool,
+    float_tolerance: f64,
+}
+
+impl DiffOptions {
+    /// Creates the default options, which report a field which is set in one message but not in
+    /// the other, and compare floating point values exactly.
+    pub fn new() -> DiffOptions {
+        DiffOptions::default()
+    }
+
+    /// Treat a field which is not set in one message as if it were set to the default value of
+    /// its type, e.g. `0` or an empty message.
+    ///
+    /// This only affects fields which track presence, such as `optional` fields and message
+    /// fields.
+    pub fn unset_as_default(&mut self, enabled: bool) -> &mut Self {
+        self.unset_as_default = enabled;
+        self
+    }
+
+    /// Treat floating point values as equal if they differ by at most `tolerance`.
+    ///
+    /// Two `NaN` values are always equal.
+    pub fn float_tolerance(&mut self, tolerance: f64) -> &mut Self {
+        self.float_tolerance = tolerance;
+        self
+    }
+
+    /// Returns the differences between two messages.
+    ///
+    /// Returns an empty list if the messages are equal.
+    pub fn diff<M: Message + MessageMeta>(&self, old: &M, new: &M) -> Vec<Difference> {
+        let descriptor = M::DESCRIPTOR;
+        let old = parse_message(&mut old.encode_to_vec().as_slice(), descriptor, None);
+        let new = parse_message(&mut new.encode_to_vec().as_slice(), descriptor, None);
+        let mut differences = Vec::new();
+        self.diff_messages(descriptor, &old, &new, "", &mut differences);
+        differences
+    }
+
+    fn diff_messages(
+        &self,
+        descriptor: &'static MessageDescriptor,
+        old: &Fields,
+        new: &Fields,
+        path: &str,
+        differences: &mut Vec<Difference>,
+    ) {
+        let fields = descriptor
+            .fields
+            .iter()
+            .chain(descriptor.oneofs.iter().flat_map(|oneof| oneof.fields));
+        for field in fields {
+            let path = if path.is_empty() {
+                String::from(field.name)
+            } else {
+                format!("{}.{}", path, field.name)
+            };
+            let old = old.get(&field.tag).map_or(&[][..], Vec::as_slice);
+            let new = new.get(&field.tag).map_or(&[][..], Vec::as_slice);
+            match field.ty {
+                FieldType::Map { value, .. } => {
+                    self.diff_maps(value, entries(old), entries(new), &path, differences)
+                }
+                _ if field.cardinality == Cardinality::Repeated => {
+                    for i in 0..old.len().max(new.len()) {
+                        let path = format!("{}[{}]", path, i);
+                        self.diff_nodes(&field.ty, old.get(i), new.get(i), &path, differences);
+                    }
+                }
+                _ => {
+                    let (old, new) = (singular(old), singular(new));
+                    let has_presence = field.cardinality != Cardinality::Singular;
+                    if has_presence && !self.unset_as_default && old.is_some() != new.is_some() {
+                        differences.push(Difference::new(path, old, new));
+                    } else {
+                        self.diff_field(field, old, new, &path, differences);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Compares the values of a singular field, where an unset value is the default value.
+    fn diff_field(
+        &self,
+        field: &FieldDescriptor,
+        old: Option<Node>,
+        new: Option<Node>,
+        path: &str,
+        differences: &mut Vec<Difference>,
+    ) {
+        if old.is_none() && new.is_none() {
+            return;
+        }
+        let old = old.unwrap_or_else(|| default_node(&field.ty));
+        let new = new.unwrap_or_else(|| default_node(&field.ty));
+        self.diff_nodes(&field.ty, Some(&old), Some(&new), path, differences);
+    }
+
+    fn diff_maps(
+        &self,
+        value_ty: &FieldType,
+        old: BTreeMap<MapKey, &Node>,
+        new: BTreeMap<MapKey, &Node>,
+        path: &str,
+        differences: &mut Vec<Difference>,
+    ) {
+        let mut keys = old.keys().chain(new.keys()).collect::<Vec<_>>();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let path = format!("{}[{}]", path, key);
+            let (old, new) = (old.get(key).copied(), new.get(key).copied());
+            self.diff_nodes(value_ty, old, new, &path, differences);
+        }
+    }
+
+    fn diff_nodes(
+        &self,
+        ty: &FieldType,
+        old: Option<&Node>,
+        new: Option<&Node>,
+        path: &str,
+        differences: &mut Vec<Difference>,
+    ) {
+        match (ty, old, new) {
+            (
+                FieldType::Message(ty) | FieldType::Group(ty),
+                Some(Node::Message(old)),
+                Some(Node::Message(new)),
+            ) => self.diff_messages(ty.descriptor(), old, new, path, differences),
+            (_, Some(Node::Value(old)), Some(Node::Value(new))) if self.values_eq(old, new) => {}
+            (_, None, None) => {}
+            _ => differences.push(Difference::new(
+                String::from(path),
+                old.cloned(),
+                new.cloned(),
+            )),
+        }
+    }
+
+    fn values_eq(&self, old: &Value, new: &Value) -> bool {
+        let (old, new) = match (old, new) {
+            (Value::F32(old), Value::F32(new)) => (f64::from(*old), f64::from(*new)),
+            (Value::F64(old), Value::F64(new)) => (*old, *new),
+            _ => return old == new,
+        };
+        let difference = old - new;
+        (old.is_nan() && new.is_nan())
+            || old == new
+            || (difference <= self.float_tolerance && -difference <= self.float_tolerance)
+    }
+}
+
+/// A difference between two messages.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference {
+    /// The path of the field, e.g. `a.b[3].c` or `labels["key"]`.
+    pub path: String,
+    /// The value in the old message, or `None` if the field is not set.
+    pub old: Option<Value>,
+    /// The value in the new message, or `None` if the field is not set.
+    pub new: Option<Value>,
+}
+
+impl Difference {
+    fn new(path: String, old: Option<Node>, new: Option<Node>) -> Difference {
+        Difference {
+            path,
+            old: old.map(Node::into_value),
+            new: new.map(Node::into_value),
+        }
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.path)?;
+        match self.old {
+            Some(ref old) => write!(f, "{}", old)?,
+            None => f.write_str("(unset)")?,
+        }
+        f.write_str(" -> ")?;
+        match self.new {
+            Some(ref new) => write!(f, "{}", new),
+            None => f.write_str("(unset)"),
+        }
+    }
+}
+
+/// A field value of a [`Difference`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Value {
+    Bool(bool),
+    I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    /// The numeric value of an enumeration field.
+    Enum(i32),
+    /// A message, if it is set in only one of the compared messages.
+    Message,
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::I32(value) | Value::Enum(value) => write!(f, "{}", value),
+            Value::I64(value) => write!(f, "{}", value),
+            Value::U32(value) => write!(f, "{}", value),
+            Value::U64(value) => write!(f, "{}", value),
+            Value::F32(value) => write!(f, "{}", value),
+            Value::F64(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{:?}", value),
+            Value::Bytes(value) => {
+                f.write_str("b\"")?;
+                for &byte in value {
+                    write!(f, "{}", core::ascii::escape_default(byte))?;
+                }
+                f.write_str("\"")
+            }
+            Value::Message => f.write_str("{..}"),
+        }
+    }
+}
+
+/// The fields of a decoded message by tag, with every value of repeated fields in order.
+type Fields = BTreeMap<u32, Vec<Node>>;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Value(Value),
+    Message(Fields),
+    Entry(MapKey, Box<Node>),
+}
+
+impl Node {
+    fn into_value(self) -> Value {
+        match self {
+            Node::Value(value) => value,
+            Node::Message(_) | Node::Entry(..) => Value::Message,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum MapKey {
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    String(String),
+}
+
+impl fmt::Display for MapKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapKey::Bool(key) => write!(f, "{}", key),
+            MapKey::Int(key) => write!(f, "{}", key),
+            MapKey::Uint(key) => write!(f, "{}", key),
+            MapKey::String(key) => write!(f, "{:?}", key),
+        }
+    }
+}
+
+impl MapKey {
+    fn new(key: Node) -> MapKey {
+        match key {
+            Node::Value(Value::Bool(key)) => MapKey::Bool(key),
+            Node::Value(Value::I32(key)) => MapKey::Int(key.into()),
+            Node::Value(Value::I64(key)) => MapKey::Int(key),
+            Node::Value(Value::U32(key)) => MapKey::Uint(key.into()),
+            Node::Value(Value::U64(key)) => MapKey::Uint(key),
+            Node::Value(Value::String(key)) => MapKey::String(key),
+            _ => panic!("invalid map key type"),
+        }
+    }
+}
+
+/// Returns the value of a singular field, merging the values if the field is a message.
+fn singular(nodes: &[Node]) -> Option<Node> {
+    let mut nodes = nodes.iter().cloned();
+    let first = nodes.next()?;
+    Some(nodes.fold(first, |merged, node| match (merged, node) {
+        (Node::Message(mut merged), Node::Message(fields)) => {
+            for (tag, mut nodes) in fields {
+                merged.entry(tag).or_default().append(&mut nodes);
+            }
+            Node::Message(merged)
+        }
+        (_, node) => node,
+    }))
+}
+
+/// Returns the entries of a map field, where later entries replace earlier entries with the same
+/// key.
+fn entries(nodes: &[Node]) -> BTreeMap<MapKey, &Node> {
+    nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Entry(key, value) => Some((key.clone(), &**value)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn default_node(ty: &FieldType) -> Node {
+    Node::Value(match ty {
+        FieldType::Double => Value::F64(0.0),
+        FieldType::Float => Value::F32(0.0),
+        FieldType::Int32 | FieldType::Sint32 | FieldType::Sfixed32 => Value::I32(0),
+        FieldType::Int64 | FieldType::Sint64 | FieldType::Sfixed64 => Value::I64(0),
+        FieldType::Uint32 | FieldType::Fixed32 => Value::U32(0),
+        FieldType::Uint64 | FieldType::Fixed64 => Value::U64(0),
+        FieldType::Bool => Value::Bool(false),
+        FieldType::String => Value::String(String::new()),
+        FieldType::Bytes => Value::Bytes(Vec::new()),
+        FieldType::Enum(_) => Value::Enum(0),
+        FieldType::Message(_) | FieldType::Group(_) | FieldType::Map { .. } => {
+            return Node::Message(Fields::new())
+        }
+    })
+}
+
+// The messages are encoded by `Message::encode`, so decoding the encoded messages can't fail.
+
+/// Decodes the fields of an encoded message, or of a group if `group_tag` is set.
+fn parse_message(
+    buf: &mut &[u8],
+    descriptor: &'static MessageDescriptor,
+    group_tag: Option<u32>,
+) -> Fields {
+    let mut fields = Fields::new();
+    while buf.has_remaining() {
+        let (tag, wire_type) = decode_key(buf).expect("invalid field key");
+        if wire_type == WireType::EndGroup && Some(tag) == group_tag {
+            break;
+        }
+        match descriptor.field_by_tag(tag) {
+            Some(field) => {
+                let nodes = fields.entry(tag).or_default();
+                parse_field(buf, &field.ty, tag, wire_type, nodes);
+            }
+            None => {
+                skip_field(wire_type, tag, buf, DecodeContext::default()).expect("invalid field")
+            }
+        }
+    }
+    fields
+}
+
+/// Decodes a field value, or every value of a packed repeated field, and appends them to `nodes`.
+fn parse_field(
+    buf: &mut &[u8],
+    ty: &FieldType,
+    tag: u32,
+    wire_type: WireType,
+    nodes: &mut Vec<Node>,
+) {
+    match ty {
+        FieldType::String => {
+            let value = length_delimited(buf);
+            nodes.push(Node::Value(Value::String(
+                String::from_utf8_lossy(value).into_owned(),
+            )));
+        }
+        FieldType::Bytes => nodes.push(Node::Value(Value::Bytes(length_delimited(buf).to_vec()))),
+        FieldType::Message(message) if wire_type == WireType::LengthDelimited => {
+            let mut value = length_delimited(buf);
+            let fields = parse_message(&mut value, message.descriptor(), None);
+            nodes.push(Node::Message(fields));
+        }
+        FieldType::Message(message) | FieldType::Group(message) => {
+            nodes.push(Node::Message(parse_message(
+                buf,
+                message.descriptor(),
+                Some(tag),
+            )));
+        }
+        FieldType::Map { key, value } => {
+            let mut entry = length_delimited(buf);
+            let (mut entry_key, mut entry_value) = (None, None);
+            while entry.has_remaining() {
+                let (tag, wire_type) = decode_key(&mut entry).expect("invalid field key");
+                let mut nodes = Vec::new();
+                match tag {
+                    1 => parse_field(&mut entry, key, tag, wire_type, &mut nodes),
+                    2 => parse_field(&mut entry, value, tag, wire_type, &mut nodes),
+                    _ => skip_field(wire_type, tag, &mut entry, DecodeContext::default())
+                        .expect("invalid field"),
+                }
+                match tag {
+                    1 => entry_key = nodes.pop(),
+                    2 => entry_value = singular(&nodes),
+                    _ => {}
+                }
+            }
+            nodes.push(Node::Entry(
+                MapKey::new(entry_key.unwrap_or_else(|| default_node(key))),
+                Box::new(entry_value.unwrap_or_else(|| default_node(value))),
+            ));
+        }
+        _ if wire_type == WireType::LengthDelimited => {
+            let mut packed = length_delimited(buf);
+            while packed.has_remaining() {
+                nodes.push(Node::Value(parse_scalar(&mut packed, ty)));
+            }
+        }
+        _ => nodes.push(Node::Value(parse_scalar(buf, ty))),
+    }
+}
+
+fn length_delimited<'a>(buf: &mut &'a [u8]) -> &'a [u8] {
+    let len = decode_varint(buf).expect("invalid length delimiter") as usize;
+    let (value, rest) = buf.split_at(len);
+    *buf = rest;
+    value
+}
+
+/// Decodes a numeric scalar value.
+fn parse_scalar(buf: &mut &[u8], ty: &FieldType) -> Value {
+    let mut varint = || decode_varint(buf).expect("invalid varint");
+    match ty {
+        FieldType::Int32 => Value::I32(varint() as i32),
+        FieldType::Int64 => Value::I64(varint() as i64),
+        FieldType::Uint32 => Value::U32(varint() as u32),
+        FieldType::Uint64 => Value::U64(varint()),
+        FieldType::Sint32 => {
+            let value = varint() as u32;
+            Value::I32(((value >> 1) as i32) ^ (-((value & 1) as i32)))
+        }
+        FieldType::Sint64 => {
+            let value = varint();
+            Value::I64(((value >> 1) as i64) ^ (-((value & 1) as i64)))
+        }
+        FieldType::Bool => Value::Bool(varint() != 0),
+        FieldType::Enum(_) => Value::Enum(varint() as i32),
+        FieldType::Fixed32 => Value::U32(buf.get_u32_le()),
+        FieldType::Fixed64 => Value::U64(buf.get_u64_le()),
+        FieldType::Sfixed32 => Value::I32(buf.get_i32_le()),
+        FieldType::Sfixed64 => Value::I64(buf.get_i64_le()),
+        FieldType::Float => Value::F32(buf.get_f32_le()),
+        FieldType::Double => Value::F64(buf.get_f64_le()),
+        FieldType::String
+        | FieldType::Bytes
+        | FieldType::Message(_)
+        | FieldType::Group(_)
+        | FieldType::Map { .. } => unreachable!("not a numeric scalar type"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn wrapper_values() {
+        assert_eq!(diff(&1u32, &1u32), vec![]);
+        assert_eq!(
+            diff(&1u32, &2u32),
+            vec![Difference {
+                path: "value".to_string(),
+                old: Some(Value::U32(1)),
+                new: Some(Value::U32(2)),
+            }]
+        );
+        assert_eq!(
+            diff(&String::from("a"), &String::new())[0].to_string(),
+            r#"value: "a" -> """#
+        );
+        assert_eq!(
+            diff(&vec![0u8, b'a'], &vec![])[0].to_string(),
+            r#"value: b"\x00a" -> b"""#
+        );
+    }
+
+    #[test]
+    fn float_tolerance() {
+        assert_eq!(diff(&f64::NAN, &f64::NAN), vec![]);
+        assert_eq!(diff(&1.0f32, &1.25f32).len(), 1);
+        assert_eq!(
+            DiffOptions::new()
+                .float_tolerance(0.25)
+                .diff(&1.0f32, &1.25f32),
+            vec![]
+        );
+        assert_eq!(
+            DiffOptions::new()
+                .float_tolerance(0.25)
+                .diff(&1.0f64, &1.5f64)
+                .len(),
+            1
+        );
+    }
+}
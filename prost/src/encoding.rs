@@ -27,6 +27,32 @@ pub use length_delimiter::{
 pub mod wire_type;
 pub use wire_type::{check_wire_type, WireType};
 
+pub mod max_len;
+
+pub mod table;
+
+/// The version of this crate.
+///
+/// The code written by `prost-build` with `expand_derives` calls the functions of this module,
+/// which are not covered by semver, and asserts that it is compiled against this exact version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Returns whether `version` is the version of this crate, in a const context.
+pub const fn is_version(version: &str) -> bool {
+    let (version, expected) = (version.as_bytes(), VERSION.as_bytes());
+    if version.len() != expected.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < version.len() {
+        if version[i] != expected[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
 /// Additional information passed to every decode/merge function.
 ///
 /// The context should be passed by value and can be freely cloned. When passing
@@ -55,6 +81,16 @@ impl Default for DecodeContext {
 }
 
 impl DecodeContext {
+    /// Creates a context without a recursion limit, for decoding a message which was encoded from
+    /// memory, and therefore nests no deeper than the message it was encoded from.
+    #[inline]
+    pub(crate) fn unlimited() -> DecodeContext {
+        DecodeContext {
+            #[cfg(not(feature = "no-recursion-limit"))]
+            recurse_count: u32::MAX,
+        }
+    }
+
     /// Call this function before recursively decoding.
     ///
     /// There is no `exit` function since this function creates a new `DecodeContext`
@@ -130,8 +166,8 @@ pub fn decode_key(buf: &mut impl Buf) -> Result<(u32, WireType), DecodeError> {
 /// Returns the width of an encoded Protobuf field key with the given tag.
 /// The returned width will be between 1 and 5 bytes (inclusive).
 #[inline]
-pub fn key_len(tag: u32) -> usize {
-    encoded_len_varint(u64::from(tag << 3))
+pub const fn key_len(tag: u32) -> usize {
+    encoded_len_varint((tag << 3) as u64)
 }
 
 /// Helper function which abstracts reading a length delimiter prefix followed
@@ -217,7 +253,7 @@ macro_rules! merge_repeated_numeric {
      $merge_repeated:ident) => {
         pub fn $merge_repeated(
             wire_type: WireType,
-            values: &mut Vec<$ty>,
+            values: &mut impl RepeatedAdapter<$ty>,
             buf: &mut impl Buf,
             ctx: DecodeContext,
         ) -> Result<(), DecodeError> {
@@ -226,16 +262,14 @@ macro_rules! merge_repeated_numeric {
                 merge_loop(values, buf, ctx, |values, buf, ctx| {
                     let mut value = Default::default();
                     $merge($wire_type, &mut value, buf, ctx)?;
-                    values.push(value);
-                    Ok(())
+                    values.try_push(value)
                 })
             } else {
                 // Unpacked.
                 check_wire_type($wire_type, wire_type)?;
                 let mut value = Default::default();
                 $merge(wire_type, &mut value, buf, ctx)?;
-                values.push(value);
-                Ok(())
+                values.try_push(value)
             }
         }
     };
@@ -519,31 +553,31 @@ fixed_width!(
     get_i64_le
 );
 
-/// Macro which emits encoding functions for a length-delimited type.
+/// Macro which emits encoding functions for the length-delimited types implementing the adapter
+/// trait.
 macro_rules! length_delimited {
-    ($ty:ty) => {
-        encode_repeated!($ty);
+    ($adapter:ident) => {
+        encode_repeated!(impl $adapter);
 
-        pub fn merge_repeated(
+        pub fn merge_repeated<T: $adapter>(
             wire_type: WireType,
-            values: &mut Vec<$ty>,
+            values: &mut impl RepeatedAdapter<T>,
             buf: &mut impl Buf,
             ctx: DecodeContext,
         ) -> Result<(), DecodeError> {
             check_wire_type(WireType::LengthDelimited, wire_type)?;
             let mut value = Default::default();
             merge(wire_type, &mut value, buf, ctx)?;
-            values.push(value);
-            Ok(())
+            values.try_push(value)
         }
 
         #[inline]
-        pub fn encoded_len(tag: u32, value: &$ty) -> usize {
+        pub fn encoded_len(tag: u32, value: &impl $adapter) -> usize {
             key_len(tag) + encoded_len_varint(value.len() as u64) + value.len()
         }
 
         #[inline]
-        pub fn encoded_len_repeated(tag: u32, values: &[$ty]) -> usize {
+        pub fn encoded_len_repeated(tag: u32, values: &[impl $adapter]) -> usize {
             key_len(tag) * values.len()
                 + values
                     .iter()
@@ -556,15 +590,15 @@ macro_rules! length_delimited {
 pub mod string {
     use super::*;
 
-    pub fn encode(tag: u32, value: &String, buf: &mut impl BufMut) {
+    pub fn encode(tag: u32, value: &impl StringAdapter, buf: &mut impl BufMut) {
         encode_key(tag, WireType::LengthDelimited, buf);
         encode_varint(value.len() as u64, buf);
-        buf.put_slice(value.as_bytes());
+        buf.put_slice(value.as_str().as_bytes());
     }
 
     pub fn merge(
         wire_type: WireType,
-        value: &mut String,
+        value: &mut impl StringAdapter,
         buf: &mut impl Buf,
         ctx: DecodeContext,
     ) -> Result<(), DecodeError> {
@@ -574,16 +608,16 @@ pub mod string {
         // well-formedness. If the utf-8 is not well-formed, or if any other error occurs, then the
         // string is cleared, so as to avoid leaking a string field with invalid data.
         //
-        // This implementation uses the unsafe `String::as_mut_vec` method instead of the safe
+        // This implementation uses the unsafe `as_mut_vec` method of the string instead of the safe
         // alternative of temporarily swapping an empty `String` into the field, because it results
         // in up to 10% better performance on the protobuf message decoding benchmarks.
         //
-        // It's required when using `String::as_mut_vec` that invalid utf-8 data not be leaked into
+        // It's required when using `as_mut_vec` that invalid utf-8 data not be leaked into
         // the backing `String`. To enforce this, even in the event of a panic in `bytes::merge` or
         // in the buf implementation, a drop guard is used.
         unsafe {
-            struct DropGuard<'a>(&'a mut Vec<u8>);
-            impl<'a> Drop for DropGuard<'a> {
+            struct DropGuard<'a, B: BytesAdapter>(&'a mut B);
+            impl<'a, B: BytesAdapter> Drop for DropGuard<'a, B> {
                 #[inline]
                 fn drop(&mut self) {
                     self.0.clear();
@@ -605,7 +639,7 @@ pub mod string {
         }
     }
 
-    length_delimited!(String);
+    length_delimited!(StringAdapter);
 
     #[cfg(test)]
     mod test {
@@ -617,8 +651,8 @@ pub mod string {
         proptest! {
             #[test]
             fn check(value: String, tag in MIN_TAG..=MAX_TAG) {
-                super::test::check_type(value, tag, WireType::LengthDelimited,
-                                        encode, merge, encoded_len)?;
+                super::test::check_type::<String, String>(value, tag, WireType::LengthDelimited,
+                                                          encode, merge, encoded_len)?;
             }
             #[test]
             fn check_repeated(value: Vec<String>, tag in MIN_TAG..=MAX_TAG) {
@@ -632,22 +666,90 @@ pub mod string {
 
 pub trait BytesAdapter: sealed::BytesAdapter {}
 
+pub trait StringAdapter: sealed::StringAdapter {}
+
+/// A container for the values of a repeated field.
+pub trait RepeatedAdapter<T>: sealed::RepeatedAdapter<T> {}
+
 mod sealed {
-    use super::{Buf, BufMut};
+    use core::ops::Deref;
+
+    use super::{Buf, BufMut, DecodeError};
 
     pub trait BytesAdapter: Default + Sized + 'static {
         fn len(&self) -> usize;
 
         /// Replace contents of this buffer with the contents of another buffer.
-        fn replace_with(&mut self, buf: impl Buf);
+        fn replace_with(&mut self, buf: impl Buf) -> Result<(), DecodeError>;
+
+        /// Replace contents of this buffer with the next `len` bytes of another buffer.
+        fn replace_with_prefix(
+            &mut self,
+            buf: &mut impl Buf,
+            len: usize,
+        ) -> Result<(), DecodeError> {
+            self.replace_with(buf.copy_to_bytes(len))
+        }
 
         /// Appends this buffer to the (contents of) other buffer.
         fn append_to(&self, buf: &mut impl BufMut);
 
+        fn clear(&mut self);
+
         fn is_empty(&self) -> bool {
             self.len() == 0
         }
     }
+
+    pub trait StringAdapter: Default + Sized + 'static {
+        type Vec: super::BytesAdapter + Deref<Target = [u8]>;
+
+        fn as_str(&self) -> &str;
+
+        /// Returns the bytes of the string.
+        ///
+        /// # Safety
+        ///
+        /// The caller must ensure that the bytes are valid UTF-8 when the borrow ends.
+        unsafe fn as_mut_vec(&mut self) -> &mut Self::Vec;
+
+        fn len(&self) -> usize {
+            self.as_str().len()
+        }
+    }
+
+    pub trait RepeatedAdapter<T> {
+        /// Appends a value, or fails if the container is full.
+        fn try_push(&mut self, value: T) -> Result<(), DecodeError>;
+
+        /// Returns `true` if no more values can be appended.
+        fn is_full(&self) -> bool {
+            false
+        }
+    }
+}
+
+impl StringAdapter for String {}
+
+impl sealed::StringAdapter for String {
+    type Vec = Vec<u8>;
+
+    fn as_str(&self) -> &str {
+        self
+    }
+
+    unsafe fn as_mut_vec(&mut self) -> &mut Vec<u8> {
+        String::as_mut_vec(self)
+    }
+}
+
+impl<T> RepeatedAdapter<T> for Vec<T> {}
+
+impl<T> sealed::RepeatedAdapter<T> for Vec<T> {
+    fn try_push(&mut self, value: T) -> Result<(), DecodeError> {
+        self.push(value);
+        Ok(())
+    }
 }
 
 impl BytesAdapter for Bytes {}
@@ -657,13 +759,18 @@ impl sealed::BytesAdapter for Bytes {
         Buf::remaining(self)
     }
 
-    fn replace_with(&mut self, mut buf: impl Buf) {
+    fn replace_with(&mut self, mut buf: impl Buf) -> Result<(), DecodeError> {
         *self = buf.copy_to_bytes(buf.remaining());
+        Ok(())
     }
 
     fn append_to(&self, buf: &mut impl BufMut) {
         buf.put(self.clone())
     }
+
+    fn clear(&mut self) {
+        Bytes::clear(self)
+    }
 }
 
 impl BytesAdapter for Vec<u8> {}
@@ -673,15 +780,107 @@ impl sealed::BytesAdapter for Vec<u8> {
         Vec::len(self)
     }
 
-    fn replace_with(&mut self, buf: impl Buf) {
+    fn replace_with(&mut self, buf: impl Buf) -> Result<(), DecodeError> {
         self.clear();
         self.reserve(buf.remaining());
         self.put(buf);
+        Ok(())
     }
 
     fn append_to(&self, buf: &mut impl BufMut) {
         buf.put(self.as_slice())
     }
+
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> BytesAdapter for heapless::Vec<u8, N> {}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> sealed::BytesAdapter for heapless::Vec<u8, N> {
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn replace_with(&mut self, mut buf: impl Buf) -> Result<(), DecodeError> {
+        self.clear();
+        if buf.remaining() > N {
+            return Err(DecodeError::capacity_exceeded());
+        }
+        while buf.has_remaining() {
+            let chunk = buf.chunk();
+            let len = chunk.len();
+            self.extend_from_slice(chunk)
+                .map_err(|()| DecodeError::capacity_exceeded())?;
+            buf.advance(len);
+        }
+        Ok(())
+    }
+
+    fn replace_with_prefix(&mut self, buf: &mut impl Buf, len: usize) -> Result<(), DecodeError> {
+        // Copy directly from the buffer, since `Buf::copy_to_bytes` may allocate.
+        self.replace_with(buf.take(len))
+    }
+
+    fn append_to(&self, buf: &mut impl BufMut) {
+        buf.put_slice(self)
+    }
+
+    fn clear(&mut self) {
+        heapless::Vec::clear(self)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> StringAdapter for heapless::String<N> {}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> sealed::StringAdapter for heapless::String<N> {
+    type Vec = heapless::Vec<u8, N>;
+
+    fn as_str(&self) -> &str {
+        heapless::String::as_str(self)
+    }
+
+    unsafe fn as_mut_vec(&mut self) -> &mut heapless::Vec<u8, N> {
+        heapless::String::as_mut_vec(self)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> RepeatedAdapter<T> for heapless::Vec<T, N> {}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> sealed::RepeatedAdapter<T> for heapless::Vec<T, N> {
+    fn try_push(&mut self, value: T) -> Result<(), DecodeError> {
+        self.push(value)
+            .map_err(|_| DecodeError::capacity_exceeded())
+    }
+
+    fn is_full(&self) -> bool {
+        heapless::Vec::is_full(self)
+    }
+}
+
+/// Appends values to a repeated field, until its container is full.
+///
+/// Used by `Message::merge_from`, which merges two valid messages and therefore does not fail when
+/// the merged values exceed the capacity of a fixed-capacity container. The remaining values are
+/// not evaluated.
+pub fn extend_repeated<T>(values: &mut impl RepeatedAdapter<T>, iter: impl IntoIterator<Item = T>) {
+    let mut iter = iter.into_iter();
+    while !values.is_full() {
+        match iter.next() {
+            // Cannot fail, since the container is not full.
+            Some(value) => {
+                let _ = values.try_push(value);
+            }
+            None => break,
+        }
+    }
 }
 
 pub mod bytes {
@@ -718,8 +917,7 @@ pub mod bytes {
         // This is intended for A and B both being Bytes so it is zero-copy.
         // Some combinations of A and B types may cause a double-copy,
         // in which case merge_one_copy() should be used instead.
-        value.replace_with(buf.copy_to_bytes(len));
-        Ok(())
+        value.replace_with_prefix(buf, len)
     }
 
     pub(super) fn merge_one_copy(
@@ -736,11 +934,10 @@ pub mod bytes {
         let len = len as usize;
 
         // If we must copy, make sure to copy only once.
-        value.replace_with(buf.take(len));
-        Ok(())
+        value.replace_with(buf.take(len))
     }
 
-    length_delimited!(impl BytesAdapter);
+    length_delimited!(BytesAdapter);
 
     #[cfg(test)]
     mod test {
@@ -772,7 +969,7 @@ pub mod bytes {
 
             #[test]
             fn check_repeated_bytes(value: Vec<Vec<u8>>, tag in MIN_TAG..=MAX_TAG) {
-                let value = value.into_iter().map(Bytes::from).collect();
+                let value: Vec<Bytes> = value.into_iter().map(Bytes::from).collect();
                 super::test::check_collection_type(value, tag, WireType::LengthDelimited,
                                                    encode_repeated, merge_repeated,
                                                    encoded_len_repeated)?;
@@ -827,7 +1024,7 @@ pub mod message {
 
     pub fn merge_repeated<M>(
         wire_type: WireType,
-        messages: &mut Vec<M>,
+        messages: &mut impl RepeatedAdapter<M>,
         buf: &mut impl Buf,
         ctx: DecodeContext,
     ) -> Result<(), DecodeError>
@@ -837,8 +1034,7 @@ pub mod message {
         check_wire_type(WireType::LengthDelimited, wire_type)?;
         let mut msg = M::default();
         merge(WireType::LengthDelimited, &mut msg, buf, ctx)?;
-        messages.push(msg);
-        Ok(())
+        messages.try_push(msg)
     }
 
     #[inline]
@@ -914,7 +1110,7 @@ pub mod group {
     pub fn merge_repeated<M>(
         tag: u32,
         wire_type: WireType,
-        messages: &mut Vec<M>,
+        messages: &mut impl RepeatedAdapter<M>,
         buf: &mut impl Buf,
         ctx: DecodeContext,
     ) -> Result<(), DecodeError>
@@ -924,8 +1120,7 @@ pub mod group {
         check_wire_type(WireType::StartGroup, wire_type)?;
         let mut msg = M::default();
         merge(tag, WireType::StartGroup, &mut msg, buf, ctx)?;
-        messages.push(msg);
-        Ok(())
+        messages.try_push(msg)
     }
 
     #[inline]
@@ -946,23 +1141,27 @@ pub mod group {
 }
 
 /// Rust doesn't have a `Map` trait, so macros are currently the best way to be
-/// generic over `HashMap` and `BTreeMap`.
+/// generic over `HashMap`, `BTreeMap` and the other supported map types.
+///
+/// Hash based map types pass the name of their hasher type parameter, so that
+/// maps using a custom `BuildHasher` can be encoded and decoded as well.
 macro_rules! map {
-    ($map_ty:ident) => {
+    ($map_ty:ident $(, $hasher:ident)?) => {
         use crate::encoding::*;
         use core::hash::Hash;
 
         /// Generic protobuf map encode function.
-        pub fn encode<K, V, B, KE, KL, VE, VL>(
+        pub fn encode<K, V, B, KE, KL, VE, VL $(, $hasher)?>(
             key_encode: KE,
             key_encoded_len: KL,
             val_encode: VE,
             val_encoded_len: VL,
             tag: u32,
-            values: &$map_ty<K, V>,
+            values: &$map_ty<K, V $(, $hasher)?>,
             buf: &mut B,
         ) where
             K: Default + Eq + Hash + Ord,
+            $($hasher: ::core::hash::BuildHasher,)?
             V: Default + PartialEq,
             B: BufMut,
             KE: Fn(u32, &K, &mut B),
@@ -983,15 +1182,16 @@ macro_rules! map {
         }
 
         /// Generic protobuf map merge function.
-        pub fn merge<K, V, B, KM, VM>(
+        pub fn merge<K, V, B, KM, VM $(, $hasher)?>(
             key_merge: KM,
             val_merge: VM,
-            values: &mut $map_ty<K, V>,
+            values: &mut $map_ty<K, V $(, $hasher)?>,
             buf: &mut B,
             ctx: DecodeContext,
         ) -> Result<(), DecodeError>
         where
             K: Default + Eq + Hash + Ord,
+            $($hasher: ::core::hash::BuildHasher,)?
             V: Default,
             B: Buf,
             KM: Fn(WireType, &mut K, &mut B, DecodeContext) -> Result<(), DecodeError>,
@@ -1001,14 +1201,15 @@ macro_rules! map {
         }
 
         /// Generic protobuf map encode function.
-        pub fn encoded_len<K, V, KL, VL>(
+        pub fn encoded_len<K, V, KL, VL $(, $hasher)?>(
             key_encoded_len: KL,
             val_encoded_len: VL,
             tag: u32,
-            values: &$map_ty<K, V>,
+            values: &$map_ty<K, V $(, $hasher)?>,
         ) -> usize
         where
             K: Default + Eq + Hash + Ord,
+            $($hasher: ::core::hash::BuildHasher,)?
             V: Default + PartialEq,
             KL: Fn(u32, &K) -> usize,
             VL: Fn(u32, &V) -> usize,
@@ -1020,17 +1221,18 @@ macro_rules! map {
         ///
         /// This is necessary because enumeration values can have a default value other
         /// than 0 in proto2.
-        pub fn encode_with_default<K, V, B, KE, KL, VE, VL>(
+        pub fn encode_with_default<K, V, B, KE, KL, VE, VL $(, $hasher)?>(
             key_encode: KE,
             key_encoded_len: KL,
             val_encode: VE,
             val_encoded_len: VL,
             val_default: &V,
             tag: u32,
-            values: &$map_ty<K, V>,
+            values: &$map_ty<K, V $(, $hasher)?>,
             buf: &mut B,
         ) where
             K: Default + Eq + Hash + Ord,
+            $($hasher: ::core::hash::BuildHasher,)?
             V: PartialEq,
             B: BufMut,
             KE: Fn(u32, &K, &mut B),
@@ -1060,16 +1262,17 @@ macro_rules! map {
         ///
         /// This is necessary because enumeration values can have a default value other
         /// than 0 in proto2.
-        pub fn merge_with_default<K, V, B, KM, VM>(
+        pub fn merge_with_default<K, V, B, KM, VM $(, $hasher)?>(
             key_merge: KM,
             val_merge: VM,
             val_default: V,
-            values: &mut $map_ty<K, V>,
+            values: &mut $map_ty<K, V $(, $hasher)?>,
             buf: &mut B,
             ctx: DecodeContext,
         ) -> Result<(), DecodeError>
         where
             K: Default + Eq + Hash + Ord,
+            $($hasher: ::core::hash::BuildHasher,)?
             B: Buf,
             KM: Fn(WireType, &mut K, &mut B, DecodeContext) -> Result<(), DecodeError>,
             VM: Fn(WireType, &mut V, &mut B, DecodeContext) -> Result<(), DecodeError>,
@@ -1099,15 +1302,16 @@ macro_rules! map {
         ///
         /// This is necessary because enumeration values can have a default value other
         /// than 0 in proto2.
-        pub fn encoded_len_with_default<K, V, KL, VL>(
+        pub fn encoded_len_with_default<K, V, KL, VL $(, $hasher)?>(
             key_encoded_len: KL,
             val_encoded_len: VL,
             val_default: &V,
             tag: u32,
-            values: &$map_ty<K, V>,
+            values: &$map_ty<K, V $(, $hasher)?>,
         ) -> usize
         where
             K: Default + Eq + Hash + Ord,
+            $($hasher: ::core::hash::BuildHasher,)?
             V: PartialEq,
             KL: Fn(u32, &K) -> usize,
             VL: Fn(u32, &V) -> usize,
@@ -1135,13 +1339,25 @@ macro_rules! map {
 #[cfg(feature = "std")]
 pub mod hash_map {
     use std::collections::HashMap;
-    map!(HashMap);
+    map!(HashMap, S);
 }
 
 pub mod btree_map {
     map!(BTreeMap);
 }
 
+#[cfg(feature = "indexmap")]
+pub mod index_map {
+    use indexmap::IndexMap;
+    map!(IndexMap, S);
+}
+
+#[cfg(feature = "hashbrown")]
+pub mod hashbrown_map {
+    use hashbrown::HashMap;
+    map!(HashMap, S);
+}
+
 #[cfg(test)]
 mod test {
     #[cfg(not(feature = "std"))]
@@ -1322,6 +1538,138 @@ mod test {
         assert!(s.is_empty());
     }
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn hash_map_custom_hasher() {
+        use core::hash::BuildHasherDefault;
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashMap;
+
+        let mut values: HashMap<String, i32, BuildHasherDefault<DefaultHasher>> =
+            HashMap::default();
+        values.insert("one".to_string(), 1);
+        values.insert("two".to_string(), 2);
+
+        check_collection_type(
+            values,
+            3,
+            WireType::LengthDelimited,
+            |tag, values, buf| {
+                super::hash_map::encode(
+                    string::encode,
+                    string::encoded_len,
+                    int32::encode,
+                    int32::encoded_len,
+                    tag,
+                    values,
+                    buf,
+                )
+            },
+            |wire_type, values, buf, ctx| {
+                check_wire_type(WireType::LengthDelimited, wire_type)?;
+                super::hash_map::merge(string::merge, int32::merge, values, buf, ctx)
+            },
+            |tag, values| {
+                super::hash_map::encoded_len(string::encoded_len, int32::encoded_len, tag, values)
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "indexmap"))]
+    fn index_map_preserves_wire_order() {
+        use core::hash::BuildHasherDefault;
+        use indexmap::IndexMap;
+        use std::collections::hash_map::DefaultHasher;
+
+        type Map = IndexMap<String, i32, BuildHasherDefault<DefaultHasher>>;
+
+        let mut values = Map::default();
+        for (i, key) in ["zulu", "alpha", "mike", "bravo"].iter().enumerate() {
+            values.insert(key.to_string(), i as i32);
+        }
+
+        let mut buf = Vec::new();
+        index_map::encode(
+            string::encode,
+            string::encoded_len,
+            int32::encode,
+            int32::encoded_len,
+            1,
+            &values,
+            &mut buf,
+        );
+        assert_eq!(
+            buf.len(),
+            index_map::encoded_len(string::encoded_len, int32::encoded_len, 1, &values)
+        );
+
+        let mut buf = &buf[..];
+        let mut roundtrip = Map::default();
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf).unwrap();
+            assert_eq!(tag, 1);
+            index_map::merge(
+                string::merge,
+                int32::merge,
+                &mut roundtrip,
+                &mut buf,
+                DecodeContext::default(),
+            )
+            .unwrap();
+            assert_eq!(wire_type, WireType::LengthDelimited);
+        }
+
+        assert!(values.iter().eq(roundtrip.iter()));
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "hashbrown"))]
+    fn hashbrown_map_roundtrip() {
+        use core::hash::BuildHasherDefault;
+        use hashbrown::HashMap;
+        use std::collections::hash_map::DefaultHasher;
+
+        let mut values: HashMap<u32, String, BuildHasherDefault<DefaultHasher>> =
+            HashMap::default();
+        values.insert(1, "one".to_string());
+        values.insert(0, String::new());
+
+        check_collection_type(
+            values,
+            7,
+            WireType::LengthDelimited,
+            |tag, values, buf| {
+                hashbrown_map::encode(
+                    uint32::encode,
+                    uint32::encoded_len,
+                    string::encode,
+                    string::encoded_len,
+                    tag,
+                    values,
+                    buf,
+                )
+            },
+            |wire_type, values, buf, ctx| {
+                check_wire_type(WireType::LengthDelimited, wire_type)?;
+                hashbrown_map::merge(uint32::merge, string::merge, values, buf, ctx)
+            },
+            |tag, values| {
+                hashbrown_map::encoded_len(uint32::encoded_len, string::encoded_len, tag, values)
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn is_version() {
+        const _: () = assert!(super::is_version(VERSION));
+        assert!(!super::is_version(""));
+        assert!(!super::is_version("0.0.0"));
+        assert!(!super::is_version(&format!("{}.0", VERSION)));
+    }
+
     /// This big bowl o' macro soup generates an encoding property test for each combination of map
     /// type, scalar map key, and value type.
     /// TODO: these tests take a long time to compile, can this be improved?
@@ -0,0 +1,137 @@
+//! Helpers for computing the maximum encoded length of derived messages at compile time.
+//!
+//! An unbounded length is represented by `None`, which every helper propagates. Lengths which
+//! overflow `usize` are unbounded as well.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ::bytes::Bytes;
+
+/// A field value container, whose capacity limits the encoded length of the field.
+pub trait Capacity {
+    /// The type of the elements of the container.
+    type Item;
+
+    /// The maximum number of elements the container can hold, or `None` if it can grow without
+    /// limit. For `string` and `bytes` values, this is the maximum length in bytes.
+    const CAPACITY: Option<usize>;
+}
+
+impl<T: Capacity> Capacity for Option<T> {
+    type Item = T::Item;
+    const CAPACITY: Option<usize> = T::CAPACITY;
+}
+
+impl Capacity for String {
+    type Item = u8;
+    const CAPACITY: Option<usize> = None;
+}
+
+impl Capacity for Bytes {
+    type Item = u8;
+    const CAPACITY: Option<usize> = None;
+}
+
+impl<T> Capacity for Vec<T> {
+    type Item = T;
+    const CAPACITY: Option<usize> = None;
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> Capacity for heapless::String<N> {
+    type Item = u8;
+    const CAPACITY: Option<usize> = Some(N);
+}
+
+#[cfg(feature = "heapless")]
+impl<T, const N: usize> Capacity for heapless::Vec<T, N> {
+    type Item = T;
+    const CAPACITY: Option<usize> = Some(N);
+}
+
+/// Returns the sum of the lengths.
+pub const fn sum(lens: &[Option<usize>]) -> Option<usize> {
+    let mut total = 0usize;
+    let mut i = 0;
+    while i < lens.len() {
+        total = match lens[i] {
+            Some(len) => match total.checked_add(len) {
+                Some(total) => total,
+                None => return None,
+            },
+            None => return None,
+        };
+        i += 1;
+    }
+    Some(total)
+}
+
+/// Returns the largest of the lengths, or `Some(0)` if there are none.
+pub const fn max(lens: &[Option<usize>]) -> Option<usize> {
+    let mut largest = 0;
+    let mut i = 0;
+    while i < lens.len() {
+        match lens[i] {
+            Some(len) if len > largest => largest = len,
+            Some(_) => {}
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(largest)
+}
+
+/// Returns the length of `count` values of length `len`.
+pub const fn repeated(count: Option<usize>, len: Option<usize>) -> Option<usize> {
+    match (count, len) {
+        (Some(count), Some(len)) => count.checked_mul(len),
+        _ => None,
+    }
+}
+
+/// Returns the length of a length-delimited field with the given key length, whose value is at
+/// most `len` bytes long.
+pub const fn length_delimited(key_len: usize, len: Option<usize>) -> Option<usize> {
+    match len {
+        Some(len) => sum(&[
+            Some(key_len),
+            Some(super::encoded_len_varint(len as u64)),
+            Some(len),
+        ]),
+        None => None,
+    }
+}
+
+/// Returns the length of a group field with the given key length, whose fields are at most `len`
+/// bytes long.
+pub const fn group(key_len: usize, len: Option<usize>) -> Option<usize> {
+    sum(&[Some(key_len), len, Some(key_len)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn propagates_unbounded_lengths() {
+        assert_eq!(sum(&[]), Some(0));
+        assert_eq!(sum(&[Some(1), Some(2)]), Some(3));
+        assert_eq!(sum(&[Some(1), None]), None);
+        assert_eq!(sum(&[Some(usize::MAX), Some(1)]), None);
+
+        assert_eq!(max(&[]), Some(0));
+        assert_eq!(max(&[Some(1), Some(3), Some(2)]), Some(3));
+        assert_eq!(max(&[Some(1), None]), None);
+
+        assert_eq!(repeated(Some(3), Some(2)), Some(6));
+        assert_eq!(repeated(None, Some(2)), None);
+        assert_eq!(repeated(Some(usize::MAX), Some(2)), None);
+
+        assert_eq!(length_delimited(1, Some(127)), Some(129));
+        assert_eq!(length_delimited(1, Some(128)), Some(131));
+        assert_eq!(length_delimited(1, None), None);
+
+        assert_eq!(group(2, Some(3)), Some(7));
+    }
+}
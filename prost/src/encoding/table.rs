@@ -0,0 +1,360 @@
+//! Table-driven encoding and decoding of messages.
+//!
+//! `#[derive(Message)]` with `#[prost(table_driven)]` generates a static table with an entry for
+//! every field, and implements [`Message`](crate::Message) with the interpreter functions of this
+//! module. The entries only encode to [`DynBufMut`] and decode from [`DynBuf`], so the field
+//! encodings are compiled once for every message rather than once for every message and buffer
+//! type. This makes binaries smaller, at the cost of some speed.
+//!
+//! The interpreter functions are never inlined, so that the compiler does not inline the entries
+//! of the constant table back into every message.
+
+use ::bytes::buf::UninitSlice;
+use ::bytes::{Buf, BufMut};
+
+use super::{skip_field, DecodeContext, WireType};
+use crate::DecodeError;
+
+/// A message which is encoded and decoded with a field table.
+pub trait TableMessage: Sized + 'static {
+    /// The field table of the message.
+    const TABLE: &'static MessageTable<Self>;
+}
+
+/// The field table of a message.
+pub struct MessageTable<M: 'static> {
+    /// The name of the message, for decode errors.
+    pub name: &'static str,
+    /// The fields of the message, in encoding order.
+    pub fields: &'static [FieldTable<M>],
+    /// The tags of the message, sorted by tag, with the index of their field in `fields`.
+    pub tags: &'static [(u32, usize)],
+}
+
+/// Merges a value with the given tag and wire type into a field of a message.
+pub type MergeFn<M> =
+    fn(&mut M, u32, WireType, &mut DynBuf<'_>, DecodeContext) -> Result<(), DecodeError>;
+
+/// An entry of a field table, for a single field or oneof of a message.
+pub struct FieldTable<M> {
+    /// The name of the field, for decode errors.
+    pub name: &'static str,
+    /// Encodes the field, if it is set.
+    pub encode: fn(&M, &mut DynBufMut<'_>),
+    /// Merges a value with the given tag and wire type into the field.
+    pub merge: MergeFn<M>,
+    /// Returns the encoded length of the field.
+    pub encoded_len: fn(&M) -> usize,
+    /// Merges the field of another message into the field.
+    pub merge_from: fn(&mut M, &M),
+}
+
+/// A [`Buf`] which reads from a buffer of any type.
+///
+/// Unlike `dyn Buf`, only the required methods of `Buf` are compiled for every buffer type, and
+/// the provided methods are compiled once.
+pub struct DynBuf<'a>(&'a mut dyn RawBuf);
+
+impl<'a> DynBuf<'a> {
+    /// Wraps a buffer.
+    pub fn new(buf: &'a mut impl Buf) -> Self {
+        DynBuf(buf)
+    }
+}
+
+impl Buf for DynBuf<'_> {
+    fn remaining(&self) -> usize {
+        self.0.remaining()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.0.chunk()
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.0.advance(cnt)
+    }
+}
+
+/// The required methods of [`Buf`].
+trait RawBuf {
+    fn remaining(&self) -> usize;
+    fn chunk(&self) -> &[u8];
+    fn advance(&mut self, cnt: usize);
+}
+
+impl<B: Buf> RawBuf for B {
+    fn remaining(&self) -> usize {
+        Buf::remaining(self)
+    }
+
+    fn chunk(&self) -> &[u8] {
+        Buf::chunk(self)
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        Buf::advance(self, cnt)
+    }
+}
+
+/// A [`BufMut`] which writes to a buffer of any type.
+///
+/// Unlike `dyn BufMut`, only the required methods of `BufMut` are compiled for every buffer type,
+/// and the provided methods are compiled once.
+pub struct DynBufMut<'a>(&'a mut dyn RawBufMut);
+
+impl<'a> DynBufMut<'a> {
+    /// Wraps a buffer.
+    pub fn new(buf: &'a mut impl BufMut) -> Self {
+        DynBufMut(buf)
+    }
+}
+
+// SAFETY: the methods forward to a `BufMut` implementation.
+unsafe impl BufMut for DynBufMut<'_> {
+    fn remaining_mut(&self) -> usize {
+        self.0.remaining_mut()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        self.0.advance_mut(cnt)
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        self.0.chunk_mut()
+    }
+}
+
+/// The required methods of [`BufMut`].
+trait RawBufMut {
+    fn remaining_mut(&self) -> usize;
+    unsafe fn advance_mut(&mut self, cnt: usize);
+    fn chunk_mut(&mut self) -> &mut UninitSlice;
+}
+
+impl<B: BufMut> RawBufMut for B {
+    fn remaining_mut(&self) -> usize {
+        BufMut::remaining_mut(self)
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        BufMut::advance_mut(self, cnt)
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        BufMut::chunk_mut(self)
+    }
+}
+
+/// Encodes the fields of `message` to a buffer.
+#[inline(never)]
+pub fn encode_raw<M>(message: &M, table: &MessageTable<M>, buf: &mut DynBufMut<'_>) {
+    for field in table.fields {
+        (field.encode)(message, buf);
+    }
+}
+
+/// Decodes a field from a buffer, and merges it into `message`.
+///
+/// Fields with a tag which is not in the table are skipped.
+#[inline(never)]
+pub fn merge_field<M>(
+    message: &mut M,
+    table: &MessageTable<M>,
+    tag: u32,
+    wire_type: WireType,
+    buf: &mut DynBuf<'_>,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    match field_index(table.tags, tag) {
+        Some(index) => {
+            let field = &table.fields[index];
+            (field.merge)(message, tag, wire_type, buf, ctx)
+                .map_err(|error| push_location(error, table.name, field.name))
+        }
+        None => skip_field(wire_type, tag, buf, ctx),
+    }
+}
+
+/// Returns the index of the field with the given tag.
+///
+/// Split from `merge_field`, so that it is compiled once rather than once for every message.
+#[inline(never)]
+fn field_index(tags: &[(u32, usize)], tag: u32) -> Option<usize> {
+    tags.binary_search_by_key(&tag, |&(tag, _)| tag)
+        .ok()
+        .map(|index| tags[index].1)
+}
+
+#[cold]
+#[inline(never)]
+fn push_location(
+    mut error: DecodeError,
+    message: &'static str,
+    field: &'static str,
+) -> DecodeError {
+    error.push(message, field);
+    error
+}
+
+/// Merges the fields of `other` into `message`.
+#[inline(never)]
+pub fn merge_from<M>(message: &mut M, other: &M, table: &MessageTable<M>) {
+    for field in table.fields {
+        (field.merge_from)(message, other);
+    }
+}
+
+/// Returns the encoded length of the fields of `message`.
+#[inline(never)]
+pub fn encoded_len<M>(message: &M, table: &MessageTable<M>) -> usize {
+    table
+        .fields
+        .iter()
+        .map(|field| (field.encoded_len)(message))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::encoding::{int32, string};
+    use crate::Message;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Pair {
+        id: i32,
+        names: Vec<String>,
+    }
+
+    impl TableMessage for Pair {
+        const TABLE: &'static MessageTable<Self> = &MessageTable {
+            name: "Pair",
+            fields: &[
+                FieldTable {
+                    name: "names",
+                    encode: |message: &Pair, buf| string::encode_repeated(1, &message.names, buf),
+                    merge: |message: &mut Pair, _, wire_type, buf, ctx| {
+                        string::merge_repeated(wire_type, &mut message.names, buf, ctx)
+                    },
+                    encoded_len: |message: &Pair| string::encoded_len_repeated(1, &message.names),
+                    merge_from: |message: &mut Pair, other: &Pair| {
+                        message.names.extend(other.names.iter().cloned())
+                    },
+                },
+                FieldTable {
+                    name: "id",
+                    encode: |message: &Pair, buf| {
+                        if message.id != 0 {
+                            int32::encode(3, &message.id, buf)
+                        }
+                    },
+                    merge: |message: &mut Pair, _, wire_type, buf, ctx| {
+                        int32::merge(wire_type, &mut message.id, buf, ctx)
+                    },
+                    encoded_len: |message: &Pair| {
+                        if message.id != 0 {
+                            int32::encoded_len(3, &message.id)
+                        } else {
+                            0
+                        }
+                    },
+                    merge_from: |message: &mut Pair, other: &Pair| {
+                        if other.id != 0 {
+                            message.id = other.id;
+                        }
+                    },
+                },
+            ],
+            tags: &[(1, 0), (3, 1)],
+        };
+    }
+
+    impl Message for Pair {
+        fn encode_raw(&self, buf: &mut impl BufMut) {
+            encode_raw(self, Self::TABLE, &mut DynBufMut::new(buf))
+        }
+
+        fn merge_field(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut impl Buf,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            merge_field(
+                self,
+                Self::TABLE,
+                tag,
+                wire_type,
+                &mut DynBuf::new(buf),
+                ctx,
+            )
+        }
+
+        fn encoded_len(&self) -> usize {
+            encoded_len(self, Self::TABLE)
+        }
+
+        fn merge_from(&mut self, other: &Self) {
+            merge_from(self, other, Self::TABLE)
+        }
+
+        fn clear(&mut self) {
+            *self = Pair::default();
+        }
+    }
+
+    #[test]
+    fn table_roundtrip() {
+        let pair = Pair {
+            id: 150,
+            names: vec!["a".to_string(), "bc".to_string()],
+        };
+        let buf = pair.encode_to_vec();
+        assert_eq!(buf, b"\x0a\x01a\x0a\x02bc\x18\x96\x01");
+        assert_eq!(pair.encoded_len(), buf.len());
+        assert_eq!(Pair::decode(buf.as_slice()).unwrap(), pair);
+
+        // Unknown fields are skipped.
+        assert_eq!(
+            Pair::decode(&b"\x10\x01\x18\x02"[..]).unwrap(),
+            Pair {
+                id: 2,
+                names: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn table_merge_from() {
+        let mut pair = Pair {
+            id: 1,
+            names: vec!["a".to_string()],
+        };
+        pair.merge_from(&Pair {
+            id: 0,
+            names: vec!["b".to_string()],
+        });
+        assert_eq!(
+            pair,
+            Pair {
+                id: 1,
+                names: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn table_decode_error_path() {
+        let error = Pair::decode(&b"\x18\x80"[..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to decode Protobuf message: Pair.id: invalid varint"
+        );
+    }
+}
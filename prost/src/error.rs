@@ -1,9 +1,11 @@
 //! Protobuf encoding and decoding errors.
 
 use alloc::borrow::Cow;
-#[cfg(not(feature = "std"))]
+#[cfg(all(not(feature = "std"), not(feature = "no-alloc-decode-error")))]
 use alloc::boxed::Box;
 #[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(all(not(feature = "std"), not(feature = "no-alloc-decode-error")))]
 use alloc::vec::Vec;
 
 use core::fmt;
@@ -13,11 +15,22 @@ use core::fmt;
 /// `DecodeError` indicates that the input buffer does not contain a valid
 /// Protobuf message. The error details should be considered 'best effort': in
 /// general it is not possible to exactly pinpoint why data is malformed.
+///
+/// With the `no-alloc-decode-error` feature, creating a `DecodeError` with a
+/// static description does not allocate, and the location stack is limited to
+/// the innermost two levels of nesting.
 #[derive(Clone, PartialEq, Eq)]
 pub struct DecodeError {
+    #[cfg(not(feature = "no-alloc-decode-error"))]
     inner: Box<Inner>,
+    #[cfg(feature = "no-alloc-decode-error")]
+    inner: Inner,
 }
 
+/// The maximum number of (message, field) name pairs kept without allocating.
+#[cfg(feature = "no-alloc-decode-error")]
+const MAX_STACK_DEPTH: usize = 2;
+
 #[derive(Clone, PartialEq, Eq)]
 struct Inner {
     /// A 'best effort' root cause description.
@@ -25,7 +38,10 @@ struct Inner {
     /// A stack of (message, field) name pairs, which identify the specific
     /// message type and field where decoding failed. The stack contains an
     /// entry per level of nesting.
+    #[cfg(not(feature = "no-alloc-decode-error"))]
     stack: Vec<(&'static str, &'static str)>,
+    #[cfg(feature = "no-alloc-decode-error")]
+    stack: heapless::Vec<(&'static str, &'static str), MAX_STACK_DEPTH>,
 }
 
 impl DecodeError {
@@ -35,20 +51,36 @@ impl DecodeError {
     #[doc(hidden)]
     #[cold]
     pub fn new(description: impl Into<Cow<'static, str>>) -> DecodeError {
+        let inner = Inner {
+            description: description.into(),
+            stack: Default::default(),
+        };
         DecodeError {
-            inner: Box::new(Inner {
-                description: description.into(),
-                stack: Vec::new(),
-            }),
+            #[cfg(not(feature = "no-alloc-decode-error"))]
+            inner: Box::new(inner),
+            #[cfg(feature = "no-alloc-decode-error")]
+            inner,
         }
     }
 
+    /// Creates a new `DecodeError` for a value which exceeds the capacity of
+    /// a fixed capacity field.
+    #[cfg(feature = "heapless")]
+    #[cold]
+    pub(crate) fn capacity_exceeded() -> DecodeError {
+        DecodeError::new("field capacity exceeded")
+    }
+
     /// Pushes a (message, field) name location pair on to the location stack.
     ///
     /// Meant to be used only by `Message` implementations.
     #[doc(hidden)]
     pub fn push(&mut self, message: &'static str, field: &'static str) {
+        #[cfg(not(feature = "no-alloc-decode-error"))]
         self.inner.stack.push((message, field));
+        // Drop the outermost levels of nesting once the stack is full.
+        #[cfg(feature = "no-alloc-decode-error")]
+        let _ = self.inner.stack.push((message, field));
     }
 }
 
@@ -148,3 +180,80 @@ impl fmt::Display for UnknownEnumValue {
 
 #[cfg(feature = "std")]
 impl std::error::Error for UnknownEnumValue {}
+
+/// An error indicating that a generated message builder is missing a value for a required field.
+///
+/// Only Protobuf `proto2` messages can have required fields.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MissingRequiredField {
+    /// The fully qualified name of the message.
+    pub message: &'static str,
+    /// The name of the missing field.
+    pub field: &'static str,
+}
+
+impl fmt::Display for MissingRequiredField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "missing required field {}.{}", self.message, self.field)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MissingRequiredField {}
+
+/// An error returned by a service dispatcher generated by `prost-build`.
+///
+/// Dispatchers decode an encoded request, call the service method it is addressed to and encode
+/// the response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DispatchError<E> {
+    /// The service has no method with the requested name.
+    UnknownMethod(String),
+    /// The request could not be decoded as the input message of the method.
+    Decode(DecodeError),
+    /// The service method returned an error.
+    Service(E),
+}
+
+impl<E: fmt::Display> fmt::Display for DispatchError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatchError::UnknownMethod(method) => write!(f, "unknown method {}", method),
+            DispatchError::Decode(error) => write!(f, "failed to decode request: {}", error),
+            DispatchError::Service(error) => error.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for DispatchError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DispatchError::UnknownMethod(_) => None,
+            DispatchError::Decode(error) => Some(error),
+            DispatchError::Service(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn decode_error_location_stack() {
+        let mut error = DecodeError::new("invalid varint");
+        error.push("Inner", "a");
+        error.push("Middle", "b");
+        error.push("Outer", "c");
+
+        #[cfg(not(feature = "no-alloc-decode-error"))]
+        let expected =
+            "failed to decode Protobuf message: Inner.a: Middle.b: Outer.c: invalid varint";
+        #[cfg(feature = "no-alloc-decode-error")]
+        let expected = "failed to decode Protobuf message: Inner.a: Middle.b: invalid varint";
+        assert_eq!(error.to_string(), expected);
+    }
+}
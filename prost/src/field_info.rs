@@ -0,0 +1,16 @@
+//! Static information about the fields of a generated [`Message`](crate::Message).
+
+/// Names and number of a message field, as listed in the `FIELDS` table which `prost-build`
+/// generates for messages when field constants are enabled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FieldInfo {
+    /// The field number, e.g. `1`.
+    pub number: u32,
+    /// The field name as it appears in the source .proto file, e.g. `foo_bar`.
+    pub name: &'static str,
+    /// The JSON name of the field, e.g. `fooBar`.
+    pub json_name: &'static str,
+    /// The Rust identifier of the field, e.g. `foo_bar`. For fields in a `oneof`, this is the
+    /// identifier of the enum variant, e.g. `FooBar`.
+    pub rust_ident: &'static str,
+}
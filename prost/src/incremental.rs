@@ -0,0 +1,513 @@
+//! Decoding of messages from partially received buffers.
+//!
+//! [`IncrementalDecoder`] accepts the encoded message in chunks of arbitrary size, as they arrive
+//! from the network, and keeps its decode state between the chunks. Fields are merged into the
+//! message as soon as they are complete, and singular message and group fields are decoded field
+//! by field, so that only the values of the remaining length delimited fields are buffered.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::encoding::{
+    decode_key, decode_varint, encode_key, encode_varint, DecodeContext, WireType,
+};
+use crate::meta::{Cardinality, FieldType, MessageDescriptor, MessageMeta, MessageType};
+use crate::{DecodeError, Message};
+
+/// The default maximum number of bytes buffered for a single field.
+pub const DEFAULT_MAX_LOOKAHEAD: usize = 4 * 1024 * 1024;
+
+/// A decoder which accepts an encoded message in chunks.
+///
+/// # Example
+///
+/// ```rust
+/// # use prost::Message;
+/// # use prost::incremental::IncrementalDecoder;
+/// #[derive(Clone, PartialEq, Message)]
+/// #[prost(meta)]
+/// struct Frame {
+///     #[prost(uint64, tag = "1")]
+///     id: u64,
+///     #[prost(string, tag = "2")]
+///     payload: String,
+/// }
+///
+/// let frame = Frame { id: 1, payload: "payload".to_string() };
+/// let buf = frame.encode_length_delimited_to_vec();
+///
+/// let mut decoder = IncrementalDecoder::<Frame>::length_delimited();
+/// for chunk in buf.chunks(3) {
+///     decoder.feed(chunk)?;
+/// }
+///
+/// assert_eq!(decoder.next_message(), Some(frame));
+/// # Ok::<(), prost::DecodeError>(())
+/// ```
+pub struct IncrementalDecoder<M> {
+    /// Whether the messages are preceded by their length delimiter.
+    delimited: bool,
+    max_lookahead: usize,
+    /// The message being decoded.
+    message: M,
+    /// The decoded messages which were not yet returned.
+    messages: VecDeque<M>,
+    state: State,
+    /// The bytes of the partially received varint or value.
+    pending: Vec<u8>,
+    /// The number of bytes of the message received so far.
+    position: usize,
+    /// The length of the message, if it is length delimited.
+    end: Option<usize>,
+    /// The message and group fields being decoded, from the outermost to the innermost.
+    stack: Vec<Frame>,
+}
+
+impl<M> IncrementalDecoder<M>
+where
+    M: Message + MessageMeta + Default,
+{
+    /// Creates a decoder for a single message, which extends until the end of the input.
+    ///
+    /// The message is complete once [`finish`][Self::finish] is called.
+    pub fn new() -> Self {
+        Self::with_framing(false)
+    }
+
+    /// Creates a decoder for a sequence of messages, each preceded by its length delimiter as
+    /// written by [`Message::encode_length_delimited`].
+    ///
+    /// Each message is complete as soon as its last byte is fed to the decoder.
+    pub fn length_delimited() -> Self {
+        Self::with_framing(true)
+    }
+
+    fn with_framing(delimited: bool) -> Self {
+        IncrementalDecoder {
+            delimited,
+            max_lookahead: DEFAULT_MAX_LOOKAHEAD,
+            message: M::default(),
+            messages: VecDeque::new(),
+            state: if delimited {
+                State::Delimiter
+            } else {
+                State::Key
+            },
+            pending: Vec::new(),
+            position: 0,
+            end: None,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of bytes buffered for a single field.
+    ///
+    /// The values of length delimited fields, other than singular message fields, are buffered
+    /// until they are complete, as are groups other than singular group fields. Decoding fails if
+    /// such a field is longer than the limit.
+    ///
+    /// Defaults to [`DEFAULT_MAX_LOOKAHEAD`].
+    pub fn max_lookahead(&mut self, max_lookahead: usize) -> &mut Self {
+        self.max_lookahead = max_lookahead;
+        self
+    }
+
+    /// Decodes the next chunk of the input.
+    ///
+    /// After an error, the state of the decoder is unspecified and it should be discarded.
+    pub fn feed(&mut self, mut chunk: &[u8]) -> Result<(), DecodeError> {
+        loop {
+            self.unwind()?;
+            if let State::Bytes { len, .. } = self.state {
+                if self.pending.len() == len {
+                    self.complete_bytes()?;
+                    continue;
+                }
+            }
+            let byte = match chunk.first() {
+                Some(&byte) => byte,
+                None => return Ok(()),
+            };
+            if let State::Delimiter = self.state {
+                chunk = &chunk[1..];
+                if let Some(len) = self.push_varint_byte(byte)? {
+                    self.start_message(len)?;
+                }
+                continue;
+            }
+            if self.limit().is_some_and(|limit| self.position >= limit) {
+                return Err(DecodeError::new("delimited length exceeded"));
+            }
+            match self.state {
+                State::Bytes { len, .. } => {
+                    let n = usize::min(len - self.pending.len(), chunk.len());
+                    self.pending.extend_from_slice(&chunk[..n]);
+                    self.position += n;
+                    chunk = &chunk[n..];
+                }
+                _ => {
+                    chunk = &chunk[1..];
+                    self.position += 1;
+                    if let Some(value) = self.push_varint_byte(byte)? {
+                        self.complete_varint(value)?;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Signals the end of the input.
+    ///
+    /// If the decoder was created with [`new`][Self::new], this completes the message. Fails if
+    /// the input ends in the middle of a field or message.
+    pub fn finish(&mut self) -> Result<(), DecodeError> {
+        self.unwind()?;
+        let complete = match self.state {
+            State::Delimiter => self.pending.is_empty(),
+            State::Key => !self.delimited && self.pending.is_empty() && self.stack.is_empty(),
+            _ => false,
+        };
+        if !complete {
+            return Err(DecodeError::new("buffer underflow"));
+        }
+        if !self.delimited {
+            self.complete_message();
+        }
+        Ok(())
+    }
+
+    /// Returns the next decoded message, if any.
+    pub fn next_message(&mut self) -> Option<M> {
+        self.messages.pop_front()
+    }
+
+    /// Adds a byte to the pending varint, and returns the varint if it is complete.
+    fn push_varint_byte(&mut self, byte: u8) -> Result<Option<u64>, DecodeError> {
+        self.pending.push(byte);
+        if byte >= 0x80 {
+            if self.pending.len() >= 10 {
+                return Err(DecodeError::new("invalid varint"));
+            }
+            return Ok(None);
+        }
+        let value = decode_varint(&mut self.pending.as_slice())?;
+        self.pending.clear();
+        Ok(Some(value))
+    }
+
+    fn start_message(&mut self, len: u64) -> Result<(), DecodeError> {
+        let len =
+            usize::try_from(len).map_err(|_| DecodeError::new("delimited length exceeded"))?;
+        self.end = Some(len);
+        self.position = 0;
+        self.state = State::Key;
+        Ok(())
+    }
+
+    fn complete_message(&mut self) {
+        let message = core::mem::take(&mut self.message);
+        self.messages.push_back(message);
+        self.position = 0;
+        self.end = None;
+    }
+
+    /// Returns the position at which the innermost message ends, if it is known.
+    fn limit(&self) -> Option<usize> {
+        match self.stack.last() {
+            Some(frame) => frame.end,
+            None => self.end,
+        }
+    }
+
+    /// Leaves the message fields which are complete, and completes the message if it is length
+    /// delimited and complete.
+    fn unwind(&mut self) -> Result<(), DecodeError> {
+        if !matches!(self.state, State::Key) || !self.pending.is_empty() {
+            return Ok(());
+        }
+        while let Some(frame) = self.stack.last() {
+            if frame.end != Some(self.position) {
+                return Ok(());
+            }
+            if !matches!(frame.kind, FrameKind::Message(_)) {
+                return Err(DecodeError::new("delimited length exceeded"));
+            }
+            self.stack.pop();
+        }
+        if self.delimited && self.end == Some(self.position) {
+            self.complete_message();
+            self.state = State::Delimiter;
+        }
+        Ok(())
+    }
+
+    fn complete_varint(&mut self, value: u64) -> Result<(), DecodeError> {
+        match self.state {
+            State::Key => {
+                let mut key = Vec::with_capacity(10);
+                encode_varint(value, &mut key);
+                let (tag, wire_type) = decode_key(&mut key.as_slice())?;
+                self.start_field(tag, wire_type)
+            }
+            State::Varint { tag } => {
+                let mut record = Vec::with_capacity(20);
+                encode_key(tag, WireType::Varint, &mut record);
+                encode_varint(value, &mut record);
+                self.state = State::Key;
+                self.emit(record)
+            }
+            State::Length { tag } => {
+                let len = self.check_len(value)?;
+                match self.descend(tag, |ty| match ty {
+                    FieldType::Message(message_type) => Some(message_type),
+                    _ => None,
+                }) {
+                    Some(descriptor) => {
+                        self.state = State::Key;
+                        self.stack.push(Frame {
+                            tag,
+                            end: Some(self.position + len),
+                            kind: FrameKind::Message(descriptor),
+                        });
+                        self.emit_empty()
+                    }
+                    None => self.read_bytes(tag, WireType::LengthDelimited, len),
+                }
+            }
+            State::Delimiter | State::Bytes { .. } => unreachable!(),
+        }
+    }
+
+    fn start_field(&mut self, tag: u32, wire_type: WireType) -> Result<(), DecodeError> {
+        match wire_type {
+            WireType::Varint => self.state = State::Varint { tag },
+            WireType::SixtyFourBit => {
+                let len = self.check_len(8)?;
+                return self.read_bytes(tag, wire_type, len);
+            }
+            WireType::LengthDelimited => self.state = State::Length { tag },
+            WireType::ThirtyTwoBit => {
+                let len = self.check_len(4)?;
+                return self.read_bytes(tag, wire_type, len);
+            }
+            WireType::StartGroup => {
+                let end = self.limit();
+                match self.descend(tag, |ty| match ty {
+                    FieldType::Group(message_type) => Some(message_type),
+                    _ => None,
+                }) {
+                    Some(descriptor) => {
+                        self.stack.push(Frame {
+                            tag,
+                            end,
+                            kind: FrameKind::Group(descriptor),
+                        });
+                        return self.emit_empty();
+                    }
+                    None => {
+                        let mut buf = Vec::new();
+                        encode_key(tag, WireType::StartGroup, &mut buf);
+                        self.stack.push(Frame {
+                            tag,
+                            end,
+                            kind: FrameKind::Capture(buf),
+                        });
+                    }
+                }
+            }
+            WireType::EndGroup => {
+                let frame = match self.stack.pop() {
+                    Some(frame) if frame.tag == tag => frame,
+                    _ => return Err(DecodeError::new("unexpected end group tag")),
+                };
+                match frame.kind {
+                    FrameKind::Message(_) => {
+                        return Err(DecodeError::new("unexpected end group tag"))
+                    }
+                    FrameKind::Group(_) => {}
+                    FrameKind::Capture(mut record) => {
+                        encode_key(tag, WireType::EndGroup, &mut record);
+                        return self.emit(record);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the descriptor of the field with the given tag if it is a singular message or
+    /// group field which can be merged field by field.
+    fn descend(
+        &self,
+        tag: u32,
+        message_type: impl FnOnce(FieldType) -> Option<MessageType>,
+    ) -> Option<&'static MessageDescriptor> {
+        let descriptor = match self.stack.last() {
+            Some(Frame {
+                kind: FrameKind::Message(descriptor) | FrameKind::Group(descriptor),
+                ..
+            }) => *descriptor,
+            Some(Frame {
+                kind: FrameKind::Capture(_),
+                ..
+            }) => return None,
+            None => M::DESCRIPTOR,
+        };
+        let field = descriptor.field_by_tag(tag)?;
+        if field.cardinality == Cardinality::Repeated {
+            return None;
+        }
+        message_type(field.ty).map(|message_type| message_type.descriptor())
+    }
+
+    /// Checks that a value of `len` bytes fits into the innermost message.
+    fn check_len(&self, len: u64) -> Result<usize, DecodeError> {
+        usize::try_from(len)
+            .ok()
+            .filter(|&len| {
+                self.limit()
+                    .map_or(true, |limit| len <= limit - self.position)
+            })
+            .ok_or_else(|| DecodeError::new("buffer underflow"))
+    }
+
+    fn read_bytes(&mut self, tag: u32, wire_type: WireType, len: usize) -> Result<(), DecodeError> {
+        if len > self.max_lookahead {
+            return Err(DecodeError::new("field exceeds the lookahead limit"));
+        }
+        self.state = State::Bytes {
+            tag,
+            wire_type,
+            len,
+        };
+        Ok(())
+    }
+
+    fn complete_bytes(&mut self) -> Result<(), DecodeError> {
+        let (tag, wire_type) = match self.state {
+            State::Bytes { tag, wire_type, .. } => (tag, wire_type),
+            _ => unreachable!(),
+        };
+        let mut record = Vec::with_capacity(self.pending.len() + 20);
+        encode_key(tag, wire_type, &mut record);
+        if wire_type == WireType::LengthDelimited {
+            encode_varint(self.pending.len() as u64, &mut record);
+        }
+        record.append(&mut self.pending);
+        self.state = State::Key;
+        self.emit(record)
+    }
+
+    /// Merges an empty occurrence of the innermost message or group field, so that the field is
+    /// set even if it contains no fields.
+    fn emit_empty(&mut self) -> Result<(), DecodeError> {
+        let frame = self.stack.pop().expect("no message field");
+        let result = self.emit(frame.wrap(Vec::new()));
+        self.stack.push(frame);
+        result
+    }
+
+    /// Merges an encoded field of the innermost message into the message.
+    fn emit(&mut self, record: Vec<u8>) -> Result<(), DecodeError> {
+        if let Some(Frame {
+            kind: FrameKind::Capture(buf),
+            ..
+        }) = self.stack.last_mut()
+        {
+            if buf.len() + record.len() > self.max_lookahead {
+                return Err(DecodeError::new("field exceeds the lookahead limit"));
+            }
+            buf.extend_from_slice(&record);
+            return Ok(());
+        }
+        let record = self
+            .stack
+            .iter()
+            .rev()
+            .fold(record, |record, frame| frame.wrap(record));
+        let mut buf = record.as_slice();
+        let (tag, wire_type) = decode_key(&mut buf)?;
+        self.message
+            .merge_field(tag, wire_type, &mut buf, DecodeContext::default())
+    }
+}
+
+impl<M> Default for IncrementalDecoder<M>
+where
+    M: Message + MessageMeta + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> fmt::Debug for IncrementalDecoder<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncrementalDecoder")
+            .field("delimited", &self.delimited)
+            .field("max_lookahead", &self.max_lookahead)
+            .field("state", &self.state)
+            .field("position", &self.position)
+            .field("end", &self.end)
+            .field("stack", &self.stack)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    /// Reading the length delimiter of a message.
+    Delimiter,
+    /// Reading the key of a field.
+    Key,
+    /// Reading the value of a varint field.
+    Varint { tag: u32 },
+    /// Reading the length delimiter of a length delimited field.
+    Length { tag: u32 },
+    /// Reading the `len` bytes of a fixed width or length delimited value.
+    Bytes {
+        tag: u32,
+        wire_type: WireType,
+        len: usize,
+    },
+}
+
+/// A message or group field being decoded.
+#[derive(Debug)]
+struct Frame {
+    tag: u32,
+    /// The position at which the field ends, if it is known.
+    end: Option<usize>,
+    kind: FrameKind,
+}
+
+#[derive(Debug)]
+enum FrameKind {
+    /// A singular message field, which is merged field by field.
+    Message(&'static MessageDescriptor),
+    /// A singular group field, which is merged field by field.
+    Group(&'static MessageDescriptor),
+    /// Any other group, which is buffered until it ends.
+    Capture(Vec<u8>),
+}
+
+impl Frame {
+    /// Wraps an encoded field of the message in an occurrence of this field.
+    fn wrap(&self, record: Vec<u8>) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(record.len() + 20);
+        match self.kind {
+            FrameKind::Message(_) => {
+                encode_key(self.tag, WireType::LengthDelimited, &mut buf);
+                encode_varint(record.len() as u64, &mut buf);
+                buf.extend_from_slice(&record);
+            }
+            FrameKind::Group(_) | FrameKind::Capture(_) => {
+                encode_key(self.tag, WireType::StartGroup, &mut buf);
+                buf.extend_from_slice(&record);
+                encode_key(self.tag, WireType::EndGroup, &mut buf);
+            }
+        }
+        buf
+    }
+}
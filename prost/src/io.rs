@@ -0,0 +1,181 @@
+//! Encoding of messages to a [`Write`], without encoding them into memory first.
+//!
+//! [`WriteBuf`] is the [`BufMut`] behind [`Message::encode_to_writer`][1]. Use `encode_to_writer`
+//! instead of `encode_to_vec` when the message is only written to a file or socket, so that memory
+//! usage is bounded by the intermediate buffer rather than by the size of the message.
+//!
+//! [1]: crate::Message::encode_to_writer
+
+use std::io::{self, Write};
+
+use bytes::buf::UninitSlice;
+use bytes::{Buf, BufMut};
+
+/// The capacity of the intermediate buffer, which matches `std::io::BufWriter`.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// A buffer which writes its contents to a writer once it is full.
+///
+/// Values which do not fit into the buffer are written to the writer directly, so that large
+/// `Bytes` fields are never copied. Since `BufMut` methods can't fail, the first error returned
+/// by the writer is kept until [`finish`][Self::finish], and all output following it is discarded.
+pub(crate) struct WriteBuf<'a, W: Write> {
+    writer: &'a mut W,
+    buf: Vec<u8>,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: Write> WriteBuf<'a, W> {
+    pub(crate) fn new(writer: &'a mut W) -> Self {
+        WriteBuf {
+            writer,
+            buf: Vec::with_capacity(BUFFER_SIZE),
+            error: None,
+        }
+    }
+
+    /// Writes the remaining contents of the buffer, and returns the first error of the writer.
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        self.flush();
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            let buf = core::mem::take(&mut self.buf);
+            self.write(&buf);
+            self.buf = buf;
+            self.buf.clear();
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) {
+        if self.error.is_none() {
+            if let Err(error) = self.writer.write_all(buf) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Makes room for `len` bytes, and returns whether they should bypass the buffer.
+    fn reserve(&mut self, len: usize) -> bool {
+        if len > self.buf.capacity() - self.buf.len() {
+            self.flush();
+        }
+        len >= BUFFER_SIZE
+    }
+}
+
+unsafe impl<W: Write> BufMut for WriteBuf<'_, W> {
+    fn remaining_mut(&self) -> usize {
+        usize::MAX
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        self.buf.advance_mut(cnt)
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        if self.buf.len() == self.buf.capacity() {
+            self.flush();
+        }
+        self.buf.chunk_mut()
+    }
+
+    fn put<T: Buf>(&mut self, mut src: T)
+    where
+        Self: Sized,
+    {
+        let direct = self.reserve(src.remaining());
+        while src.has_remaining() {
+            let chunk = src.chunk();
+            let len = chunk.len();
+            if direct {
+                self.write(chunk);
+            } else {
+                self.buf.extend_from_slice(chunk);
+            }
+            src.advance(len);
+        }
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        if self.reserve(src.len()) {
+            self.write(src);
+        } else {
+            self.buf.extend_from_slice(src);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::Message;
+
+    /// A writer which records the location of every write.
+    #[derive(Default)]
+    struct Recorder {
+        writes: Vec<(*const u8, usize)>,
+        output: Vec<u8>,
+        fail_after: Option<usize>,
+    }
+
+    impl std::io::Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.fail_after.is_some_and(|n| self.output.len() >= n) {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            self.writes.push((buf.as_ptr(), buf.len()));
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_large_bytes_directly() {
+        let value = Bytes::from(vec![7u8; 100_000]);
+        let mut writer = Recorder::default();
+        value.encode_to_writer(&mut writer).unwrap();
+
+        assert_eq!(writer.output, value.encode_to_vec());
+        assert_eq!(writer.writes.len(), 2);
+        assert_eq!(writer.writes[1], (value.as_ptr(), value.len()));
+    }
+
+    #[test]
+    fn writes_length_delimited() {
+        let value = String::from("foo").repeat(10_000);
+        let mut writer = Recorder::default();
+        value
+            .encode_length_delimited_to_writer(&mut writer)
+            .unwrap();
+        assert_eq!(writer.output, value.encode_length_delimited_to_vec());
+
+        let mut writer = Recorder::default();
+        String::new()
+            .encode_length_delimited_to_writer(&mut writer)
+            .unwrap();
+        assert_eq!(writer.output, [0]);
+    }
+
+    #[test]
+    fn propagates_errors() {
+        let value = Bytes::from(vec![7u8; 100_000]);
+        let mut writer = Recorder {
+            fail_after: Some(0),
+            ..Recorder::default()
+        };
+        let error = value.encode_to_writer(&mut writer).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe);
+        assert!(writer.output.is_empty());
+    }
+}
@@ -9,19 +9,42 @@ pub extern crate alloc;
 // Re-export the bytes crate for use within derived code.
 pub use bytes;
 
+// Re-export the map crates for use within derived code.
+#[cfg(feature = "hashbrown")]
+pub use hashbrown;
+#[cfg(feature = "heapless")]
+pub use heapless;
+#[cfg(feature = "indexmap")]
+pub use indexmap;
+
+mod chain;
 mod error;
+mod field_info;
+#[cfg(feature = "std")]
+mod io;
+mod max_encoded_len;
 mod message;
 mod name;
 mod types;
 
+pub mod diff;
 #[doc(hidden)]
 pub mod encoding;
+pub mod incremental;
+pub mod meta;
+pub mod streaming;
 
+pub use crate::diff::diff;
 pub use crate::encoding::length_delimiter::{
     decode_length_delimiter, encode_length_delimiter, length_delimiter_len,
 };
-pub use crate::error::{DecodeError, EncodeError, UnknownEnumValue};
+pub use crate::error::{
+    DecodeError, DispatchError, EncodeError, MissingRequiredField, UnknownEnumValue,
+};
+pub use crate::field_info::FieldInfo;
+pub use crate::max_encoded_len::MaxEncodedLen;
 pub use crate::message::Message;
+pub use crate::meta::{MessageMeta, OneofMeta};
 pub use crate::name::Name;
 
 // See `encoding::DecodeContext` for more info.
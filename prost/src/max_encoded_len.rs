@@ -0,0 +1,63 @@
+//! Support for a compile-time upper bound on the encoded length of a message.
+
+/// A [`Message`](crate::Message) type with a compile-time upper bound on its encoded length.
+///
+/// `#[derive(Message)]` implements this trait for messages with the `#[prost(max_encoded_len)]`
+/// attribute, and requires the message types of their inline fields to implement it as well.
+/// `prost-build` adds the attribute to the messages selected with `Config::max_encoded_len`. The
+/// length is bounded if all fields of the message are bounded, which is the case for:
+///
+///  * numeric, `bool` and enumeration fields,
+///  * `string` and `bytes` fields of a fixed-capacity type, such as `heapless::String<N>`,
+///  * `repeated` fields of a fixed-capacity type, such as `heapless::Vec<T, N>`, whose elements
+///    are bounded,
+///  * message and group fields of a bounded message type, which are stored inline and not in a
+///    `Box` or `Arc`,
+///  * `oneof` fields whose variants are all bounded.
+///
+/// `map` fields are always unbounded.
+///
+/// `#[derive(Oneof)]` implements this trait for oneofs with the attribute as well, with the maximum
+/// encoded length of any variant, including its key.
+///
+/// # Examples
+///
+/// A bounded message can be encoded into a buffer on the stack, which is always large enough:
+///
+/// ```rust
+/// # use prost::{Message, MaxEncodedLen};
+/// #[derive(Clone, PartialEq, Message)]
+/// #[prost(max_encoded_len)]
+/// struct Reading {
+///     #[prost(uint32, tag = "1")]
+///     sensor: u32,
+///     #[prost(double, repeated, tag = "2")]
+///     values: Vec<f64>,
+/// }
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// #[prost(max_encoded_len)]
+/// struct Sample {
+///     #[prost(uint32, tag = "1")]
+///     sensor: u32,
+///     #[prost(sint64, optional, tag = "2")]
+///     value: Option<i64>,
+/// }
+///
+/// assert_eq!(Reading::MAX_ENCODED_LEN, None);
+///
+/// const LEN: usize = match Sample::MAX_ENCODED_LEN {
+///     Some(len) => len,
+///     None => panic!("unbounded message"),
+/// };
+/// let mut buf = [0u8; LEN];
+/// let sample = Sample { sensor: u32::MAX, value: Some(i64::MIN) };
+/// sample.encode(&mut &mut buf[..]).unwrap();
+/// ```
+pub trait MaxEncodedLen {
+    /// The maximum encoded length of the message in bytes, or `None` if the length is unbounded.
+    ///
+    /// This does not include the length delimiter of
+    /// [`encode_length_delimited`](crate::Message::encode_length_delimited).
+    const MAX_ENCODED_LEN: Option<usize>;
+}
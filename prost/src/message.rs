@@ -1,15 +1,17 @@
 #[cfg(not(feature = "std"))]
 use alloc::boxed::Box;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
 use core::fmt::Debug;
 
-use bytes::{Buf, BufMut};
+use bytes::{Buf, BufMut, Bytes};
 
 use crate::encoding::varint::{encode_varint, encoded_len_varint};
 use crate::encoding::wire_type::WireType;
-use crate::encoding::{decode_key, message, DecodeContext};
+use crate::encoding::{decode_key, message, skip_field, DecodeContext};
 use crate::DecodeError;
 use crate::EncodeError;
 
@@ -101,6 +103,52 @@ pub trait Message: Debug + Send + Sync {
         buf
     }
 
+    /// Encodes the message to a list of buffers.
+    ///
+    /// The contents of large `Bytes` fields are referenced by the buffers instead of being
+    /// copied. The buffers can be written with vectored I/O, or combined into a single
+    /// [`Buf`] with [`Buf::chain`].
+    fn encode_to_chain(&self) -> Vec<Bytes>
+    where
+        Self: Sized,
+    {
+        let mut buf = crate::chain::ChainBuf::new();
+        self.encode_raw(&mut buf);
+        buf.finish()
+    }
+
+    /// Encodes the message to a writer.
+    ///
+    /// The message is written through a bounded intermediate buffer, and large values such as
+    /// `Bytes` fields are written to the writer directly, without being copied into it. Once the
+    /// writer fails, the remainder of the message is discarded and the error is returned.
+    #[cfg(feature = "std")]
+    fn encode_to_writer(&self, writer: &mut impl std::io::Write) -> std::io::Result<()>
+    where
+        Self: Sized,
+    {
+        let mut buf = crate::io::WriteBuf::new(writer);
+        self.encode_raw(&mut buf);
+        buf.finish()
+    }
+
+    /// Encodes the message with a length-delimiter to a writer.
+    ///
+    /// See [`encode_to_writer`][Self::encode_to_writer] for details.
+    #[cfg(feature = "std")]
+    fn encode_length_delimited_to_writer(
+        &self,
+        writer: &mut impl std::io::Write,
+    ) -> std::io::Result<()>
+    where
+        Self: Sized,
+    {
+        let mut buf = crate::io::WriteBuf::new(writer);
+        encode_varint(self.encoded_len() as u64, &mut buf);
+        self.encode_raw(&mut buf);
+        buf.finish()
+    }
+
     /// Decodes an instance of the message from a buffer.
     ///
     /// The entire buffer will be consumed.
@@ -151,6 +199,41 @@ pub trait Message: Debug + Send + Sync {
         )
     }
 
+    /// Merges `other` into `self`, with the same result as encoding `other` and merging it into
+    /// `self`.
+    ///
+    /// Scalar fields set in `other` overwrite the fields of `self`, repeated fields are appended,
+    /// map entries are inserted, and message and oneof fields are merged recursively.
+    ///
+    /// Repeated fields of a fixed-capacity type, such as `heapless::Vec<T, N>`, are appended to
+    /// until they are full, and the remaining values of `other` are dropped.
+    ///
+    /// The default implementation encodes `other` and merges it field by field, without a
+    /// recursion limit. A field of `other` which fails to merge, for example because it exceeds
+    /// the capacity of a fixed-capacity field, is skipped. `#[derive(Message)]` generates an
+    /// implementation which merges the fields directly, and hand-written implementations should
+    /// override this method where possible.
+    fn merge_from(&mut self, other: &Self)
+    where
+        Self: Sized,
+    {
+        let encoded = other.encode_to_vec();
+        let mut buf = encoded.as_slice();
+        let ctx = DecodeContext::unlimited();
+        while buf.has_remaining() {
+            let Ok((tag, wire_type)) = decode_key(&mut buf) else {
+                break;
+            };
+            let mut rest = buf;
+            if skip_field(wire_type, tag, &mut rest, ctx.clone()).is_err() {
+                break;
+            }
+            let mut field = &buf[..buf.len() - rest.len()];
+            let _ = self.merge_field(tag, wire_type, &mut field, ctx.clone());
+            buf = rest;
+        }
+    }
+
     /// Clears the message, resetting all fields to their default.
     fn clear(&mut self);
 }
@@ -174,14 +257,121 @@ where
     fn encoded_len(&self) -> usize {
         (**self).encoded_len()
     }
+    fn merge_from(&mut self, other: &Self) {
+        (**self).merge_from(other)
+    }
     fn clear(&mut self) {
         (**self).clear()
     }
 }
 
+/// Shared message fields use copy-on-write semantics: merging into a field whose value is also
+/// referenced elsewhere clones the value first, leaving the other references untouched.
+#[cfg(target_has_atomic = "ptr")]
+impl<M> Message for Arc<M>
+where
+    M: Message + Clone + Default,
+{
+    fn encode_raw(&self, buf: &mut impl BufMut) {
+        (**self).encode_raw(buf)
+    }
+    fn merge_field(
+        &mut self,
+        tag: u32,
+        wire_type: WireType,
+        buf: &mut impl Buf,
+        ctx: DecodeContext,
+    ) -> Result<(), DecodeError> {
+        Arc::make_mut(self).merge_field(tag, wire_type, buf, ctx)
+    }
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+    fn merge_from(&mut self, other: &Self) {
+        Arc::make_mut(self).merge_from(other)
+    }
+    fn clear(&mut self) {
+        match Arc::get_mut(self) {
+            Some(message) => message.clear(),
+            None => *self = Arc::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     const _MESSAGE_IS_OBJECT_SAFE: Option<&dyn Message> = None;
+
+    #[cfg(target_has_atomic = "ptr")]
+    #[test]
+    fn arc_merge_is_copy_on_write() {
+        use alloc::string::String;
+
+        let shared = Arc::new(String::from("foo"));
+        let mut field = Arc::clone(&shared);
+        field.merge(&b"\x0a\x03bar"[..]).unwrap();
+
+        assert_eq!(*shared, "foo");
+        assert_eq!(*field, "bar");
+
+        field.clear();
+        assert_eq!(*field, "");
+        assert_eq!(*shared, "foo");
+    }
+
+    /// A message whose `values` field holds at most two values.
+    #[derive(Debug, Default, PartialEq)]
+    struct Limited {
+        values: Vec<u32>,
+        name: alloc::string::String,
+    }
+
+    impl Message for Limited {
+        fn encode_raw(&self, buf: &mut impl BufMut) {
+            crate::encoding::uint32::encode_repeated(1, &self.values, buf);
+            crate::encoding::string::encode(2, &self.name, buf);
+        }
+
+        fn merge_field(
+            &mut self,
+            tag: u32,
+            wire_type: WireType,
+            buf: &mut impl Buf,
+            ctx: DecodeContext,
+        ) -> Result<(), DecodeError> {
+            match tag {
+                1 if self.values.len() == 2 => Err(DecodeError::new("field capacity exceeded")),
+                1 => crate::encoding::uint32::merge_repeated(wire_type, &mut self.values, buf, ctx),
+                2 => crate::encoding::string::merge(wire_type, &mut self.name, buf, ctx),
+                _ => skip_field(wire_type, tag, buf, ctx),
+            }
+        }
+
+        fn encoded_len(&self) -> usize {
+            crate::encoding::uint32::encoded_len_repeated(1, &self.values)
+                + crate::encoding::string::encoded_len(2, &self.name)
+        }
+
+        fn clear(&mut self) {
+            *self = Limited::default();
+        }
+    }
+
+    #[test]
+    fn default_merge_from_skips_fields_which_fail_to_merge() {
+        let mut merged = Limited::default();
+        merged.merge_from(&Limited {
+            values: alloc::vec![1, 2, 3],
+            name: "foo".into(),
+        });
+        assert_eq!(
+            merged,
+            Limited {
+                values: alloc::vec![1, 2],
+                name: "foo".into(),
+            }
+        );
+    }
 }
@@ -0,0 +1,215 @@
+//! Static descriptions of derived messages.
+//!
+//! `#[derive(Message)]` implements [`MessageMeta`] and `#[derive(Oneof)]` implements
+//! [`OneofMeta`] for types annotated with `#[prost(meta)]`, which describe the fields of the type
+//! without requiring `protoc` or a descriptor set at runtime. The nested messages and oneofs of
+//! such a type must implement the traits as well. `prost-build` adds the annotation to the
+//! messages selected with `Config::message_meta`.
+//!
+//! ```rust
+//! use prost::{Message, MessageMeta};
+//!
+//! #[derive(Clone, PartialEq, Message)]
+//! #[prost(meta)]
+//! struct Person {
+//!     #[prost(string, tag = "1")]
+//!     name: String,
+//! }
+//!
+//! assert_eq!(Person::DESCRIPTOR.fields[0].name, "name");
+//! ```
+
+use core::fmt;
+
+/// A message type with a static description of its fields.
+pub trait MessageMeta {
+    /// The description of the message.
+    const DESCRIPTOR: &'static MessageDescriptor;
+}
+
+/// A oneof type with a static description of its variants.
+pub trait OneofMeta {
+    /// The fields of the oneof, one for each variant.
+    const FIELDS: &'static [FieldDescriptor];
+}
+
+/// Describes a message type.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MessageDescriptor {
+    /// The name of the message, e.g. `FooBar`.
+    pub name: &'static str,
+    /// The fields of the message which are not part of a oneof, in declaration order.
+    pub fields: &'static [FieldDescriptor],
+    /// The oneofs of the message, in declaration order.
+    pub oneofs: &'static [OneofDescriptor],
+}
+
+impl MessageDescriptor {
+    /// Returns the field with the given tag, including fields which are part of a oneof.
+    pub fn field_by_tag(&self, tag: u32) -> Option<&'static FieldDescriptor> {
+        self.fields
+            .iter()
+            .chain(self.oneofs.iter().flat_map(|oneof| oneof.fields))
+            .find(|field| field.tag == tag)
+    }
+
+    /// Returns the field with the given name, including fields which are part of a oneof.
+    pub fn field_by_name(&self, name: &str) -> Option<&'static FieldDescriptor> {
+        self.fields
+            .iter()
+            .chain(self.oneofs.iter().flat_map(|oneof| oneof.fields))
+            .find(|field| field.name == name)
+    }
+}
+
+/// Describes a oneof field of a message.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OneofDescriptor {
+    /// The Protobuf name of the oneof, e.g. `foo_bar`.
+    ///
+    /// This is given by the `name` attribute of the field holding the oneof, and defaults to the
+    /// name of the Rust field.
+    pub name: &'static str,
+    /// The fields of the oneof, one for each variant.
+    pub fields: &'static [FieldDescriptor],
+}
+
+/// Describes a message field, or a oneof variant.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FieldDescriptor {
+    /// The field tag.
+    pub tag: u32,
+    /// The Protobuf name of the field, e.g. `foo_bar`.
+    ///
+    /// This is given by the `name` attribute of the field, e.g. `#[prost(string, tag = "1", name =
+    /// "self")]`, which `prost-build` adds when the name differs from the Rust field. Without the
+    /// attribute, the name of the Rust field is used, and for oneof variants, the name of the
+    /// variant converted to snake case.
+    pub name: &'static str,
+    /// The Protobuf type of the field.
+    pub ty: FieldType,
+    /// The cardinality of the field.
+    pub cardinality: Cardinality,
+    /// Whether a repeated scalar field uses the packed encoding.
+    pub packed: bool,
+}
+
+/// The Protobuf type of a field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+    /// An enumeration field, encoded as `int32`.
+    Enum(TypeName),
+    /// A length delimited message field.
+    Message(MessageType),
+    /// A group field.
+    Group(MessageType),
+    /// A map field. The key is always a scalar type.
+    Map {
+        key: &'static FieldType,
+        value: &'static FieldType,
+    },
+}
+
+/// The cardinality of a field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Cardinality {
+    /// A field without presence tracking, such as a plain proto3 scalar field.
+    Singular,
+    /// A field with presence tracking, including oneof variants.
+    Optional,
+    /// A proto2 required field.
+    Required,
+    /// A repeated or map field.
+    Repeated,
+}
+
+/// The type of a nested message.
+#[derive(Copy, Clone)]
+pub struct MessageType {
+    name: TypeName,
+    descriptor: fn() -> &'static MessageDescriptor,
+}
+
+impl MessageType {
+    /// Creates the message type of `T`.
+    pub const fn of<T: MessageMeta + ?Sized>() -> MessageType {
+        MessageType {
+            name: TypeName::of::<T>(),
+            descriptor: descriptor::<T>,
+        }
+    }
+
+    /// Returns the name of the Rust type, as returned by [`core::any::type_name`].
+    pub fn name(&self) -> &'static str {
+        self.name.name()
+    }
+
+    /// Returns the description of the message.
+    pub fn descriptor(&self) -> &'static MessageDescriptor {
+        (self.descriptor)()
+    }
+}
+
+// The descriptor is resolved lazily, since a message may contain itself.
+fn descriptor<T: MessageMeta + ?Sized>() -> &'static MessageDescriptor {
+    T::DESCRIPTOR
+}
+
+impl fmt::Debug for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MessageType").field(&self.name()).finish()
+    }
+}
+
+impl PartialEq for MessageType {
+    fn eq(&self, other: &MessageType) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for MessageType {}
+
+/// The name of the Rust type of a nested enumeration.
+#[derive(Copy, Clone)]
+pub struct TypeName(fn() -> &'static str);
+
+impl TypeName {
+    /// Creates the type name of `T`.
+    pub const fn of<T: ?Sized>() -> TypeName {
+        TypeName(core::any::type_name::<T>)
+    }
+
+    /// Returns the name of the type, as returned by [`core::any::type_name`].
+    pub fn name(&self) -> &'static str {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypeName").field(&self.name()).finish()
+    }
+}
+
+impl PartialEq for TypeName {
+    fn eq(&self, other: &TypeName) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for TypeName {}
@@ -0,0 +1,390 @@
+//! Decoding of messages with huge repeated fields, one element at a time.
+//!
+//! [`StreamingDecoder`] decodes a message like [`Message::decode`], except that the elements of
+//! the registered repeated message fields are handed to a callback one at a time instead of being
+//! collected into the message. Memory usage is bounded by the size of the largest element, rather
+//! than by the size of the repeated field.
+//!
+//! The encoded message itself is read from a [`Buf`], so it must already be available in full,
+//! for example in memory or as a memory mapped file. To decode a message which arrives in chunks,
+//! use [`IncrementalDecoder`](crate::incremental::IncrementalDecoder) instead.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use ::bytes::{Buf, BufMut, Bytes};
+
+use crate::encoding::{
+    decode_key, decode_varint, encode_key, encode_varint, DecodeContext, WireType,
+};
+use crate::meta::{Cardinality, FieldType, MessageMeta, MessageType};
+use crate::{DecodeError, Message};
+
+/// A callback for the elements of a registered field, which decodes an element with the context
+/// of its nesting level.
+type Handler<'a> = Box<dyn FnMut(Bytes, DecodeContext) -> Result<(), DecodeError> + 'a>;
+
+/// A decoder which hands the elements of repeated message fields to callbacks.
+///
+/// # Example
+///
+/// ```rust
+/// # use prost::Message;
+/// # use prost::streaming::StreamingDecoder;
+/// #[derive(Clone, PartialEq, Message)]
+/// #[prost(meta)]
+/// struct Dump {
+///     #[prost(string, tag = "1")]
+///     name: String,
+///     #[prost(message, repeated, tag = "2")]
+///     records: Vec<Record>,
+/// }
+///
+/// #[derive(Clone, PartialEq, Message)]
+/// #[prost(meta)]
+/// struct Record {
+///     #[prost(uint64, repeated, tag = "1")]
+///     values: Vec<u64>,
+/// }
+///
+/// # let buf = Dump {
+/// #     name: "dump".to_string(),
+/// #     records: vec![Record { values: vec![1, 2] }, Record { values: vec![3] }],
+/// # }
+/// # .encode_to_vec();
+/// let mut count = 0;
+/// let dump: Dump = StreamingDecoder::new()
+///     .on_element("records", |record: Record| count += record.values.len())?
+///     .decode(buf.as_slice())?;
+///
+/// assert_eq!(dump.name, "dump");
+/// assert!(dump.records.is_empty());
+/// assert_eq!(count, 3);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct StreamingDecoder<'a, M> {
+    paths: PathTree,
+    handlers: Vec<Handler<'a>>,
+    message: PhantomData<fn() -> M>,
+}
+
+impl<'a, M> StreamingDecoder<'a, M>
+where
+    M: Message + MessageMeta + Default,
+{
+    /// Creates a decoder without any callbacks, which decodes messages like
+    /// [`Message::decode`].
+    pub fn new() -> Self {
+        StreamingDecoder {
+            paths: PathTree::default(),
+            handlers: Vec::new(),
+            message: PhantomData,
+        }
+    }
+
+    /// Registers a callback for the elements of the repeated message field at `path`.
+    ///
+    /// The path is a dot separated list of field names, e.g. `records` or `dump.records`, where
+    /// every field but the last is a singular message field. The elements of the field are
+    /// decoded and passed to `handler` in order, and are not added to the decoded message.
+    ///
+    /// Returns an error, and leaves the decoder unchanged, if the path does not name a repeated
+    /// field of message type `E`, or if a callback is already registered for the path.
+    pub fn on_element<E, F>(&mut self, path: &str, mut handler: F) -> Result<&mut Self, InvalidPath>
+    where
+        E: Message + MessageMeta + Default,
+        F: FnMut(E) + 'a,
+    {
+        let tags = resolve::<M, E>(path)?;
+        let (&tag, parents) = tags
+            .split_last()
+            .expect("paths have at least one component");
+        let mut node = &mut self.paths;
+        for parent in parents {
+            node = match node
+                .fields
+                .entry(*parent)
+                .or_insert_with(|| Target::Message(PathTree::default()))
+            {
+                Target::Message(node) => node,
+                Target::Handler(_) => unreachable!("a handler is registered for a message field"),
+            };
+        }
+        if node.fields.contains_key(&tag) {
+            return Err(InvalidPath::new(path, "a callback is already registered"));
+        }
+        node.fields
+            .insert(tag, Target::Handler(self.handlers.len()));
+        self.handlers
+            .push(Box::new(move |mut buf: Bytes, ctx: DecodeContext| {
+                let mut element = E::default();
+                while buf.has_remaining() {
+                    let (tag, wire_type) = decode_key(&mut buf)?;
+                    element.merge_field(tag, wire_type, &mut buf, ctx.clone())?;
+                }
+                handler(element);
+                Ok(())
+            }));
+        Ok(self)
+    }
+
+    /// Decodes an instance of the message from a buffer, passing the elements of the registered
+    /// fields to their callbacks.
+    ///
+    /// The entire buffer will be consumed.
+    pub fn decode(&mut self, buf: impl Buf) -> Result<M, DecodeError> {
+        let mut message = M::default();
+        self.merge(&mut message, buf).map(|_| message)
+    }
+
+    /// Decodes an instance of the message from a buffer and merges it into `message`, passing
+    /// the elements of the registered fields to their callbacks.
+    ///
+    /// The entire buffer will be consumed.
+    pub fn merge(&mut self, message: &mut M, mut buf: impl Buf) -> Result<(), DecodeError> {
+        let StreamingDecoder {
+            paths, handlers, ..
+        } = self;
+        let ctx = DecodeContext::default();
+        while buf.has_remaining() {
+            let (tag, wire_type) = decode_key(&mut buf)?;
+            match paths.fields.get(&tag) {
+                Some(target) if wire_type == WireType::LengthDelimited => {
+                    let len = decode_len(&mut buf)?;
+                    match target {
+                        Target::Handler(index) => {
+                            (handlers[*index])(buf.copy_to_bytes(len), ctx.enter_recursion())?
+                        }
+                        Target::Message(paths) => {
+                            // Merge the remaining fields of the message.
+                            let mut value = Vec::new();
+                            filter(
+                                paths,
+                                handlers,
+                                &mut buf,
+                                len,
+                                &mut value,
+                                ctx.enter_recursion(),
+                            )?;
+                            let mut field = Vec::with_capacity(value.len() + 10);
+                            encode_varint(value.len() as u64, &mut field);
+                            field.extend_from_slice(&value);
+                            message.merge_field(
+                                tag,
+                                wire_type,
+                                &mut field.as_slice(),
+                                ctx.clone(),
+                            )?;
+                        }
+                    }
+                }
+                _ => message.merge_field(tag, wire_type, &mut buf, ctx.clone())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<M> Default for StreamingDecoder<'_, M>
+where
+    M: Message + MessageMeta + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M> fmt::Debug for StreamingDecoder<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingDecoder")
+            .field("paths", &self.paths)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An error indicating that a path passed to [`StreamingDecoder::on_element`] does not name a
+/// repeated message field of the expected type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidPath {
+    path: String,
+    reason: String,
+}
+
+impl InvalidPath {
+    fn new(path: &str, reason: impl Into<String>) -> InvalidPath {
+        InvalidPath {
+            path: path.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns the invalid path.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl fmt::Display for InvalidPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path {:?}: {}", self.path, self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidPath {}
+
+/// Resolves a path of field names in `M` to field tags, checking that it ends in a repeated field
+/// of message type `E`.
+fn resolve<M, E>(path: &str) -> Result<Vec<u32>, InvalidPath>
+where
+    M: MessageMeta,
+    E: MessageMeta,
+{
+    let mut descriptor = M::DESCRIPTOR;
+    let mut tags = Vec::new();
+    let mut components = path.split('.').peekable();
+    while let Some(name) = components.next() {
+        let field = descriptor
+            .field_by_name(name)
+            .ok_or_else(|| InvalidPath::new(path, format!("unknown field {:?}", name)))?;
+        let message_type = match field.ty {
+            FieldType::Message(message_type) => message_type,
+            _ => {
+                return Err(InvalidPath::new(
+                    path,
+                    format!("{:?} is not a message field", name),
+                ))
+            }
+        };
+        let repeated = field.cardinality == Cardinality::Repeated;
+        tags.push(field.tag);
+        if components.peek().is_none() {
+            if !repeated || message_type != MessageType::of::<E>() {
+                return Err(InvalidPath::new(
+                    path,
+                    format!(
+                        "{:?} is not a repeated field of {}",
+                        name,
+                        core::any::type_name::<E>()
+                    ),
+                ));
+            }
+        } else if repeated {
+            return Err(InvalidPath::new(
+                path,
+                format!("{:?} is a repeated field", name),
+            ));
+        }
+        descriptor = message_type.descriptor();
+    }
+    Ok(tags)
+}
+
+/// The registered paths, resolved to field tags.
+#[derive(Debug, Default)]
+struct PathTree {
+    fields: BTreeMap<u32, Target>,
+}
+
+#[derive(Debug)]
+enum Target {
+    /// A repeated field, with the index of its handler.
+    Handler(usize),
+    /// A message field containing registered fields.
+    Message(PathTree),
+}
+
+/// Passes the elements of the registered fields of the `len` bytes long encoded message to their
+/// handlers, and copies the remaining fields to `out`.
+fn filter(
+    paths: &PathTree,
+    handlers: &mut [Handler<'_>],
+    buf: &mut impl Buf,
+    len: usize,
+    out: &mut Vec<u8>,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    ctx.limit_reached()?;
+    let limit = buf.remaining() - len;
+    while buf.remaining() > limit {
+        let (tag, wire_type) = decode_key(buf)?;
+        match paths.fields.get(&tag) {
+            Some(target) if wire_type == WireType::LengthDelimited => {
+                let len = decode_len(buf)?;
+                match target {
+                    Target::Handler(index) => {
+                        (handlers[*index])(buf.copy_to_bytes(len), ctx.enter_recursion())?
+                    }
+                    Target::Message(paths) => {
+                        let mut value = Vec::new();
+                        filter(paths, handlers, buf, len, &mut value, ctx.enter_recursion())?;
+                        encode_key(tag, wire_type, out);
+                        encode_varint(value.len() as u64, out);
+                        out.extend_from_slice(&value);
+                    }
+                }
+            }
+            _ => copy_field(tag, wire_type, buf, out, ctx.clone())?,
+        }
+    }
+    if buf.remaining() != limit {
+        return Err(DecodeError::new("delimited length exceeded"));
+    }
+    Ok(())
+}
+
+/// Copies an encoded field to `out`.
+fn copy_field(
+    tag: u32,
+    wire_type: WireType,
+    buf: &mut impl Buf,
+    out: &mut Vec<u8>,
+    ctx: DecodeContext,
+) -> Result<(), DecodeError> {
+    ctx.limit_reached()?;
+    encode_key(tag, wire_type, out);
+    let len = match wire_type {
+        WireType::Varint => {
+            encode_varint(decode_varint(buf)?, out);
+            return Ok(());
+        }
+        WireType::ThirtyTwoBit => 4,
+        WireType::SixtyFourBit => 8,
+        WireType::LengthDelimited => {
+            let len = decode_len(buf)?;
+            encode_varint(len as u64, out);
+            len
+        }
+        WireType::StartGroup => loop {
+            let (inner_tag, inner_wire_type) = decode_key(buf)?;
+            if inner_wire_type == WireType::EndGroup {
+                if inner_tag != tag {
+                    return Err(DecodeError::new("unexpected end group tag"));
+                }
+                encode_key(tag, WireType::EndGroup, out);
+                return Ok(());
+            }
+            copy_field(inner_tag, inner_wire_type, buf, out, ctx.enter_recursion())?;
+        },
+        WireType::EndGroup => return Err(DecodeError::new("unexpected end group tag")),
+    };
+    if len > buf.remaining() {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    out.put((&mut *buf).take(len));
+    Ok(())
+}
+
+/// Decodes a length delimiter, and checks that the buffer contains the delimited value.
+fn decode_len(buf: &mut impl Buf) -> Result<usize, DecodeError> {
+    let len = decode_varint(buf)?;
+    if len > buf.remaining() as u64 {
+        return Err(DecodeError::new("buffer underflow"));
+    }
+    Ok(len as usize)
+}
@@ -13,11 +13,12 @@ use alloc::vec::Vec;
 use ::bytes::{Buf, BufMut, Bytes};
 
 use crate::encoding::wire_type::WireType;
+use crate::meta::{Cardinality, FieldDescriptor, FieldType, MessageDescriptor, MessageMeta};
 use crate::{
     encoding::{
         bool, bytes, double, float, int32, int64, skip_field, string, uint32, uint64, DecodeContext,
     },
-    DecodeError, Message,
+    DecodeError, MaxEncodedLen, Message,
 };
 
 /// `google.protobuf.BoolValue`
@@ -47,6 +48,11 @@ impl Message for bool {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if *other {
+            *self = *other;
+        }
+    }
     fn clear(&mut self) {
         *self = false;
     }
@@ -79,6 +85,11 @@ impl Message for u32 {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if *other != 0 {
+            *self = *other;
+        }
+    }
     fn clear(&mut self) {
         *self = 0;
     }
@@ -111,6 +122,11 @@ impl Message for u64 {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if *other != 0 {
+            *self = *other;
+        }
+    }
     fn clear(&mut self) {
         *self = 0;
     }
@@ -143,6 +159,11 @@ impl Message for i32 {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if *other != 0 {
+            *self = *other;
+        }
+    }
     fn clear(&mut self) {
         *self = 0;
     }
@@ -175,6 +196,11 @@ impl Message for i64 {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if *other != 0 {
+            *self = *other;
+        }
+    }
     fn clear(&mut self) {
         *self = 0;
     }
@@ -207,6 +233,11 @@ impl Message for f32 {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if *other != 0.0 {
+            *self = *other;
+        }
+    }
     fn clear(&mut self) {
         *self = 0.0;
     }
@@ -239,6 +270,11 @@ impl Message for f64 {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if *other != 0.0 {
+            *self = *other;
+        }
+    }
     fn clear(&mut self) {
         *self = 0.0;
     }
@@ -271,6 +307,11 @@ impl Message for String {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if !other.is_empty() {
+            self.clone_from(other);
+        }
+    }
     fn clear(&mut self) {
         self.clear();
     }
@@ -303,6 +344,11 @@ impl Message for Vec<u8> {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if !other.is_empty() {
+            self.clone_from(other);
+        }
+    }
     fn clear(&mut self) {
         self.clear();
     }
@@ -335,6 +381,11 @@ impl Message for Bytes {
             0
         }
     }
+    fn merge_from(&mut self, other: &Self) {
+        if !other.is_empty() {
+            self.clone_from(other);
+        }
+    }
     fn clear(&mut self) {
         self.clear();
     }
@@ -355,5 +406,55 @@ impl Message for () {
     fn encoded_len(&self) -> usize {
         0
     }
+    fn merge_from(&mut self, _other: &Self) {}
     fn clear(&mut self) {}
 }
+
+macro_rules! wrapper_meta {
+    ($ty:ty, $name:literal, $field_type:ident, $max_encoded_len:expr) => {
+        #[doc = concat!("`google.protobuf.", $name, "`")]
+        impl MessageMeta for $ty {
+            const DESCRIPTOR: &'static MessageDescriptor = &MessageDescriptor {
+                name: $name,
+                fields: &[FieldDescriptor {
+                    tag: 1,
+                    name: "value",
+                    ty: FieldType::$field_type,
+                    cardinality: Cardinality::Singular,
+                    packed: false,
+                }],
+                oneofs: &[],
+            };
+        }
+
+        #[doc = concat!("`google.protobuf.", $name, "`")]
+        impl MaxEncodedLen for $ty {
+            const MAX_ENCODED_LEN: Option<usize> = $max_encoded_len;
+        }
+    };
+}
+
+wrapper_meta!(bool, "BoolValue", Bool, Some(2));
+wrapper_meta!(u32, "UInt32Value", Uint32, Some(6));
+wrapper_meta!(u64, "UInt64Value", Uint64, Some(11));
+wrapper_meta!(i32, "Int32Value", Int32, Some(11));
+wrapper_meta!(i64, "Int64Value", Int64, Some(11));
+wrapper_meta!(f32, "FloatValue", Float, Some(5));
+wrapper_meta!(f64, "DoubleValue", Double, Some(9));
+wrapper_meta!(String, "StringValue", String, None);
+wrapper_meta!(Vec<u8>, "BytesValue", Bytes, None);
+wrapper_meta!(Bytes, "BytesValue", Bytes, None);
+
+/// `google.protobuf.Empty`
+impl MessageMeta for () {
+    const DESCRIPTOR: &'static MessageDescriptor = &MessageDescriptor {
+        name: "Empty",
+        fields: &[],
+        oneofs: &[],
+    };
+}
+
+/// `google.protobuf.Empty`
+impl MaxEncodedLen for () {
+    const MAX_ENCODED_LEN: Option<usize> = Some(0);
+}
@@ -13,6 +13,7 @@ fi
 DIR="$( cd "$( dirname "${BASH_SOURCE[0]}" )" >/dev/null 2>&1 && pwd )"
 
 CRATES=( \
+  "prost-derive-impl" \
   "prost-derive" \
   "prost" \
   "prost-types" \
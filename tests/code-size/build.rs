@@ -0,0 +1,22 @@
+use std::fs;
+
+fn main() {
+    if cfg!(feature = "no-messages") {
+        return;
+    }
+
+    // The well known types are a realistic schema, with messages of every field kind.
+    let include = "../src/include";
+    let protos = fs::read_dir("../src/include/google/protobuf")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("proto".as_ref()))
+        .collect::<Vec<_>>();
+
+    let mut config = prost_build::Config::new();
+    config.compile_well_known_types();
+    if cfg!(feature = "table-driven") {
+        config.table_driven(["."]);
+    }
+    config.compile_protos(&protos, &[include]).unwrap();
+}
@@ -0,0 +1,52 @@
+//! Encodes, decodes and merges the well known types with the common buffer types, to compare the
+//! size of binaries with inline and table driven messages:
+//!
+//! ```text
+//! cargo build --profile release-size -p code-size --features no-messages
+//! cargo build --profile release-size -p code-size
+//! cargo build --profile release-size -p code-size --features table-driven
+//! ```
+//!
+//! The `no-messages` build is the baseline, which has no message code.
+
+use std::hint::black_box;
+
+use prost::bytes::{Bytes, BytesMut};
+#[cfg(not(feature = "no-messages"))]
+use prost::Message;
+
+#[cfg(not(feature = "no-messages"))]
+#[allow(clippy::all)]
+mod protobuf {
+    include!(concat!(env!("OUT_DIR"), "/google.protobuf.rs"));
+}
+
+#[cfg(not(feature = "no-messages"))]
+fn roundtrip<M: Message + Default>(message: M) -> M {
+    let mut vec = Vec::with_capacity(message.encoded_len());
+    message.encode(&mut vec).unwrap();
+    let mut bytes = BytesMut::new();
+    message.encode_length_delimited(&mut bytes).unwrap();
+    let mut array = [0u8; 256];
+    let _ = message.encode(&mut &mut array[..]);
+
+    let mut decoded = M::decode(vec.as_slice()).unwrap();
+    decoded.merge(Bytes::from(vec)).unwrap();
+    decoded.merge_from(&M::decode_length_delimited(bytes.freeze()).unwrap());
+    decoded
+}
+
+#[cfg(not(feature = "no-messages"))]
+fn main() {
+    black_box(roundtrip(black_box(protobuf::FileDescriptorSet::default())));
+    black_box(roundtrip(black_box(protobuf::Api::default())));
+    black_box(roundtrip(black_box(protobuf::Type::default())));
+    black_box(roundtrip(black_box(protobuf::Struct::default())));
+}
+
+#[cfg(feature = "no-messages")]
+fn main() {
+    let mut vec = black_box(Vec::new());
+    vec.push(1u8);
+    black_box(Bytes::from(BytesMut::from(vec.as_slice())));
+}
@@ -0,0 +1,95 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::future::Future;
+use core::pin::Pin;
+use core::ptr;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use prost::{DispatchError, Message};
+
+mod async_service {
+    include!(concat!(env!("OUT_DIR"), "/async_service.rs"));
+}
+
+use async_service::{Greeter, Reply, Request, GREETER_METHODS, GREETER_SERVICE_NAME};
+
+struct MyGreeter;
+
+impl Greeter for MyGreeter {
+    type Error = String;
+
+    fn say_hello(
+        &self,
+        request: Request,
+    ) -> Pin<Box<dyn Future<Output = Result<Reply, String>> + Send + '_>> {
+        Box::pin(async move {
+            Ok(Reply {
+                message: format!("Hello {}!", request.name),
+            })
+        })
+    }
+
+    fn fail(&self, _: Request) -> Pin<Box<dyn Future<Output = Result<Reply, String>> + Send + '_>> {
+        Box::pin(async { Err("failed".to_string()) })
+    }
+}
+
+/// Polls a future to completion, which must not wait for a wakeup.
+fn block_on<F: Future>(future: F) -> F::Output {
+    fn raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+
+    let waker = unsafe { Waker::from_raw(raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    match future.as_mut().poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future is pending"),
+    }
+}
+
+#[test]
+fn dispatches_encoded_requests() {
+    let request = Request {
+        name: "world".to_string(),
+    }
+    .encode_to_vec();
+
+    let response = block_on(MyGreeter.dispatch("SayHello", &request)).unwrap();
+    assert_eq!(
+        Reply::decode(response.as_slice()).unwrap().message,
+        "Hello world!"
+    );
+
+    // The service can be used as a trait object.
+    let service: &dyn Greeter<Error = String> = &MyGreeter;
+    assert_eq!(
+        block_on(service.dispatch("Fail", &request)),
+        Err(DispatchError::Service("failed".to_string()))
+    );
+}
+
+#[test]
+fn rejects_invalid_requests() {
+    assert_eq!(
+        block_on(MyGreeter.dispatch("Stream", &[])),
+        Err(DispatchError::UnknownMethod("Stream".to_string()))
+    );
+    assert!(matches!(
+        block_on(MyGreeter.dispatch("SayHello", &[0x0a, 0x05])),
+        Err(DispatchError::Decode(_))
+    ));
+}
+
+#[test]
+fn names_service_and_methods() {
+    assert_eq!(GREETER_SERVICE_NAME, "async_service.Greeter");
+    assert_eq!(GREETER_METHODS, ["SayHello", "Fail"]);
+}
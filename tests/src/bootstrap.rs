@@ -40,6 +40,7 @@ fn bootstrap() {
     prost_build::Config::new()
         .compile_well_known_types()
         .btree_map(["."])
+        .message_meta(["."])
         .out_dir(tempdir.path())
         .compile_protos(
             &[
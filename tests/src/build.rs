@@ -176,6 +176,22 @@ fn main() {
         .compile_protos(&[src.join("boxed_field.proto")], includes)
         .unwrap();
 
+    prost_build::Config::new()
+        .service_generator(Box::new(prost_build::AsyncServiceGenerator::new()))
+        .compile_protos(&[src.join("async_service.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .max_len("Reading.name", 8)
+        .max_len("Reading.unit", 4)
+        .max_len("Reading.key", 4)
+        .max_len("Reading.tags", 4)
+        .max_len("Reading.source.sensor", 8)
+        .max_count("Reading.values", 4)
+        .max_count("Reading.tags", 2)
+        .compile_protos(&[src.join("heapless_fields.proto")], includes)
+        .unwrap();
+
     // Check that attempting to compile a .proto without a package declaration does not result in an error.
     config
         .compile_protos(&[src.join("no_package.proto")], includes)
@@ -213,6 +229,40 @@ fn main() {
 
     assert!(no_root_packages_with_default.join("_.rs").exists());
 
+    // Compile the same .proto twice, with derived and expanded impls, so that they can be
+    // compared.
+    prost_build::Config::new()
+        .btree_map(["."])
+        .compile_protos(&[src.join("expand_derives.proto")], includes)
+        .unwrap();
+
+    let expanded = out_dir.as_path().join("expanded");
+    fs::create_dir_all(&expanded).expect("failed to create prefix directory");
+    prost_build::Config::new()
+        .out_dir(&expanded)
+        .btree_map(["."])
+        .expand_derives()
+        .compile_protos(&[src.join("expand_derives.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .btree_map(["."])
+        .compile_protos(&[src.join("table_driven.proto")], includes)
+        .unwrap();
+
+    prost_build::Config::new()
+        .file_per_proto()
+        .include_file("file_per_proto_include.rs")
+        .compile_protos(
+            &[
+                src.join("file_per_proto/shop/items.proto"),
+                src.join("file_per_proto/shop/orders.proto"),
+                src.join("file_per_proto/root.proto"),
+            ],
+            &[src.join("file_per_proto")],
+        )
+        .unwrap();
+
     let extern_paths = out_dir.as_path().join("extern_paths");
     fs::create_dir_all(&extern_paths).expect("failed to create prefix directory");
 
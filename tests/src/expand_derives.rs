@@ -0,0 +1,49 @@
+use alloc::format;
+use alloc::vec;
+
+use prost::Message;
+
+mod derived {
+    include!(concat!(env!("OUT_DIR"), "/expand_derives.rs"));
+}
+
+mod expanded {
+    include!(concat!(env!("OUT_DIR"), "/expanded/expand_derives.rs"));
+}
+
+macro_rules! order {
+    ($module:ident) => {
+        $module::Order {
+            id: 7,
+            customer: "alice".into(),
+            note: Some(vec![1, 2, 3]),
+            items: vec![$module::order::Item {
+                sku: "A-1".into(),
+                quantity: -2,
+            }],
+            counts: [("a".into(), 1), ("b".into(), 2)].into_iter().collect(),
+            status: $module::Status::Shipped as i32,
+            payment: Some($module::order::Payment::Transfer(
+                $module::order::Transfer {
+                    iban: "DE00".into(),
+                },
+            )),
+        }
+    };
+}
+
+#[test]
+fn expanded_impls_match_derived_impls() {
+    let derived = order!(derived);
+    let expanded = order!(expanded);
+
+    let buf = expanded.encode_to_vec();
+    assert_eq!(buf, derived.encode_to_vec());
+    assert_eq!(expanded.encoded_len(), derived.encoded_len());
+    assert_eq!(expanded::Order::decode(buf.as_slice()).unwrap(), expanded);
+    assert_eq!(format!("{:?}", expanded), format!("{:?}", derived));
+
+    assert_eq!(expanded.status(), expanded::Status::Shipped);
+    assert_eq!(expanded::Status::try_from(1), Ok(expanded::Status::Shipped));
+    assert_eq!(expanded::Order::default().note(), b"");
+}
@@ -0,0 +1,18 @@
+use alloc::vec;
+
+use crate::check_message;
+
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/file_per_proto_include.rs"));
+}
+
+use generated::file_per_proto::{shop, Root};
+
+#[test]
+fn types_from_separate_files_reference_each_other() {
+    check_message(&Root {
+        order: Some(shop::Order {
+            items: vec![shop::Item { sku: "A-1".into() }],
+        }),
+    });
+}
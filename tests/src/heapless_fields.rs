@@ -0,0 +1,127 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use prost::heapless;
+use prost::Message;
+
+mod heapless_fields {
+    include!(concat!(env!("OUT_DIR"), "/heapless_fields.rs"));
+}
+
+use heapless_fields::{reading, Reading};
+
+fn string<const N: usize>(value: &str) -> heapless::String<N> {
+    heapless::String::try_from(value).unwrap()
+}
+
+fn reading() -> Reading {
+    Reading {
+        name: string("thermo"),
+        unit: Some(string("K")),
+        key: Some(heapless::Vec::from_slice(&[1, 2, 3, 4]).unwrap()),
+        values: heapless::Vec::from_slice(&[-1, 0, 1, i32::MAX]).unwrap(),
+        tags: heapless::Vec::from_slice(&[string("a"), string("bcde")]).unwrap(),
+        source: Some(reading::Source::Sensor(string("s1"))),
+    }
+}
+
+#[test]
+fn roundtrips_heapless_fields() {
+    let message = reading();
+    let buf = message.encode_to_vec();
+    assert_eq!(Reading::decode(buf.as_slice()).unwrap(), message);
+
+    let message = Reading {
+        name: string("x"),
+        ..Reading::default()
+    };
+    let decoded = Reading::decode(message.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, message);
+    assert_eq!(decoded.unit(), "C");
+}
+
+/// A message with the same fields as `Reading`, but without capacity limits.
+#[derive(Clone, PartialEq, Message)]
+struct Unbounded {
+    #[prost(string, required, tag = "1")]
+    name: String,
+    #[prost(bytes = "vec", optional, tag = "3")]
+    key: Option<Vec<u8>>,
+    #[prost(sint32, repeated, packed = "true", tag = "4")]
+    values: Vec<i32>,
+    #[prost(string, repeated, tag = "5")]
+    tags: Vec<String>,
+}
+
+fn unbounded() -> Unbounded {
+    Unbounded {
+        name: "thermo".into(),
+        key: None,
+        values: Vec::new(),
+        tags: Vec::new(),
+    }
+}
+
+#[test]
+fn rejects_values_exceeding_capacity() {
+    for message in [
+        Unbounded {
+            name: "thermometer".into(),
+            ..unbounded()
+        },
+        Unbounded {
+            key: Some(alloc::vec![0; 5]),
+            ..unbounded()
+        },
+        Unbounded {
+            values: alloc::vec![1; 5],
+            ..unbounded()
+        },
+        Unbounded {
+            tags: alloc::vec!["a".into(); 3],
+            ..unbounded()
+        },
+        Unbounded {
+            tags: alloc::vec!["abcde".into()],
+            ..unbounded()
+        },
+    ] {
+        let error = Reading::decode(message.encode_to_vec().as_slice()).unwrap_err();
+        assert!(
+            error.to_string().contains("capacity exceeded"),
+            "unexpected error: {error}"
+        );
+    }
+
+    // Values at the capacity are accepted.
+    let message = Unbounded {
+        name: "thermome".into(),
+        key: Some(alloc::vec![0; 4]),
+        values: alloc::vec![1; 4],
+        tags: alloc::vec!["abcd".into(); 2],
+    };
+    assert!(Reading::decode(message.encode_to_vec().as_slice()).is_ok());
+}
+
+#[test]
+fn merge_from_truncates_at_capacity() {
+    let mut merged = reading();
+    let update = Reading {
+        values: heapless::Vec::from_slice(&[7, 8]).unwrap(),
+        tags: heapless::Vec::from_slice(&[string("f")]).unwrap(),
+        ..Reading::default()
+    };
+    merged.merge_from(&update);
+
+    // Both repeated fields were already full, so the values of `update` are dropped.
+    assert_eq!(merged.values, reading().values);
+    assert_eq!(merged.tags, reading().tags);
+
+    let mut merged = Reading {
+        values: heapless::Vec::from_slice(&[1, 2, 3]).unwrap(),
+        ..Reading::default()
+    };
+    merged.merge_from(&update);
+    assert_eq!(merged.values.as_slice(), &[1, 2, 3, 7]);
+    assert_eq!(merged.tags.as_slice(), &[string::<4>("f")]);
+}
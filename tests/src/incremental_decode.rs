@@ -0,0 +1,207 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::incremental::IncrementalDecoder;
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(meta)]
+struct Outer {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(message, optional, tag = "2")]
+    inner: Option<Inner>,
+    #[prost(message, repeated, tag = "3")]
+    items: Vec<Inner>,
+    #[prost(btree_map = "string, message", tag = "4")]
+    children: BTreeMap<String, Inner>,
+    #[prost(group, optional, tag = "5")]
+    group: Option<Group>,
+    #[prost(group, repeated, tag = "6")]
+    groups: Vec<Group>,
+    #[prost(oneof = "Choice", tags = "7, 8")]
+    choice: Option<Choice>,
+    #[prost(fixed64, tag = "9")]
+    checksum: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(meta)]
+struct Inner {
+    #[prost(sint32, tag = "1")]
+    id: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    data: Vec<u8>,
+    #[prost(float, repeated, tag = "3")]
+    values: Vec<f32>,
+    #[prost(message, optional, boxed, tag = "4")]
+    child: Option<Box<Inner>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(meta)]
+struct Group {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(message, optional, tag = "2")]
+    inner: Option<Inner>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+#[prost(meta)]
+enum Choice {
+    #[prost(string, tag = "7")]
+    Text(String),
+    #[prost(message, tag = "8")]
+    Nested(Inner),
+}
+
+fn inner(id: i32) -> Inner {
+    Inner {
+        id,
+        data: vec![id as u8; 10],
+        values: vec![1.0, -2.5],
+        child: Some(Box::new(Inner {
+            id: -id,
+            ..Inner::default()
+        })),
+    }
+}
+
+fn outer() -> Outer {
+    Outer {
+        name: "outer".to_string(),
+        inner: Some(inner(1)),
+        items: vec![inner(2), Inner::default(), inner(3)],
+        children: [("a".to_string(), inner(4))].into_iter().collect(),
+        group: Some(Group {
+            id: 5,
+            inner: Some(Inner::default()),
+        }),
+        groups: vec![Group {
+            id: 6,
+            inner: Some(inner(6)),
+        }],
+        choice: Some(Choice::Nested(inner(7))),
+        checksum: u64::MAX,
+    }
+}
+
+fn decode_chunks(buf: &[u8], chunk_size: usize) -> Outer {
+    let mut decoder = IncrementalDecoder::<Outer>::new();
+    for chunk in buf.chunks(chunk_size) {
+        decoder.feed(chunk).unwrap();
+        assert_eq!(decoder.next_message(), None);
+    }
+    decoder.finish().unwrap();
+    decoder.next_message().unwrap()
+}
+
+#[test]
+fn decodes_chunks() {
+    let buf = outer().encode_to_vec();
+    for chunk_size in 1..=buf.len() {
+        assert_eq!(decode_chunks(&buf, chunk_size), outer());
+    }
+}
+
+#[test]
+fn decodes_empty_messages() {
+    assert_eq!(decode_chunks(&[], 1), Outer::default());
+
+    let message = Outer {
+        inner: Some(Inner::default()),
+        group: Some(Group::default()),
+        choice: Some(Choice::Nested(Inner::default())),
+        ..Outer::default()
+    };
+    assert_eq!(decode_chunks(&message.encode_to_vec(), 1), message);
+}
+
+#[test]
+fn decodes_length_delimited_messages() {
+    let messages = vec![outer(), Outer::default(), inner_only()];
+    let mut buf = Vec::new();
+    for message in &messages {
+        message.encode_length_delimited(&mut buf).unwrap();
+    }
+
+    for chunk_size in [1, 7, buf.len()] {
+        let mut decoder = IncrementalDecoder::<Outer>::length_delimited();
+        let mut decoded = Vec::new();
+        for chunk in buf.chunks(chunk_size) {
+            decoder.feed(chunk).unwrap();
+            decoded.extend(core::iter::from_fn(|| decoder.next_message()));
+        }
+        decoder.finish().unwrap();
+        assert_eq!(decoded, messages);
+    }
+}
+
+fn inner_only() -> Outer {
+    Outer {
+        inner: Some(inner(8)),
+        ..Outer::default()
+    }
+}
+
+#[test]
+fn bounded_lookahead() {
+    let message = Outer {
+        inner: Some(Inner {
+            values: vec![0.0; 1000],
+            child: Some(Box::new(Inner {
+                id: 1,
+                data: vec![0; 100],
+                ..Inner::default()
+            })),
+            ..Inner::default()
+        }),
+        ..Outer::default()
+    };
+    let buf = message.encode_to_vec();
+
+    // Singular message fields are decoded field by field.
+    let mut decoder = IncrementalDecoder::<Outer>::new();
+    decoder.max_lookahead(4100);
+    decoder.feed(&buf).unwrap();
+    decoder.finish().unwrap();
+    assert_eq!(decoder.next_message(), Some(message.clone()));
+
+    let mut decoder = IncrementalDecoder::<Outer>::new();
+    decoder.max_lookahead(1000);
+    assert!(decoder.feed(&buf).is_err());
+}
+
+#[test]
+fn rejects_truncated_input() {
+    let buf = outer().encode_to_vec();
+    for len in [1, buf.len() / 2, buf.len() - 1] {
+        let mut decoder = IncrementalDecoder::<Outer>::new();
+        decoder.feed(&buf[..len]).unwrap();
+        assert!(decoder.finish().is_err());
+    }
+
+    let buf = outer().encode_length_delimited_to_vec();
+    let mut decoder = IncrementalDecoder::<Outer>::length_delimited();
+    decoder.feed(&buf[..buf.len() - 1]).unwrap();
+    assert!(decoder.finish().is_err());
+}
+
+#[test]
+fn rejects_invalid_input() {
+    // A nested message which is longer than its parent.
+    let mut decoder = IncrementalDecoder::<Outer>::length_delimited();
+    assert!(decoder.feed(&[0x03, 0x12, 0x05, 0x08]).is_err());
+
+    // An unterminated group.
+    let mut decoder = IncrementalDecoder::<Outer>::length_delimited();
+    assert!(decoder.feed(&[0x02, 0x2b, 0x08, 0x01]).is_err());
+
+    // A mismatched end group tag.
+    let mut decoder = IncrementalDecoder::<Outer>::new();
+    assert!(decoder.feed(&[0x2b, 0x34]).is_err());
+}
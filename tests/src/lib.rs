@@ -30,6 +30,8 @@ pub mod no_root_packages;
 pub mod packages;
 pub mod unittest;
 
+#[cfg(test)]
+mod async_service;
 #[cfg(test)]
 mod bootstrap;
 #[cfg(test)]
@@ -41,8 +43,22 @@ mod derive_copy;
 #[cfg(test)]
 mod enum_keyword_variant;
 #[cfg(test)]
+mod expand_derives;
+#[cfg(test)]
+mod file_per_proto;
+#[cfg(test)]
 mod generic_derive;
 #[cfg(test)]
+mod heapless_fields;
+#[cfg(test)]
+mod incremental_decode;
+#[cfg(test)]
+mod max_encoded_len;
+#[cfg(test)]
+mod merge_from;
+#[cfg(test)]
+mod message_diff;
+#[cfg(test)]
 mod message_encoding;
 #[cfg(test)]
 mod no_shadowed_types;
@@ -52,8 +68,12 @@ mod no_unused_results;
 #[cfg(feature = "std")]
 mod skip_debug;
 #[cfg(test)]
+mod streaming_decode;
+#[cfg(test)]
 mod submessage_without_package;
 #[cfg(test)]
+mod table_driven;
+#[cfg(test)]
 mod type_names;
 
 mod test_enum_named_option_value {
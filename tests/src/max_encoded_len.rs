@@ -0,0 +1,155 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use prost::heapless;
+use prost::{MaxEncodedLen, Message, Oneof};
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(max_encoded_len)]
+struct Scalars {
+    #[prost(int32, tag = "1")]
+    int32: i32,
+    #[prost(uint32, optional, tag = "2")]
+    uint32: Option<u32>,
+    #[prost(sint64, required, tag = "3")]
+    sint64: i64,
+    #[prost(fixed32, tag = "4")]
+    fixed32: u32,
+    #[prost(double, tag = "5")]
+    double: f64,
+    #[prost(bool, tag = "6")]
+    bool: bool,
+    #[prost(enumeration = "Level", tag = "20")]
+    level: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, prost::Enumeration)]
+enum Level {
+    Low = 0,
+    High = 1,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(max_encoded_len)]
+struct Bounded {
+    #[prost(message, optional, tag = "1")]
+    scalars: Option<Scalars>,
+    #[prost(string, tag = "2")]
+    name: heapless::String<200>,
+    #[prost(bytes = "vec", optional, tag = "3")]
+    data: Option<heapless::Vec<u8, 3>>,
+    #[prost(sint32, repeated, packed = "true", tag = "4")]
+    packed: heapless::Vec<i32, 3>,
+    #[prost(uint64, repeated, packed = "false", tag = "5")]
+    unpacked: heapless::Vec<u64, 2>,
+    #[prost(string, repeated, tag = "6")]
+    tags: heapless::Vec<heapless::String<2>, 2>,
+    #[prost(message, repeated, tag = "7")]
+    items: heapless::Vec<Scalars, 2>,
+    #[prost(group, optional, tag = "8")]
+    group: Option<Group>,
+    #[prost(oneof = "Choice", tags = "9, 10")]
+    choice: Option<Choice>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(max_encoded_len)]
+struct Group {
+    #[prost(fixed64, tag = "1")]
+    id: u64,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+#[prost(max_encoded_len)]
+enum Choice {
+    #[prost(float, tag = "9")]
+    Value(f32),
+    #[prost(message, tag = "10")]
+    Scalars(Scalars),
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(max_encoded_len)]
+struct Unbounded {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(message, optional, tag = "2")]
+    bounded: Option<Bounded>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(max_encoded_len)]
+struct Recursive {
+    #[prost(message, optional, boxed, tag = "1")]
+    child: Option<Box<Recursive>>,
+    #[prost(message, repeated, tag = "2")]
+    children: Vec<Recursive>,
+    #[prost(oneof = "RecursiveChoice", tags = "3")]
+    choice: Option<RecursiveChoice>,
+    #[prost(btree_map = "int32, int32", tag = "4")]
+    map: BTreeMap<i32, i32>,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+#[prost(max_encoded_len)]
+enum RecursiveChoice {
+    #[prost(message, tag = "3")]
+    Child(Box<Recursive>),
+}
+
+fn scalars() -> Scalars {
+    Scalars {
+        int32: -1,
+        uint32: Some(u32::MAX),
+        sint64: i64::MIN,
+        fixed32: 1,
+        double: 1.0,
+        bool: true,
+        level: -1,
+    }
+}
+
+fn string<const N: usize>(len: usize) -> heapless::String<N> {
+    (0..len).map(|_| 'x').collect()
+}
+
+#[test]
+fn bounds_scalar_fields() {
+    let message = scalars();
+    assert_eq!(Some(message.encoded_len()), Scalars::MAX_ENCODED_LEN);
+    assert_eq!(Scalars::MAX_ENCODED_LEN, Some(11 + 6 + 11 + 5 + 9 + 2 + 12));
+}
+
+#[test]
+fn bounds_nested_fields() {
+    let message = Bounded {
+        scalars: Some(scalars()),
+        name: string(200),
+        data: Some(heapless::Vec::from_slice(&[1, 2, 3]).unwrap()),
+        packed: heapless::Vec::from_slice(&[i32::MIN; 3]).unwrap(),
+        unpacked: heapless::Vec::from_slice(&[u64::MAX; 2]).unwrap(),
+        tags: heapless::Vec::from_slice(&[string(2), string(2)]).unwrap(),
+        items: heapless::Vec::from_slice(&[scalars(), scalars()]).unwrap(),
+        group: Some(Group { id: 1 }),
+        choice: Some(Choice::Scalars(scalars())),
+    };
+    assert_eq!(Some(message.encoded_len()), Bounded::MAX_ENCODED_LEN);
+
+    let mut buf = [0u8; match Bounded::MAX_ENCODED_LEN {
+        Some(len) => len,
+        None => panic!("unbounded"),
+    }];
+    message.encode(&mut &mut buf[..]).unwrap();
+    assert_eq!(Bounded::decode(&buf[..]).unwrap(), message);
+
+    assert_eq!(Choice::MAX_ENCODED_LEN, Some(1 + 1 + 56));
+}
+
+#[test]
+fn unbounded_fields() {
+    assert_eq!(Unbounded::MAX_ENCODED_LEN, None);
+    assert_eq!(Recursive::MAX_ENCODED_LEN, None);
+    assert_eq!(RecursiveChoice::MAX_ENCODED_LEN, None);
+}
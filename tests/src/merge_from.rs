@@ -0,0 +1,151 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+struct Outer {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(int32, optional, tag = "2")]
+    count: Option<i32>,
+    #[prost(message, optional, boxed, tag = "3")]
+    inner: Option<Box<Inner>>,
+    #[prost(message, repeated, tag = "4")]
+    items: Vec<Inner>,
+    #[prost(btree_map = "string, message", tag = "5")]
+    children: BTreeMap<String, Inner>,
+    #[prost(btree_map = "uint32, string", tag = "6")]
+    labels: BTreeMap<u32, String>,
+    #[prost(oneof = "Choice", tags = "7, 8")]
+    choice: Option<Choice>,
+    #[prost(message, optional, tag = "9")]
+    wrapper: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Inner {
+    #[prost(uint32, tag = "1")]
+    id: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    data: Vec<u8>,
+    #[prost(sint64, repeated, tag = "3")]
+    values: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum Choice {
+    #[prost(string, tag = "7")]
+    Text(String),
+    #[prost(message, tag = "8")]
+    Nested(Inner),
+}
+
+fn inner(id: u32, values: &[i64]) -> Inner {
+    Inner {
+        id,
+        data: Vec::new(),
+        values: values.to_vec(),
+    }
+}
+
+/// Checks that `merge_from` gives the same result as encoding and merging.
+fn check_merge_from(base: &Outer, update: &Outer) -> Outer {
+    let mut expected = base.clone();
+    expected.merge(update.encode_to_vec().as_slice()).unwrap();
+
+    let mut merged = base.clone();
+    merged.merge_from(update);
+    assert_eq!(merged, expected);
+    merged
+}
+
+#[test]
+fn merge_from_empty() {
+    let base = Outer {
+        name: "base".to_string(),
+        count: Some(1),
+        inner: Some(Box::new(inner(1, &[1]))),
+        ..Outer::default()
+    };
+    assert_eq!(check_merge_from(&base, &Outer::default()), base);
+    assert_eq!(check_merge_from(&Outer::default(), &base), base);
+}
+
+#[test]
+fn merge_from_semantics() {
+    let base = Outer {
+        name: "base".to_string(),
+        count: Some(1),
+        inner: Some(Box::new(Inner {
+            id: 1,
+            data: b"data".to_vec(),
+            values: vec![1],
+        })),
+        items: vec![inner(1, &[])],
+        children: [
+            ("a".to_string(), inner(1, &[1])),
+            ("b".to_string(), inner(2, &[])),
+        ]
+        .into_iter()
+        .collect(),
+        labels: [(1, "one".to_string())].into_iter().collect(),
+        choice: Some(Choice::Nested(inner(1, &[1]))),
+        wrapper: Some("wrapper".to_string()),
+    };
+    let update = Outer {
+        name: String::new(),
+        count: Some(0),
+        inner: Some(Box::new(inner(2, &[2]))),
+        items: vec![inner(2, &[])],
+        children: [("a".to_string(), inner(3, &[]))].into_iter().collect(),
+        labels: [(1, "uno".to_string()), (2, "two".to_string())]
+            .into_iter()
+            .collect(),
+        choice: Some(Choice::Nested(inner(2, &[2]))),
+        wrapper: Some(String::new()),
+    };
+
+    let merged = check_merge_from(&base, &update);
+    assert_eq!(
+        merged,
+        Outer {
+            name: "base".to_string(),
+            count: Some(0),
+            inner: Some(Box::new(Inner {
+                id: 2,
+                data: b"data".to_vec(),
+                values: vec![1, 2],
+            })),
+            items: vec![inner(1, &[]), inner(2, &[])],
+            children: [
+                ("a".to_string(), inner(3, &[])),
+                ("b".to_string(), inner(2, &[]))
+            ]
+            .into_iter()
+            .collect(),
+            labels: [(1, "uno".to_string()), (2, "two".to_string())]
+                .into_iter()
+                .collect(),
+            choice: Some(Choice::Nested(inner(2, &[1, 2]))),
+            wrapper: Some("wrapper".to_string()),
+        }
+    );
+}
+
+#[test]
+fn merge_from_switches_oneof() {
+    let base = Outer {
+        choice: Some(Choice::Nested(inner(1, &[1]))),
+        ..Outer::default()
+    };
+    let update = Outer {
+        choice: Some(Choice::Text(String::new())),
+        ..Outer::default()
+    };
+    assert_eq!(check_merge_from(&base, &update), update);
+    assert_eq!(check_merge_from(&update, &base), base);
+}
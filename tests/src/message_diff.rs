@@ -0,0 +1,154 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::diff::{DiffOptions, Difference, Value};
+
+#[derive(Clone, PartialEq, prost::Message)]
+#[prost(meta)]
+struct Outer {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(message, optional, tag = "2")]
+    inner: Option<Inner>,
+    #[prost(message, repeated, tag = "3")]
+    items: Vec<Inner>,
+    #[prost(btree_map = "string, message", tag = "4")]
+    labels: BTreeMap<String, Inner>,
+    #[prost(int32, optional, tag = "5")]
+    count: Option<i32>,
+    #[prost(oneof = "Choice", tags = "6, 7")]
+    choice: Option<Choice>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+#[prost(meta)]
+struct Inner {
+    #[prost(uint32, tag = "1")]
+    id: u32,
+    #[prost(double, tag = "2")]
+    score: f64,
+    #[prost(sint64, repeated, tag = "3")]
+    values: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+#[prost(meta)]
+enum Choice {
+    #[prost(string, tag = "6")]
+    Text(String),
+    #[prost(bytes, tag = "7")]
+    RawData(Vec<u8>),
+}
+
+fn inner(id: u32) -> Inner {
+    Inner {
+        id,
+        score: 0.5,
+        values: vec![-1, 2],
+    }
+}
+
+fn outer() -> Outer {
+    Outer {
+        name: "outer".to_string(),
+        inner: Some(inner(1)),
+        items: vec![inner(2), inner(3)],
+        labels: [("a".to_string(), inner(4))].into_iter().collect(),
+        count: Some(0),
+        choice: Some(Choice::Text("text".to_string())),
+    }
+}
+
+fn paths(differences: &[Difference]) -> Vec<String> {
+    differences.iter().map(|d| d.to_string()).collect()
+}
+
+#[test]
+fn equal_messages() {
+    assert_eq!(prost::diff(&outer(), &outer()), vec![]);
+    assert_eq!(prost::diff(&Outer::default(), &Outer::default()), vec![]);
+}
+
+#[test]
+fn nested_differences() {
+    let mut new = outer();
+    new.name = "new".to_string();
+    new.inner.as_mut().unwrap().values.push(3);
+    new.items[1].id = 5;
+    new.items.push(inner(6));
+    new.labels.get_mut("a").unwrap().score = 1.5;
+    new.labels.insert("b".to_string(), Inner::default());
+    new.choice = Some(Choice::RawData(b"raw".to_vec()));
+
+    assert_eq!(
+        paths(&prost::diff(&outer(), &new)),
+        vec![
+            r#"name: "outer" -> "new""#,
+            "inner.values[2]: (unset) -> 3",
+            "items[1].id: 3 -> 5",
+            "items[2]: (unset) -> {..}",
+            r#"labels["a"].score: 0.5 -> 1.5"#,
+            r#"labels["b"]: (unset) -> {..}"#,
+            r#"text: "text" -> (unset)"#,
+            r#"raw_data: (unset) -> b"raw""#,
+        ]
+    );
+    assert_eq!(
+        prost::diff(&outer(), &new)[2],
+        Difference {
+            path: "items[1].id".to_string(),
+            old: Some(Value::U32(3)),
+            new: Some(Value::U32(5)),
+        }
+    );
+}
+
+#[test]
+fn unset_as_default() {
+    let old = Outer {
+        inner: Some(Inner::default()),
+        count: Some(0),
+        ..Outer::default()
+    };
+    let new = Outer {
+        inner: None,
+        count: None,
+        ..Outer::default()
+    };
+    assert_eq!(
+        paths(&prost::diff(&old, &new)),
+        vec!["inner: {..} -> (unset)", "count: 0 -> (unset)"]
+    );
+    assert_eq!(
+        DiffOptions::new().unset_as_default(true).diff(&old, &new),
+        vec![]
+    );
+
+    let new = Outer {
+        inner: None,
+        count: Some(1),
+        ..Outer::default()
+    };
+    assert_eq!(
+        paths(&DiffOptions::new().unset_as_default(true).diff(&old, &new)),
+        vec!["count: 0 -> 1"]
+    );
+}
+
+#[test]
+fn float_tolerance() {
+    let mut new = outer();
+    new.inner.as_mut().unwrap().score += 1e-9;
+    assert_eq!(
+        paths(&prost::diff(&outer(), &new)),
+        vec!["inner.score: 0.5 -> 0.500000001"]
+    );
+    assert_eq!(
+        DiffOptions::new()
+            .float_tolerance(1e-6)
+            .diff(&outer(), &new),
+        vec![]
+    );
+}
@@ -0,0 +1,166 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use prost::streaming::StreamingDecoder;
+use prost::Message;
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(meta)]
+struct Envelope {
+    #[prost(string, tag = "1")]
+    id: String,
+    #[prost(message, optional, tag = "2")]
+    dump: Option<Dump>,
+    #[prost(uint32, tag = "3")]
+    trailer: u32,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(meta)]
+struct Dump {
+    #[prost(message, repeated, tag = "1")]
+    records: Vec<Record>,
+    #[prost(string, tag = "2")]
+    name: String,
+    #[prost(message, repeated, tag = "3")]
+    errors: Vec<Record>,
+    #[prost(group, optional, tag = "4")]
+    group: Option<Record>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[prost(meta)]
+struct Record {
+    #[prost(uint64, tag = "1")]
+    id: u64,
+    #[prost(string, repeated, tag = "2")]
+    values: Vec<String>,
+}
+
+fn record(id: u64) -> Record {
+    Record {
+        id,
+        values: vec![id.to_string()],
+    }
+}
+
+fn envelope() -> Envelope {
+    Envelope {
+        id: "envelope".to_string(),
+        dump: Some(Dump {
+            records: (0..100).map(record).collect(),
+            name: "dump".to_string(),
+            errors: vec![record(1000)],
+            group: Some(record(2000)),
+        }),
+        trailer: 7,
+    }
+}
+
+#[test]
+fn streams_nested_repeated_field() {
+    let buf = envelope().encode_to_vec();
+
+    let mut records = Vec::new();
+    let decoded = StreamingDecoder::<Envelope>::new()
+        .on_element("dump.records", |record: Record| records.push(record.id))
+        .unwrap()
+        .decode(buf.as_slice())
+        .unwrap();
+
+    assert_eq!(records, (0..100).collect::<Vec<_>>());
+
+    let mut expected = envelope();
+    expected.dump.as_mut().unwrap().records.clear();
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+fn streams_multiple_fields() {
+    let buf = envelope().encode_to_vec();
+
+    let (mut records, mut errors) = (0, Vec::new());
+    let decoded = StreamingDecoder::<Envelope>::new()
+        .on_element("dump.records", |_: Record| records += 1)
+        .unwrap()
+        .on_element("dump.errors", |error: Record| errors.push(error))
+        .unwrap()
+        .decode(buf.as_slice())
+        .unwrap();
+
+    assert_eq!(records, 100);
+    assert_eq!(errors, vec![record(1000)]);
+    assert_eq!(decoded.dump.unwrap().name, "dump");
+}
+
+#[test]
+fn decodes_without_callbacks() {
+    let buf = envelope().encode_to_vec();
+    assert_eq!(
+        StreamingDecoder::<Envelope>::new()
+            .decode(buf.as_slice())
+            .unwrap(),
+        envelope()
+    );
+}
+
+/// Returns an envelope whose first record contains an unknown field of `depth` nested groups.
+fn deeply_nested_envelope(depth: usize) -> Vec<u8> {
+    let mut record = record(0).encode_to_vec();
+    record.extend(core::iter::repeat(0x7b).take(depth)); // field 15, start group
+    record.extend(core::iter::repeat(0x7c).take(depth)); // field 15, end group
+    let mut dump = Vec::new();
+    prost::encoding::bytes::encode(1, &record, &mut dump);
+    let mut envelope = Vec::new();
+    prost::encoding::bytes::encode(2, &dump, &mut envelope);
+    envelope
+}
+
+#[test]
+fn enforces_recursion_limit_in_elements() {
+    // The first depth at which the regular decoder reaches the recursion limit.
+    let depth = (1..200)
+        .find(|depth| Envelope::decode(deeply_nested_envelope(*depth).as_slice()).is_err())
+        .unwrap();
+
+    let mut decoder = StreamingDecoder::<Envelope>::new();
+    decoder.on_element("dump.records", |_: Record| {}).unwrap();
+    assert!(decoder
+        .decode(deeply_nested_envelope(depth - 1).as_slice())
+        .is_ok());
+    assert!(decoder
+        .decode(deeply_nested_envelope(depth).as_slice())
+        .is_err());
+}
+
+#[test]
+fn rejects_truncated_input() {
+    let buf = envelope().encode_to_vec();
+    let mut decoder = StreamingDecoder::<Envelope>::new();
+    decoder.on_element("dump.records", |_: Record| {}).unwrap();
+    assert!(decoder.decode(&buf[..buf.len() - 10]).is_err());
+}
+
+#[test]
+fn rejects_invalid_paths() {
+    let mut decoder = StreamingDecoder::<Envelope>::new();
+    let error = decoder.on_element("dump", |_: Dump| {}).unwrap_err();
+    assert_eq!(error.path(), "dump");
+    assert!(error.to_string().contains("is not a repeated field"));
+    assert!(decoder.on_element("dump.missing", |_: Record| {}).is_err());
+    assert!(decoder.on_element("id.records", |_: Record| {}).is_err());
+    assert!(decoder.on_element("dump.records", |_: Dump| {}).is_err());
+
+    decoder.on_element("dump.records", |_: Record| {}).unwrap();
+    let error = decoder
+        .on_element("dump.records", |_: Record| {})
+        .unwrap_err();
+    assert!(error.to_string().contains("already registered"));
+
+    // Failed registrations leave the decoder unchanged.
+    let buf = envelope().encode_to_vec();
+    let mut expected = envelope();
+    expected.dump.as_mut().unwrap().records.clear();
+    assert_eq!(decoder.decode(buf.as_slice()).unwrap(), expected);
+}
@@ -0,0 +1,105 @@
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec;
+
+use prost::encoding::table::TableMessage;
+use prost::Message;
+
+use crate::check_message;
+
+mod table_driven {
+    include!(concat!(env!("OUT_DIR"), "/table_driven.rs"));
+}
+
+use table_driven::{order, Order, Status};
+
+fn order() -> Order {
+    Order {
+        id: 7,
+        customer: "alice".into(),
+        note: Some(vec![1, 2, 3]),
+        items: vec![order::Item {
+            sku: "A-1".into(),
+            quantity: -2,
+        }],
+        counts: [("a".into(), 1), ("b".into(), 2)].into_iter().collect(),
+        status: Status::Shipped as i32,
+        deltas: vec![-1, 0, 1],
+        payment: Some(order::Payment::Transfer(order::Transfer {
+            iban: "DE00".into(),
+        })),
+        replaces: Some(Box::new(Order {
+            id: 6,
+            payment: Some(order::Payment::Card("1234".into())),
+            ..Order::default()
+        })),
+    }
+}
+
+#[test]
+fn messages_are_table_driven() {
+    let table = <Order as TableMessage>::TABLE;
+    assert_eq!(table.name, "Order");
+    // The oneof has a single entry for both of its tags.
+    assert_eq!(table.fields.len(), 9);
+    assert_eq!(table.tags.len(), 10);
+    assert_eq!(<order::Item as TableMessage>::TABLE.fields.len(), 2);
+}
+
+#[test]
+fn roundtrip_table_driven_message() {
+    check_message(&order());
+    check_message(&Order::default());
+}
+
+#[test]
+fn encode_table_driven_message() {
+    let order = Order {
+        id: 150,
+        customer: "bo".into(),
+        deltas: vec![-1, 1],
+        payment: Some(order::Payment::Card("x".into())),
+        ..Order::default()
+    };
+    assert_eq!(
+        order.encode_to_vec(),
+        b"\x08\x96\x01\x12\x02bo\x3a\x02\x01\x02\x42\x01x"
+    );
+}
+
+#[test]
+fn decode_table_driven_message() {
+    // Unknown fields are skipped, and repeated fields are appended.
+    let order = Order::decode(&b"\x3a\x01\x01\xa0\x06\x01\x3a\x01\x02"[..]).unwrap();
+    assert_eq!(order.deltas, [-1, 1]);
+
+    let mut merged = Order {
+        id: 1,
+        customer: "alice".into(),
+        ..Order::default()
+    };
+    merged.merge(&b"\x08\x02"[..]).unwrap();
+    assert_eq!(merged.id, 2);
+    assert_eq!(merged.customer, "alice");
+
+    let error = Order::decode(&b"\x22\x02\x10\x80"[..]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to decode Protobuf message: Item.quantity: Order.items: invalid varint"
+    );
+}
+
+#[test]
+fn merge_from_table_driven_message() {
+    let mut merged = Order {
+        id: 1,
+        customer: "bob".into(),
+        deltas: vec![5],
+        ..Order::default()
+    };
+    merged.merge_from(&order());
+
+    let mut expected = order();
+    expected.deltas.insert(0, 5);
+    assert_eq!(merged, expected);
+}